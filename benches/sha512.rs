@@ -1,11 +1,15 @@
 use ark_std::{end_timer, start_timer};
 use halo2_proofs::circuit::{SimpleFloorPlanner, Layouter, Value};
-use halo2_proofs::plonk::{create_proof, keygen_pk, keygen_vk, verify_proof, Circuit, ConstraintSystem, Error};
-use halo2_proofs::poly::kzg::commitment::{KZGCommitmentScheme, ParamsKZG, ParamsVerifierKZG};
+use halo2_proofs::dev::CircuitCost;
+use halo2_proofs::plonk::{
+    create_proof, verify_proof, Advice, Any, Assigned, Assignment, Challenge, Circuit, Column,
+    ConstraintSystem, Error, Fixed, FloorPlanner, Instance, Selector,
+};
+use halo2_proofs::poly::kzg::commitment::{KZGCommitmentScheme, ParamsVerifierKZG};
 use halo2_proofs::poly::kzg::multiopen::{ProverSHPLONK, VerifierSHPLONK};
 use halo2_proofs::poly::kzg::strategy::SingleStrategy;
 use halo2_proofs::{
-    halo2curves::bn256::{Bn256, Fr, G1Affine},
+    halo2curves::bn256::{Bn256, Fr, G1, G1Affine},
     poly::commitment::ParamsProver,
     transcript::{
         Blake2bRead, Blake2bWrite, Challenge255, TranscriptReadBuffer, TranscriptWriterBuffer,
@@ -16,13 +20,13 @@ use rand_chacha::ChaCha20Rng;
 use rand_xorshift::XorShiftRng;
 
 use criterion::{criterion_group, criterion_main, Criterion};
-use std::{
-    fs::{create_dir_all, File},
-    io::{prelude::*, BufReader},
-    path::Path,
-};
+use std::path::{Path, PathBuf};
 
-use sha512_halo2::sha512::{BlockWord, Sha512, Table16Chip, Table16Config, BLOCK_SIZE};
+use sha512_halo2::sha512::{
+    keys::{CachedCircuit, KeyCache},
+    pbkdf2::Pbkdf2Sha512,
+    BlockWord, ByteInput, Sha512, Table16Chip, Table16Config, BLOCK_SIZE,
+};
 
 pub const SETUP_PREFIX: &str = "[Setup generation]";
 pub const PROOFGEN_PREFIX: &str = "[Proof generation]";
@@ -34,11 +38,21 @@ fn bench() {
 
     use halo2_proofs::arithmetic::Field;
 
+    /// The number of blocks of the message, after which the digest compresses a block
+    /// of padding.
+    const MESSAGE_BLOCKS: usize = 31;
+
     #[derive(Default)]
     struct MyCircuit {}
 
+    impl CachedCircuit for MyCircuit {
+        fn shape(&self) -> String {
+            format!("b{}", MESSAGE_BLOCKS + 1)
+        }
+    }
+
     impl Circuit<Fr> for MyCircuit {
-        type Config = Table16Config;
+        type Config = Table16Config<Fr>;
         type FloorPlanner = SimpleFloorPlanner;
 
         fn without_witnesses(&self) -> Self {
@@ -78,9 +92,9 @@ fn bench() {
                     BlockWord(Value::known(0b0000000000000000000000000000000000000000000000000000000000011000)),
                 ];
 
-            // Create a message of length 31 blocks
-            let mut input = Vec::with_capacity(31 * BLOCK_SIZE);
-            for _ in 0..31 {
+            // Create a message of length MESSAGE_BLOCKS blocks
+            let mut input = Vec::with_capacity(MESSAGE_BLOCKS * BLOCK_SIZE);
+            for _ in 0..MESSAGE_BLOCKS {
                 input.extend_from_slice(&test_input);
             }
 
@@ -89,9 +103,6 @@ fn bench() {
             Ok(())
         }
     }
-    let setup_prfx = SETUP_PREFIX;
-    let proof_gen_prfx = PROOFGEN_PREFIX;
-    let proof_ver_prfx = PROOFVER_PREFIX;
     let degree: u32 = var("DEGREE")
     .unwrap_or_else(|_| "19".to_string())
     .parse()
@@ -100,48 +111,297 @@ fn bench() {
     //Unique string used by bench results module for parsing the result
     const BENCHMARK_ID: &str = "SHA512 Circuit";
 
+    // Create the circuit
+    let circuit: MyCircuit = MyCircuit {};
+
+    prove_and_verify(BENCHMARK_ID, circuit, degree);
+}
+
+#[test]
+fn bench_pbkdf2() {
+    use std::env::var;
+
+    /// The number of iterations with which BIP39 derives a seed from its mnemonic.
+    const BIP39_ITERATIONS: usize = 2048;
+
+    #[derive(Debug, Default)]
+    struct Bip39Circuit {}
+
+    impl CachedCircuit for Bip39Circuit {
+        fn shape(&self) -> String {
+            format!("i{}", BIP39_ITERATIONS)
+        }
+    }
+
+    impl Circuit<Fr> for Bip39Circuit {
+        type Config = (Table16Config<Fr>, Column<Advice>);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+            let mnemonic = meta.advice_column();
+            meta.enable_equality(mnemonic);
+            (Table16Chip::configure(meta), mnemonic)
+        }
+
+        fn synthesize(
+            &self,
+            (config, mnemonic_column): Self::Config,
+            mut layouter: impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            Table16Chip::load(config.clone(), &mut layouter)?;
+            let table16_chip = Table16Chip::construct(config);
+
+            // Test vector: the first BIP39 English vector, with passphrase "TREZOR"
+            let mnemonic = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+            let mnemonic = layouter.assign_region(
+                || "mnemonic",
+                |mut region| {
+                    mnemonic
+                        .bytes()
+                        .enumerate()
+                        .map(|(i, byte)| {
+                            region.assign_advice(
+                                || format!("mnemonic byte {}", i),
+                                mnemonic_column,
+                                i,
+                                || Value::known(Fr::from(byte as u64)),
+                            )
+                        })
+                        .collect::<Result<Vec<_>, _>>()
+                },
+            )?;
+            let salt: Vec<_> = b"mnemonicTREZOR".iter().copied().map(ByteInput::Padding).collect();
+
+            Pbkdf2Sha512::derive_key(
+                table16_chip,
+                layouter.namespace(|| "bip39 seed"),
+                &mnemonic,
+                &salt,
+                BIP39_ITERATIONS,
+                1,
+            )?;
+
+            Ok(())
+        }
+    }
+    //Unique string used by bench results module for parsing the result
+    const BENCHMARK_ID: &str = "PBKDF2-HMAC-SHA512 Circuit";
+
+    // Create the circuit
+    let circuit: Bip39Circuit = Bip39Circuit {};
+
+    // Default to the smallest degree the iterations fit in
+    let (rows, reserved) = used_rows(&circuit);
+    let degree: u32 = var("DEGREE")
+        .map(|degree| degree.parse().expect("Cannot parse DEGREE env var as u32"))
+        .unwrap_or_else(|_| (rows + reserved).next_power_of_two().trailing_zeros());
+    assert!(
+        rows + reserved <= 1 << degree,
+        "{} rows do not fit in degree = {}",
+        rows,
+        degree
+    );
+
+    // Report the rows the iterations take, against the 2^DEGREE available
+    let cost = CircuitCost::<G1, Bip39Circuit>::measure(degree, &circuit);
+    println!(
+        "{} with {} iterations and degree = {} ({} of {} rows used): {:?}",
+        BENCHMARK_ID,
+        BIP39_ITERATIONS,
+        degree,
+        rows,
+        1u64 << degree,
+        cost
+    );
+
+    prove_and_verify(BENCHMARK_ID, circuit, degree);
+}
+
+/// Records the rows a circuit assigns, without computing its witness.
+#[derive(Default)]
+struct RowCounter {
+    rows: usize,
+}
+
+impl RowCounter {
+    fn use_row(&mut self, row: usize) {
+        self.rows = self.rows.max(row + 1);
+    }
+}
+
+impl Assignment<Fr> for RowCounter {
+    fn enter_region<NR, N>(&mut self, _: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+    }
+
+    fn exit_region(&mut self) {}
+
+    fn enable_selector<A, AR>(&mut self, _: A, _: &Selector, row: usize) -> Result<(), Error>
+    where
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        self.use_row(row);
+        Ok(())
+    }
+
+    fn query_instance(&self, _: Column<Instance>, _: usize) -> Result<Value<Fr>, Error> {
+        Ok(Value::unknown())
+    }
+
+    fn assign_advice<V, VR, A, AR>(
+        &mut self,
+        _: A,
+        _: Column<Advice>,
+        row: usize,
+        _: V,
+    ) -> Result<(), Error>
+    where
+        V: FnOnce() -> Value<VR>,
+        VR: Into<Assigned<Fr>>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        self.use_row(row);
+        Ok(())
+    }
+
+    fn assign_fixed<V, VR, A, AR>(
+        &mut self,
+        _: A,
+        _: Column<Fixed>,
+        row: usize,
+        _: V,
+    ) -> Result<(), Error>
+    where
+        V: FnOnce() -> Value<VR>,
+        VR: Into<Assigned<Fr>>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        self.use_row(row);
+        Ok(())
+    }
+
+    fn copy(&mut self, _: Column<Any>, _: usize, _: Column<Any>, _: usize) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn fill_from_row(
+        &mut self,
+        _: Column<Fixed>,
+        row: usize,
+        _: Value<Assigned<Fr>>,
+    ) -> Result<(), Error> {
+        self.use_row(row);
+        Ok(())
+    }
+
+    fn get_challenge(&self, _: Challenge) -> Value<Fr> {
+        Value::unknown()
+    }
+
+    fn push_namespace<NR, N>(&mut self, _: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+    }
+
+    fn pop_namespace(&mut self, _: Option<String>) {}
+}
+
+/// Returns the number of rows the given circuit assigns, and the number of rows the
+/// prover reserves beyond them for blinding.
+fn used_rows<C: Circuit<Fr>>(circuit: &C) -> (usize, usize) {
+    let mut meta = ConstraintSystem::default();
+    let config = C::configure(&mut meta);
+    let mut counter = RowCounter::default();
+    C::FloorPlanner::synthesize(&mut counter, circuit, config, meta.constants().clone())
+        .expect("synthesis should not fail");
+    (counter.rows, meta.blinding_factors() + 1)
+}
+
+/// Benches setup, proof generation and verification of the given circuit.
+fn prove_and_verify<C: CachedCircuit>(benchmark_id: &str, circuit: C, degree: u32) {
+    let setup_prfx = SETUP_PREFIX;
+    let proof_gen_prfx = PROOFGEN_PREFIX;
+    let proof_ver_prfx = PROOFVER_PREFIX;
+
     // Initialize the polynomial commitment parameters
     let mut rng = XorShiftRng::from_seed([
         0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06,
         0xbc, 0xe5,
     ]);
-    // Create the circuit
-    let circuit: MyCircuit = MyCircuit {};
-
 
-    // Bench setup generation
-    let setup_message = format!("{} {} with degree = {}", BENCHMARK_ID, setup_prfx, degree);
-    let start1 = start_timer!(|| setup_message);
-    let general_params = ParamsKZG::<Bn256>::setup(degree as u32, &mut rng);
+    // Bench setup generation, which only sets up the parameters and generates the keys
+    // on the first run; later runs read them from the cache. Each timer says which it
+    // measured
+    let cache = KeyCache::new(Path::new(env!("CARGO_TARGET_TMPDIR")).join("keys"));
+    let source = |path: PathBuf| {
+        if path.exists() {
+            "read from the cache"
+        } else {
+            "generated"
+        }
+    };
+    let params_message = format!(
+        "{} {} parameters {} with degree = {}",
+        benchmark_id,
+        setup_prfx,
+        source(cache.params_path(degree)),
+        degree
+    );
+    let start1 = start_timer!(|| params_message);
+    let general_params = cache
+        .params(degree, &mut rng)
+        .expect("params setup should not fail");
     let verifier_params: ParamsVerifierKZG<Bn256> = general_params.verifier_params().clone();
     end_timer!(start1);
 
     // Initialize the proving key
-    let vk = keygen_vk(&general_params, &circuit).expect("keygen_vk should not fail");
-    let pk = keygen_pk(&general_params, vk, &circuit).expect("keygen_pk should not fail");
+    let keys_message = format!(
+        "{} {} keys {} with degree = {}",
+        benchmark_id,
+        setup_prfx,
+        source(cache.pk_path(degree, &circuit)),
+        degree
+    );
+    let start2 = start_timer!(|| keys_message);
+    let pk = cache
+        .pk(&general_params, &circuit)
+        .expect("keygen should not fail");
+    end_timer!(start2);
     // Create a proof
     let mut transcript = Blake2bWrite::<_, G1Affine, Challenge255<_>>::init(vec![]);
 
     // Bench proof generation time
     let proof_message = format!(
         "{} {} with degree = {}",
-        BENCHMARK_ID, proof_gen_prfx, degree
+        benchmark_id, proof_gen_prfx, degree
     );
-    let start2 = start_timer!(|| proof_message);
+    let start3 = start_timer!(|| proof_message);
     create_proof::<
         KZGCommitmentScheme<Bn256>,
         ProverSHPLONK<'_, Bn256>,
         Challenge255<G1Affine>,
         XorShiftRng,
         Blake2bWrite<Vec<u8>, G1Affine, Challenge255<G1Affine>>,
-        MyCircuit,
+        C,
     >(&general_params, &pk, &[circuit], &[], rng, &mut transcript)
     .expect("proof generation should not fail");
     let proof = transcript.finalize();
-    end_timer!(start2);
+    end_timer!(start3);
 
     // Bench verification time
-    let start3 = start_timer!(|| format!("{} {}", BENCHMARK_ID, proof_ver_prfx));
+    let start4 = start_timer!(|| format!("{} {}", benchmark_id, proof_ver_prfx));
     let mut verifier_transcript = Blake2bRead::<_, G1Affine, Challenge255<_>>::init(&proof[..]);
     let strategy = SingleStrategy::new(&general_params);
 
@@ -159,7 +419,7 @@ fn bench() {
         &mut verifier_transcript,
     )
     .expect("failed to verify bench circuit");
-    end_timer!(start3);
+    end_timer!(start4);
 }
 
  
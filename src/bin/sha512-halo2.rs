@@ -0,0 +1,389 @@
+//! Proves and verifies knowledge of a SHA-512 preimage from the command line, with the
+//! [`Sha512PreimageCircuit`] over BN254, committed to with KZG and opened with SHPLONK.
+//!
+//! The parameters, keys and proofs are read from and written to files, which default
+//! to `params.bin`, `vk.bin`, `pk.bin` and `proof.bin` in the working directory, with
+//! the helpers of [`keys`](sha512_halo2::sha512::keys). Beside each key, keygen writes
+//! the number of blocks and the layout of the circuit it is for, in a file named after
+//! the key with `.shape` appended, which prove and verify read back.
+
+use std::fs;
+use std::path::Path;
+use std::process;
+
+use halo2_proofs::{
+    halo2curves::bn256::{Bn256, Fr, G1Affine},
+    plonk::{create_proof, keygen_pk, keygen_vk, verify_proof},
+    poly::{
+        commitment::{Params, ParamsProver},
+        kzg::{
+            commitment::{KZGCommitmentScheme, ParamsKZG},
+            multiopen::{ProverSHPLONK, VerifierSHPLONK},
+            strategy::SingleStrategy,
+        },
+    },
+    transcript::{
+        Blake2bRead, Blake2bWrite, Challenge255, TranscriptReadBuffer, TranscriptWriterBuffer,
+    },
+};
+use rand::rngs::OsRng;
+
+use sha512_halo2::sha512::{
+    keys::{read_params, read_pk, read_vk, write_params, write_pk, write_vk},
+    preimage::{DigestLayout, PreimageDigest, Sha512PreimageCircuit},
+    Sha512Error, DIGEST_SIZE,
+};
+
+const USAGE: &str = "\
+usage: sha512-halo2 <command> [--flag value]...
+
+commands:
+  setup   --k <k> [--params <file>]
+          Generates parameters for circuits of 2^k rows. The toxic waste is drawn
+          from the OS and discarded, which is only fit for testing; production
+          parameters come from a trusted setup ceremony.
+  keygen  --max-blocks <n> [--layout <layout>] [--params <file>] [--vk <file>]
+          [--pk <file>]
+          Generates the keys of the circuit hashing messages of up to n blocks,
+          and records n and the layout beside each key.
+  prove   --message <file> [--params <file>] [--pk <file>] [--proof <file>]
+          Proves knowledge of the message, printing its digest and length.
+  verify  --digest <hex> --length <bytes> [--params <file>] [--vk <file>]
+          [--proof <file>]
+          Checks a proof of knowledge of a message with the given digest and length.
+
+The layout of the digest in the public inputs is `words` (the default) or
+`half-words`. Prove and verify take the number of blocks and the layout
+recorded beside the key they read.";
+
+/// The flags following a command, each given as `--name value`.
+#[derive(Debug)]
+struct Flags(Vec<(String, String)>);
+
+impl Flags {
+    /// Parses the flags in `args`, rejecting any not in `known`.
+    fn parse(args: &[String], known: &[&str]) -> Result<Self, String> {
+        let mut flags = vec![];
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            let name = arg
+                .strip_prefix("--")
+                .ok_or_else(|| format!("expected a flag, got '{}'", arg))?;
+            if !known.contains(&name) {
+                return Err(format!("unknown flag '--{}'", name));
+            }
+            if flags.iter().any(|(flag, _)| flag == name) {
+                return Err(format!("flag '--{}' given twice", name));
+            }
+            let value = args
+                .next()
+                .ok_or_else(|| format!("flag '--{}' needs a value", name))?;
+            flags.push((name.to_string(), value.clone()));
+        }
+        Ok(Flags(flags))
+    }
+
+    fn get(&self, name: &str) -> Option<&str> {
+        self.0
+            .iter()
+            .find(|(flag, _)| flag == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    fn required(&self, name: &str) -> Result<&str, String> {
+        self.get(name)
+            .ok_or_else(|| format!("missing flag '--{}'", name))
+    }
+
+    /// Returns the path given for the file `name`, or its default `name.bin`.
+    fn path(&self, name: &str) -> String {
+        self.get(name)
+            .map(str::to_string)
+            .unwrap_or_else(|| format!("{}.bin", name))
+    }
+
+    fn number<T: std::str::FromStr>(&self, name: &str) -> Result<T, String> {
+        let value = self.required(name)?;
+        value
+            .parse()
+            .map_err(|_| format!("flag '--{}' expects a number, got '{}'", name, value))
+    }
+
+    fn layout(&self) -> Result<DigestLayout, String> {
+        match self.get("layout") {
+            None => Ok(DigestLayout::Words),
+            Some(layout) => parse_layout(layout),
+        }
+    }
+}
+
+fn parse_layout(layout: &str) -> Result<DigestLayout, String> {
+    DigestLayout::from_name(layout).ok_or_else(|| format!("unknown layout '{}'", layout))
+}
+
+/// Formats the number of blocks and the layout of the circuit a key is for.
+fn format_shape(circuit: &Sha512PreimageCircuit) -> String {
+    format!(
+        "max-blocks {}\nlayout {}\n",
+        circuit.max_blocks(),
+        circuit.layout().name()
+    )
+}
+
+/// Parses the number of blocks and the layout written by [`format_shape`].
+fn parse_shape(shape: &str) -> Result<(usize, DigestLayout), String> {
+    let mut lines = shape.lines().map(|line| line.split_once(' '));
+    match (lines.next(), lines.next(), lines.next()) {
+        (Some(Some(("max-blocks", max_blocks))), Some(Some(("layout", layout))), None) => {
+            let max_blocks = max_blocks
+                .parse()
+                .map_err(|_| format!("expected a number of blocks, got '{}'", max_blocks))?;
+            Ok((max_blocks, parse_layout(layout)?))
+        }
+        _ => Err("expected a line 'max-blocks <n>' and a line 'layout <layout>'".to_string()),
+    }
+}
+
+/// The file recording the shape of the circuit the key at `key` is for.
+fn shape_path(key: &str) -> String {
+    format!("{}.shape", key)
+}
+
+fn write_shape(key: &str, circuit: &Sha512PreimageCircuit) -> Result<(), String> {
+    let path = shape_path(key);
+    fs::write(&path, format_shape(circuit)).map_err(|e| format!("cannot write '{}': {}", path, e))
+}
+
+/// Reads the number of blocks and the layout of the circuit the key at `key` is for.
+fn read_shape(key: &str) -> Result<(usize, DigestLayout), String> {
+    let path = shape_path(key);
+    let shape = fs::read_to_string(&path).map_err(|e| {
+        format!(
+            "cannot read '{}', which keygen writes beside the key '{}': {}",
+            path, key, e
+        )
+    })?;
+    parse_shape(&shape).map_err(|e| format!("'{}': {}", path, e))
+}
+
+/// Parses a digest given as 128 hexadecimal digits.
+fn parse_digest(hex: &str) -> Result<[u64; DIGEST_SIZE], String> {
+    if hex.len() != 16 * DIGEST_SIZE || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(format!(
+            "a digest is {} hexadecimal digits, got '{}'",
+            16 * DIGEST_SIZE,
+            hex
+        ));
+    }
+    let mut digest = [0; DIGEST_SIZE];
+    for (word, digits) in digest.iter_mut().zip(hex.as_bytes().chunks(16)) {
+        let digits = std::str::from_utf8(digits).expect("the digits are ASCII");
+        *word = u64::from_str_radix(digits, 16).expect("the digits are hexadecimal");
+    }
+    Ok(digest)
+}
+
+fn format_digest(digest: &[u64; DIGEST_SIZE]) -> String {
+    digest.iter().map(|word| format!("{:016x}", word)).collect()
+}
+
+/// Adds the path of the file being read or written to an error.
+fn in_file(path: &str) -> impl Fn(Sha512Error) -> String + '_ {
+    move |e| format!("'{}': {}", path, e)
+}
+
+fn setup(flags: &Flags) -> Result<(), String> {
+    let k: u32 = flags.number("k")?;
+    let path = flags.path("params");
+
+    let params = ParamsKZG::<Bn256>::setup(k, OsRng);
+    write_params(&params, Path::new(&path)).map_err(in_file(&path))
+}
+
+fn keygen(flags: &Flags) -> Result<(), String> {
+    let max_blocks = flags.number("max-blocks")?;
+    if max_blocks == 0 {
+        return Err("a circuit hashes at least one block".to_string());
+    }
+    let circuit = Sha512PreimageCircuit::unknown(max_blocks).with_layout(flags.layout()?);
+    let path = flags.path("params");
+    let params = read_params(Path::new(&path)).map_err(in_file(&path))?;
+
+    let vk = keygen_vk(&params, &circuit).map_err(|e| {
+        format!(
+            "cannot generate the verifying key, k = {} may be too small: {:?}",
+            params.k(),
+            e
+        )
+    })?;
+    let path = flags.path("vk");
+    write_vk(&vk, Path::new(&path)).map_err(in_file(&path))?;
+    write_shape(&path, &circuit)?;
+
+    let pk = keygen_pk(&params, vk, &circuit)
+        .map_err(|e| format!("cannot generate the proving key: {:?}", e))?;
+    let path = flags.path("pk");
+    write_pk(&pk, Path::new(&path)).map_err(in_file(&path))?;
+    write_shape(&path, &circuit)
+}
+
+fn prove(flags: &Flags) -> Result<(), String> {
+    let path = flags.required("message")?;
+    let message = fs::read(path).map_err(|e| format!("cannot read '{}': {}", path, e))?;
+    let path = flags.path("pk");
+    let (max_blocks, layout) = read_shape(&path)?;
+    let circuit = Sha512PreimageCircuit::with_max_blocks(&message, max_blocks)
+        .map_err(|e| {
+            format!(
+                "the key '{}' is for messages of up to {} blocks: {}",
+                path, max_blocks, e
+            )
+        })?
+        .with_layout(layout);
+    let pk = read_pk::<Sha512PreimageCircuit>(Path::new(&path)).map_err(in_file(&path))?;
+    let path = flags.path("params");
+    let params = read_params(Path::new(&path)).map_err(in_file(&path))?;
+
+    let public = PreimageDigest::of(&message);
+    let instance: Vec<Fr> = public.instance(layout);
+    let mut transcript = Blake2bWrite::<_, G1Affine, Challenge255<_>>::init(vec![]);
+    create_proof::<
+        KZGCommitmentScheme<Bn256>,
+        ProverSHPLONK<'_, Bn256>,
+        Challenge255<G1Affine>,
+        OsRng,
+        Blake2bWrite<Vec<u8>, G1Affine, Challenge255<G1Affine>>,
+        Sha512PreimageCircuit,
+    >(
+        &params,
+        &pk,
+        &[circuit],
+        &[&[&instance[..]]],
+        OsRng,
+        &mut transcript,
+    )
+    .map_err(|e| format!("cannot generate the proof: {:?}", e))?;
+    let proof = transcript.finalize();
+
+    let path = flags.path("proof");
+    fs::write(&path, proof).map_err(|e| format!("cannot write '{}': {}", path, e))?;
+    println!("digest: {}", format_digest(&public.digest));
+    println!("length: {}", public.length);
+    Ok(())
+}
+
+fn verify(flags: &Flags) -> Result<(), String> {
+    let public = PreimageDigest {
+        digest: parse_digest(flags.required("digest")?)?,
+        length: flags.number("length")?,
+    };
+    let path = flags.path("vk");
+    let (_, layout) = read_shape(&path)?;
+    let instance: Vec<Fr> = public.instance(layout);
+    let vk = read_vk::<Sha512PreimageCircuit>(Path::new(&path)).map_err(in_file(&path))?;
+    let path = flags.path("params");
+    let params = read_params(Path::new(&path)).map_err(in_file(&path))?;
+    let path = flags.path("proof");
+    let proof = fs::read(&path).map_err(|e| format!("cannot read '{}': {}", path, e))?;
+
+    let mut transcript = Blake2bRead::<_, G1Affine, Challenge255<_>>::init(&proof[..]);
+    let strategy = SingleStrategy::new(&params);
+    verify_proof::<
+        KZGCommitmentScheme<Bn256>,
+        VerifierSHPLONK<'_, Bn256>,
+        Challenge255<G1Affine>,
+        Blake2bRead<&[u8], G1Affine, Challenge255<G1Affine>>,
+        SingleStrategy<'_, Bn256>,
+    >(
+        params.verifier_params(),
+        &vk,
+        strategy,
+        &[&[&instance[..]]],
+        &mut transcript,
+    )
+    .map_err(|e| format!("the proof does not verify: {:?}", e))?;
+    println!("the proof verifies");
+    Ok(())
+}
+
+/// Runs the command given by `args`, the arguments after the program name.
+fn run(args: &[String]) -> Result<(), String> {
+    let (command, args) = args.split_first().ok_or_else(|| USAGE.to_string())?;
+    match command.as_str() {
+        "setup" => setup(&Flags::parse(args, &["k", "params"])?),
+        "keygen" => keygen(&Flags::parse(
+            args,
+            &["max-blocks", "layout", "params", "vk", "pk"],
+        )?),
+        "prove" => prove(&Flags::parse(args, &["message", "params", "pk", "proof"])?),
+        "verify" => verify(&Flags::parse(
+            args,
+            &["digest", "length", "params", "vk", "proof"],
+        )?),
+        "help" | "--help" | "-h" => {
+            println!("{}", USAGE);
+            Ok(())
+        }
+        command => Err(format!("unknown command '{}'\n\n{}", command, USAGE)),
+    }
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Err(e) = run(&args) {
+        eprintln!("{}", e);
+        process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{format_digest, format_shape, parse_digest, parse_shape, Flags};
+    use sha512_halo2::sha512::{
+        preimage::{DigestLayout, Sha512PreimageCircuit},
+        IV,
+    };
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn flags() {
+        let flags = Flags::parse(
+            &args(&["--max-blocks", "2", "--layout", "half-words"]),
+            &["max-blocks", "layout", "pk"],
+        )
+        .unwrap();
+        assert_eq!(flags.number::<usize>("max-blocks"), Ok(2));
+        assert_eq!(flags.layout(), Ok(DigestLayout::HalfWords));
+        assert_eq!(flags.path("pk"), "pk.bin");
+        assert!(flags.required("message").is_err());
+
+        assert!(Flags::parse(&args(&["--k"]), &["k"]).is_err());
+        assert!(Flags::parse(&args(&["k", "19"]), &["k"]).is_err());
+        assert!(Flags::parse(&args(&["--q", "19"]), &["k"]).is_err());
+        assert!(Flags::parse(&args(&["--k", "1", "--k", "2"]), &["k"]).is_err());
+    }
+
+    #[test]
+    fn digests() {
+        let hex = format_digest(&IV);
+        assert_eq!(&hex[..16], "6a09e667f3bcc908");
+        assert_eq!(parse_digest(&hex), Ok(IV));
+        assert!(parse_digest(&hex[1..]).is_err());
+        assert!(parse_digest(&hex.replace('a', "g")).is_err());
+    }
+
+    #[test]
+    fn shapes() {
+        let circuit = Sha512PreimageCircuit::unknown(3).with_layout(DigestLayout::HalfWords);
+        let shape = format_shape(&circuit);
+        assert_eq!(shape, "max-blocks 3\nlayout half-words\n");
+        assert_eq!(parse_shape(&shape), Ok((3, DigestLayout::HalfWords)));
+        assert!(parse_shape("max-blocks 3\n").is_err());
+        assert!(parse_shape("max-blocks three\nlayout words\n").is_err());
+        assert!(parse_shape("max-blocks 3\nlayout bytes\n").is_err());
+        assert!(parse_shape("layout words\nmax-blocks 3\n").is_err());
+    }
+}
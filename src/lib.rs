@@ -1 +1,21 @@
-pub mod sha512;
\ No newline at end of file
+//! Halo2 gadgets for the SHA-512 family of hash functions, built on the Table16 chip.
+//!
+//! The gadgets and the chip live in [`sha512`]. The [`prelude`] re-exports what a
+//! circuit hashing with them needs.
+
+pub mod sha512;
+
+/// The items a circuit needs to hash with the Table16 chip: the gadgets, their digests
+/// and the error they return, the members of the SHA-512 family the truncated gadgets
+/// are generic over, the instruction traits, the chip, the word and state types it
+/// assigns, and the sizes and initialization vectors of the SHA-512 family.
+pub mod prelude {
+    pub use crate::sha512::{
+        AssignedBits, AssignedByte, AssignedWord, Bits, BlockInput, BlockWord, ByteInput,
+        DigestWord, MidstateWord, Sha384, Sha384Digest, Sha384Variant, Sha512, Sha512Digest,
+        Sha512Error, Sha512FullVariant, Sha512Instructions, Sha512T, Sha512TDigest,
+        Sha512TInstructions, Sha512Truncated, Sha512VarLenInstructions, Sha512Variant, Sha512_224,
+        Sha512_224Digest, Sha512_256, Sha512_256Digest, Sha512_256Variant, State, Table16Chip,
+        Table16Config, BLOCK_SIZE, DIGEST_SIZE, IV, IV_384, IV_512_224, IV_512_256,
+    };
+}
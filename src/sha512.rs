@@ -1,95 +1,319 @@
 use std::cmp::min;
 use std::convert::TryInto;
 use std::fmt;
+use std::iter;
+use std::marker::PhantomData;
 
 use halo2_proofs::arithmetic::FieldExt;
-use halo2_proofs::{
-    circuit::{Chip, Layouter},
-    plonk::Error,
-};
+use halo2_proofs::circuit::{Chip, Layouter, Value};
 
+pub mod chain;
+#[cfg(feature = "dev-trace")]
+pub mod dev_trace;
+pub mod diagnostics;
+mod error;
+pub mod hkdf;
+pub mod hmac;
+pub mod keys;
+pub mod pbkdf2;
+pub mod preimage;
+pub mod trace;
+pub mod native;
 mod table16;
 
-pub use table16::{BlockWord, Table16Chip, Table16Config,IV};
+pub use error::Sha512Error;
+pub use table16::{
+    AssignedBits, AssignedByte, AssignedWord, Bits, BlockWord, DigestWord, State, Table16Chip,
+    Table16Config, IV, IV_384, IV_512_224, IV_512_256,
+};
 
 /// The size of a SHA-512 block, in 64-bit words.
 pub const BLOCK_SIZE: usize = 16;
-/// The size of a SHA-512 block, in 64-bit words.
-const DIGEST_SIZE: usize = 8;
+/// The size of a SHA-512 digest, in 64-bit words.
+pub const DIGEST_SIZE: usize = 8;
+/// The size of a SHA-384 digest, in 64-bit words.
+const SHA384_DIGEST_SIZE: usize = 6;
+/// The size of a SHA-512/256 digest, in 64-bit words.
+const SHA512_256_DIGEST_SIZE: usize = 4;
+/// The size of a SHA-512 word, in bytes.
+const WORD_BYTES: usize = 8;
+/// The number of bytes the padding appends to a message at least: the 1 bit, padded to
+/// a byte, and the 128-bit length.
+const MIN_PADDING_BYTES: usize = 17;
+
+/// A 64-bit word of a block passed to [`Sha512Instructions::compress_block`].
+#[derive(Clone, Debug)]
+pub enum BlockInput<BlockWord, AssignedWord> {
+    /// A message word witnessed by the prover.
+    Message(BlockWord),
+    /// A message word already assigned elsewhere in the circuit, which is
+    /// copy-constrained into the block.
+    Assigned(AssignedWord),
+    /// A padding word, constrained in the circuit to equal the given constant.
+    Padding(u64),
+}
+
+/// A byte of input passed to [`Sha512Instructions::pack_bytes`].
+#[derive(Clone, Debug)]
+pub enum ByteInput<AssignedByte> {
+    /// A message byte witnessed by the prover.
+    Message(Value<u8>),
+    /// A message byte already assigned elsewhere in the circuit, which is
+    /// copy-constrained into the word.
+    Assigned(AssignedByte),
+    /// A padding byte, constrained in the circuit to equal the given constant.
+    Padding(u8),
+}
+
+/// A 64-bit word of an intermediate hash value passed to [`Sha512::from_midstate`].
+#[derive(Clone, Debug)]
+pub enum MidstateWord<AssignedWord> {
+    /// A word already assigned elsewhere in the circuit, such as from public input,
+    /// which is copy-constrained into the state.
+    Assigned(AssignedWord),
+    /// A word constrained in the circuit to equal the given constant.
+    Constant(u64),
+}
 
 /// The set of circuit instructions required to use the [`Sha512`] gadget.
+///
+/// Instructions return a [`Sha512Error`], so that an error a chip raises while
+/// assigning a region, such as [`Sha512Error::WrongLength`], reaches the gadgets with
+/// its detail.
 pub trait Sha512Instructions<F: FieldExt>: Chip<F> {
     /// Variable representing the SHA-512 internal state.
     type State: Clone + fmt::Debug;
     /// Variable representing a 64-bit word of the input block to the SHA-512 compression
     /// function.
     type BlockWord: Copy + fmt::Debug + Default;
+    /// Variable representing a 64-bit word that has already been assigned in the
+    /// circuit, and can be used as input to the SHA-512 compression function.
+    type AssignedWord: Clone + fmt::Debug;
+    /// Variable representing a byte that has already been assigned in the circuit.
+    type AssignedByte: Clone + fmt::Debug;
+    /// Variable representing a 64-bit word of the digest.
+    type DigestWord: Clone + fmt::Debug;
+
+    /// Places the given IV in the circuit, constrained to its fixed value, returning the
+    /// initial state variable.
+    fn initialization_vector(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        iv: [u64; DIGEST_SIZE],
+    ) -> Result<Self::State, Sha512Error>;
 
-    /// Places the SHA-512 IV in the circuit, returning the initial state variable.
-    fn initialization_vector(&self, layouter: &mut impl Layouter<F>) -> Result<Self::State, Error>;
+    /// Places the given intermediate hash value in the circuit, range-checking each
+    /// word to 64 bits, and returns it as a state from which hashing resumes.
+    fn initialization_from_midstate(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        midstate: [MidstateWord<Self::AssignedWord>; DIGEST_SIZE],
+    ) -> Result<Self::State, Sha512Error>;
 
-    /// Creates an initial state from the output state of a previous block
+    /// Creates an initial state from the output state of a previous block, or from the
+    /// IV.
     fn initialization(
         &self,
         layouter: &mut impl Layouter<F>,
         init_state: &Self::State,
-    ) -> Result<Self::State, Error>;
+    ) -> Result<Self::State, Sha512Error>;
 
     /// Starting from the given initialized state, processes a block of input and returns the
-    /// final state.
+    /// intermediate hash value, i.e. the initialized state plus the working variables
+    /// output by the final round.
     fn compress(
         &self,
         layouter: &mut impl Layouter<F>,
         initialized_state: &Self::State,
         input: [Self::BlockWord; BLOCK_SIZE],
-    ) -> Result<Self::State, Error>;
+    ) -> Result<Self::State, Sha512Error>;
+
+    /// Starting from the given initialized state, processes a block of input in which
+    /// some words are fixed padding, and returns the final state.
+    fn compress_block(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        initialized_state: &Self::State,
+        input: [BlockInput<Self::BlockWord, Self::AssignedWord>; BLOCK_SIZE],
+    ) -> Result<Self::State, Sha512Error>;
+
+    /// Range-checks the given bytes to 8 bits and packs them, most significant first,
+    /// into a 64-bit word that can be used as input to the compression function.
+    fn pack_bytes(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        bytes: [ByteInput<Self::AssignedByte>; WORD_BYTES],
+    ) -> Result<Self::AssignedWord, Sha512Error>;
 
     /// Converts the given state into a message digest.
     fn digest(
         &self,
         layouter: &mut impl Layouter<F>,
         state: &Self::State,
-    ) -> Result<[Self::BlockWord; DIGEST_SIZE], Error>;
+    ) -> Result<[Self::DigestWord; DIGEST_SIZE], Sha512Error>;
+}
+
+/// The circuit instructions required to hash messages whose length is a witness with
+/// [`Sha512::digest_var_len`].
+pub trait Sha512VarLenInstructions<F: FieldExt>: Sha512Instructions<F> {
+    /// Variable representing a boolean flag.
+    type Flag: Clone + fmt::Debug;
+
+    /// Pads the message held in the first `length` of the given bytes, which span a
+    /// whole number of blocks, with the 1 bit and zeros of FIPS 180-4. The message must
+    /// leave room for the padding in the final block.
+    ///
+    /// Returns the padded bytes, the assigned message length in bytes, and for each
+    /// block a flag set only on the block the padding ends in.
+    #[allow(clippy::type_complexity)]
+    fn pad_var_len(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        bytes: &[Value<u8>],
+        length: Value<u64>,
+    ) -> Result<(Vec<Self::AssignedByte>, Self::AssignedWord, Vec<Self::Flag>), Sha512Error>;
+
+    /// Adds the message length in bits to `word`, the final word of a block, if
+    /// `is_last` is set.
+    fn append_length(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        word: &Self::AssignedWord,
+        is_last: &Self::Flag,
+        length: &Self::AssignedWord,
+    ) -> Result<Self::AssignedWord, Sha512Error>;
+
+    /// Selects the digest whose flag is set. Exactly one flag must be set.
+    fn select_digest(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        digests: &[[Self::DigestWord; DIGEST_SIZE]],
+        is_last: &[Self::Flag],
+    ) -> Result<[Self::DigestWord; DIGEST_SIZE], Sha512Error>;
+}
+
+/// The circuit instructions required to truncate digests to an arbitrary number of bits
+/// with [`Sha512T`].
+pub trait Sha512TInstructions<F: FieldExt>: Sha512Instructions<F> {
+    /// Returns a word holding the high `bits` bits of the given digest word, where
+    /// `0 < bits < 64`.
+    fn truncate_word(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        word: &Self::DigestWord,
+        bits: usize,
+    ) -> Result<Self::AssignedWord, Sha512Error>;
 }
 
 /// The output of a SHA-512 circuit invocation.
 #[derive(Debug)]
-pub struct Sha512Digest<BlockWord>(pub [BlockWord; DIGEST_SIZE]);
+pub struct Sha512Digest<DigestWord>(pub [DigestWord; DIGEST_SIZE]);
 
-/// A gadget that constrains a SHA-512 invocation. It supports input at a granularity of
-/// 64 bits.
+/// The output of a SHA-384 circuit invocation.
+#[derive(Debug)]
+pub struct Sha384Digest<DigestWord>(pub [DigestWord; SHA384_DIGEST_SIZE]);
+
+/// The output of a SHA-512/256 circuit invocation.
+#[derive(Debug)]
+pub struct Sha512_256Digest<DigestWord>(pub [DigestWord; SHA512_256_DIGEST_SIZE]);
+
+/// The output of a SHA-512/224 circuit invocation: its first three 64-bit words, followed
+/// by the high 32 bits of the fourth. It is the output of [`Sha512T`] for `t = 224`.
+pub type Sha512_224Digest<DigestWord, AssignedWord> = Sha512TDigest<DigestWord, AssignedWord>;
+
+/// The output of a SHA-512/t circuit invocation: the whole 64-bit words of the digest,
+/// followed by the high `t % 64` bits of the next word if `t` is not a multiple of 64.
 #[derive(Debug)]
+pub struct Sha512TDigest<DigestWord, AssignedWord> {
+    /// The whole words of the digest.
+    pub words: Vec<DigestWord>,
+    /// The truncated last word of the digest, if any.
+    pub last: Option<AssignedWord>,
+}
+
+/// A gadget that constrains a SHA-512 invocation. It supports input at a granularity of
+/// 64 bits through [`Sha512::update`], or of bytes through [`Sha512::update_bytes`].
+#[derive(Clone, Debug)]
 pub struct Sha512<F: FieldExt, CS: Sha512Instructions<F>> {
     chip: CS,
     state: CS::State,
-    cur_block: Vec<CS::BlockWord>,
+    cur_block: Vec<BlockInput<CS::BlockWord, CS::AssignedWord>>,
+    cur_word: Vec<ByteInput<CS::AssignedByte>>,
     length: usize,
 }
 
 impl<F: FieldExt, Sha512Chip: Sha512Instructions<F>> Sha512<F, Sha512Chip> {
     /// Create a new hasher instance.
-    pub fn new(chip: Sha512Chip, mut layouter: impl Layouter<F>) -> Result<Self, Error> {
-        let state = chip.initialization_vector(&mut layouter)?;
+    pub fn new(chip: Sha512Chip, layouter: impl Layouter<F>) -> Result<Self, Sha512Error> {
+        Self::with_iv(chip, layouter, IV)
+    }
+
+    /// Create a new hasher instance starting from the given IV, for the other members of
+    /// the SHA-512 family.
+    fn with_iv(
+        chip: Sha512Chip,
+        mut layouter: impl Layouter<F>,
+        iv: [u64; DIGEST_SIZE],
+    ) -> Result<Self, Sha512Error> {
+        let state = chip.initialization_vector(&mut layouter, iv)?;
         Ok(Sha512 {
             chip,
             state,
             cur_block: Vec::with_capacity(BLOCK_SIZE),
+            cur_word: Vec::with_capacity(WORD_BYTES),
             length: 0,
         })
     }
 
+    /// Create a new hasher instance that resumes from an intermediate hash value, after
+    /// `processed_len` bytes of message have been hashed into it, e.g. to hash a
+    /// message whose fixed prefix was hashed outside the circuit, or to continue a
+    /// message across several proofs.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Sha512Error::MalformedInput`] if `processed_len` is not a whole number
+    /// of blocks.
+    pub fn from_midstate(
+        chip: Sha512Chip,
+        mut layouter: impl Layouter<F>,
+        state_words: [MidstateWord<Sha512Chip::AssignedWord>; DIGEST_SIZE],
+        processed_len: usize,
+    ) -> Result<Self, Sha512Error> {
+        if processed_len % (BLOCK_SIZE * WORD_BYTES) != 0 {
+            return Err(Sha512Error::MalformedInput("a midstate is taken between blocks"));
+        }
+        let length = processed_len
+            .checked_mul(8)
+            .ok_or(Sha512Error::LengthOverflow)?;
+        let state = chip.initialization_from_midstate(&mut layouter, state_words)?;
+        Ok(Sha512 {
+            chip,
+            state,
+            cur_block: Vec::with_capacity(BLOCK_SIZE),
+            cur_word: Vec::with_capacity(WORD_BYTES),
+            length,
+        })
+    }
+
     /// Digest data, updating the internal state.
     pub fn update(
         &mut self,
         mut layouter: impl Layouter<F>,
         mut data: &[Sha512Chip::BlockWord],
-    ) -> Result<(), Error> {
-        self.length += data.len() * 64;
+    ) -> Result<(), Sha512Error> {
+        if !self.cur_word.is_empty() {
+            return Err(Sha512Error::MalformedInput(
+                "word input cannot follow a partial word of bytes",
+            ));
+        }
+        self.absorb_length(data.len(), 64)?;
 
         // Fill the current block, if possible.
         let remaining = BLOCK_SIZE - self.cur_block.len();
         let (l, r) = data.split_at(min(remaining, data.len()));
-        self.cur_block.extend_from_slice(l);
+        self.cur_block
+            .extend(l.iter().copied().map(BlockInput::Message));
         data = r;
 
         // If we still don't have a full block, we are done.
@@ -98,14 +322,14 @@ impl<F: FieldExt, Sha512Chip: Sha512Instructions<F>> Sha512<F, Sha512Chip> {
         }
 
         // Process the now-full current block.
-        self.state = self.chip.compress(
+        self.state = self.chip.initialization(&mut layouter, &self.state)?;
+        self.state = self.chip.compress_block(
             &mut layouter,
             &self.state,
-            self.cur_block[..]
+            std::mem::take(&mut self.cur_block)
                 .try_into()
                 .expect("cur_block.len() == BLOCK_SIZE"),
         )?;
-        self.cur_block.clear();
 
         // Process any additional full blocks.
         let mut chunks_iter = data.chunks_exact(BLOCK_SIZE);
@@ -120,32 +344,215 @@ impl<F: FieldExt, Sha512Chip: Sha512Instructions<F>> Sha512<F, Sha512Chip> {
 
         // Cache the remaining partial block, if any.
         let rem = chunks_iter.remainder();
-        self.cur_block.extend_from_slice(rem);
+        self.cur_block
+            .extend(rem.iter().copied().map(BlockInput::Message));
+
+        Ok(())
+    }
+
+    /// Digest data that has already been assigned in the circuit, updating the internal
+    /// state. Each word is copy-constrained into the message schedule of the block it
+    /// falls in.
+    pub fn update_assigned(
+        &mut self,
+        mut layouter: impl Layouter<F>,
+        data: &[Sha512Chip::AssignedWord],
+    ) -> Result<(), Sha512Error> {
+        let data: Vec<_> = data.iter().cloned().map(BlockInput::Assigned).collect();
+        self.update_block_input(&mut layouter, &data)
+    }
+
+    /// Digest words that are each either assigned in the circuit or fixed, updating the
+    /// internal state.
+    fn update_block_input(
+        &mut self,
+        layouter: &mut impl Layouter<F>,
+        data: &[BlockInput<Sha512Chip::BlockWord, Sha512Chip::AssignedWord>],
+    ) -> Result<(), Sha512Error> {
+        if !self.cur_word.is_empty() {
+            return Err(Sha512Error::MalformedInput(
+                "word input cannot follow a partial word of bytes",
+            ));
+        }
+        self.absorb_length(data.len(), 64)?;
+
+        for word in data {
+            self.absorb_word(layouter, word.clone())?;
+        }
+
+        Ok(())
+    }
+
+    /// Digest bytes, updating the internal state. Bytes are range-checked and packed
+    /// big-endian into 64-bit words in the circuit.
+    pub fn update_bytes(
+        &mut self,
+        mut layouter: impl Layouter<F>,
+        data: &[Value<u8>],
+    ) -> Result<(), Sha512Error> {
+        self.absorb_length(data.len(), 8)?;
+
+        for byte in data {
+            self.absorb_byte(&mut layouter, ByteInput::Message(*byte))?;
+        }
+
+        Ok(())
+    }
+
+    /// Digest bytes that have already been assigned in the circuit, updating the
+    /// internal state. Each byte is copy-constrained into the word it falls in, and
+    /// range-checked there.
+    pub fn update_assigned_bytes(
+        &mut self,
+        mut layouter: impl Layouter<F>,
+        data: &[Sha512Chip::AssignedByte],
+    ) -> Result<(), Sha512Error> {
+        self.absorb_length(data.len(), 8)?;
+
+        for byte in data {
+            self.absorb_byte(&mut layouter, ByteInput::Assigned(byte.clone()))?;
+        }
+
+        Ok(())
+    }
+
+    /// Digest bytes that are each witnessed, assigned in the circuit or fixed, updating
+    /// the internal state.
+    fn update_byte_input(
+        &mut self,
+        layouter: &mut impl Layouter<F>,
+        data: &[ByteInput<Sha512Chip::AssignedByte>],
+    ) -> Result<(), Sha512Error> {
+        self.absorb_length(data.len(), 8)?;
+
+        for byte in data {
+            self.absorb_byte(layouter, byte.clone())?;
+        }
+
+        Ok(())
+    }
 
+    /// Counts `count` items of `bits` bits each towards the message length.
+    fn absorb_length(&mut self, count: usize, bits: usize) -> Result<(), Sha512Error> {
+        self.length = count
+            .checked_mul(bits)
+            .and_then(|bits| self.length.checked_add(bits))
+            .ok_or(Sha512Error::LengthOverflow)?;
+        Ok(())
+    }
+
+    /// Appends a byte to the current word, packing the word into the current block once
+    /// it is full.
+    fn absorb_byte(
+        &mut self,
+        layouter: &mut impl Layouter<F>,
+        byte: ByteInput<Sha512Chip::AssignedByte>,
+    ) -> Result<(), Sha512Error> {
+        self.cur_word.push(byte);
+        if self.cur_word.len() < WORD_BYTES {
+            return Ok(());
+        }
+
+        let word = self.chip.pack_bytes(
+            layouter,
+            std::mem::take(&mut self.cur_word)
+                .try_into()
+                .expect("cur_word.len() == WORD_BYTES"),
+        )?;
+        self.absorb_word(layouter, BlockInput::Assigned(word))
+    }
+
+    /// Appends a word to the current block, processing the block once it is full.
+    fn absorb_word(
+        &mut self,
+        layouter: &mut impl Layouter<F>,
+        word: BlockInput<Sha512Chip::BlockWord, Sha512Chip::AssignedWord>,
+    ) -> Result<(), Sha512Error> {
+        self.cur_block.push(word);
+        if self.cur_block.len() < BLOCK_SIZE {
+            return Ok(());
+        }
+
+        self.state = self.chip.initialization(layouter, &self.state)?;
+        self.state = self.chip.compress_block(
+            layouter,
+            &self.state,
+            std::mem::take(&mut self.cur_block)
+                .try_into()
+                .expect("cur_block.len() == BLOCK_SIZE"),
+        )?;
         Ok(())
     }
 
     /// Retrieve result and consume hasher instance.
     pub fn finalize(
+        self,
+        layouter: impl Layouter<F>,
+    ) -> Result<Sha512Digest<Sha512Chip::DigestWord>, Sha512Error> {
+        self.finalize_with_chip(layouter).map(|(_, digest)| digest)
+    }
+
+    /// Retrieve the intermediate hash value reached so far, without padding the
+    /// message, and consume hasher instance. Hashing may resume from it in another
+    /// circuit with [`Sha512::from_midstate`]. Its words take the form of a digest's,
+    /// so that they can be exposed as public inputs like one.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Sha512Error::MalformedInput`] if the message absorbed so far is not a
+    /// whole number of blocks.
+    pub fn midstate(
+        self,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<Sha512Digest<Sha512Chip::DigestWord>, Sha512Error> {
+        if !self.cur_block.is_empty() || !self.cur_word.is_empty() {
+            return Err(Sha512Error::MalformedInput("a midstate is taken between blocks"));
+        }
+        let state = self.chip.digest(&mut layouter, &self.state)?;
+        Ok(Sha512Digest(state))
+    }
+
+    /// Retrieve result and consume hasher instance, handing back the chip for further
+    /// use on the digest.
+    fn finalize_with_chip(
         mut self,
         mut layouter: impl Layouter<F>,
-    ) -> Result<Sha512Digest<Sha512Chip::BlockWord>, Error> {
-        // Pad the remaining block
-        if !self.cur_block.is_empty() {
-            let padding = vec![Sha512Chip::BlockWord::default(); BLOCK_SIZE - self.cur_block.len()];
-            self.cur_block.extend_from_slice(&padding);
+    ) -> Result<(Sha512Chip, Sha512Digest<Sha512Chip::DigestWord>), Sha512Error> {
+        // Pad the message as specified in FIPS 180-4, section 5.1.2: a single 1 bit,
+        // then zeros up to 128 bits short of a block boundary, then the message length
+        // in bits as a 128-bit big-endian integer. The 1 bit begins the byte following
+        // the message, which completes a partial word if there is one.
+        if self.cur_word.is_empty() {
+            self.cur_block.push(BlockInput::Padding(1 << 63));
+        } else {
+            let zeros = WORD_BYTES - 1 - self.cur_word.len();
+            for byte in iter::once(0x80).chain(iter::repeat(0).take(zeros)) {
+                self.absorb_byte(&mut layouter, ByteInput::Padding(byte))?;
+            }
+        }
+
+        let zeros = (2 * BLOCK_SIZE - 2 - self.cur_block.len()) % BLOCK_SIZE;
+        let length = self.length as u128;
+        let padding = iter::repeat(0)
+            .take(zeros)
+            .chain([(length >> 64) as u64, length as u64]);
+        self.cur_block.extend(padding.map(BlockInput::Padding));
+
+        // The padding spills into a second block if it does not fit in the current one.
+        let blocks = std::mem::take(&mut self.cur_block);
+        for block in blocks.chunks_exact(BLOCK_SIZE) {
             self.state = self.chip.initialization(&mut layouter, &self.state)?;
-            self.state = self.chip.compress(
+            self.state = self.chip.compress_block(
                 &mut layouter,
                 &self.state,
-                self.cur_block[..]
+                block
+                    .to_vec()
                     .try_into()
-                    .expect("cur_block.len() == BLOCK_SIZE"),
+                    .expect("block.len() == BLOCK_SIZE"),
             )?;
         }
-        self.chip
-            .digest(&mut layouter, &self.state)
-            .map(Sha512Digest)
+        let digest = self.chip.digest(&mut layouter, &self.state)?;
+        Ok((self.chip, Sha512Digest(digest)))
     }
 
     /// Convenience function to compute hash of the data. It will handle hasher creation,
@@ -154,9 +561,519 @@ impl<F: FieldExt, Sha512Chip: Sha512Instructions<F>> Sha512<F, Sha512Chip> {
         chip: Sha512Chip,
         mut layouter: impl Layouter<F>,
         data: &[Sha512Chip::BlockWord],
-    ) -> Result<Sha512Digest<Sha512Chip::BlockWord>, Error> {
+    ) -> Result<Sha512Digest<Sha512Chip::DigestWord>, Sha512Error> {
         let mut hasher = Self::new(chip, layouter.namespace(|| "init"))?;
         hasher.update(layouter.namespace(|| "update"), data)?;
         hasher.finalize(layouter.namespace(|| "finalize"))
     }
-}
\ No newline at end of file
+
+    /// Convenience function to compute hash of a byte string.
+    pub fn digest_bytes(
+        chip: Sha512Chip,
+        mut layouter: impl Layouter<F>,
+        data: &[Value<u8>],
+    ) -> Result<Sha512Digest<Sha512Chip::DigestWord>, Sha512Error> {
+        let mut hasher = Self::new(chip, layouter.namespace(|| "init"))?;
+        hasher.update_bytes(layouter.namespace(|| "update"), data)?;
+        hasher.finalize(layouter.namespace(|| "finalize"))
+    }
+
+    /// Convenience function to compute hash of data that has already been assigned in
+    /// the circuit.
+    pub fn digest_assigned(
+        chip: Sha512Chip,
+        mut layouter: impl Layouter<F>,
+        data: &[Sha512Chip::AssignedWord],
+    ) -> Result<Sha512Digest<Sha512Chip::DigestWord>, Sha512Error> {
+        let mut hasher = Self::new(chip, layouter.namespace(|| "init"))?;
+        hasher.update_assigned(layouter.namespace(|| "update"), data)?;
+        hasher.finalize(layouter.namespace(|| "finalize"))
+    }
+}
+
+/// Checks that a message of `len` bytes fits with its padding in `max_blocks` blocks, as
+/// [`Sha512::digest_var_len`] requires.
+fn check_var_len(len: usize, max_blocks: usize) -> Result<(), Sha512Error> {
+    if max_blocks == 0 {
+        return Err(Sha512Error::MalformedInput(
+            "a variable-length message spans at least one block",
+        ));
+    }
+    if len + MIN_PADDING_BYTES > max_blocks * BLOCK_SIZE * WORD_BYTES {
+        return Err(Sha512Error::MalformedInput(
+            "the message and its padding must fit in max_blocks blocks",
+        ));
+    }
+    Ok(())
+}
+
+impl<F: FieldExt, Sha512Chip: Sha512VarLenInstructions<F>> Sha512<F, Sha512Chip> {
+    /// Computes the hash of a message whose length in bytes is a witness. The circuit
+    /// always compresses `max_blocks` blocks, so it only depends on `max_blocks`; the
+    /// message and its padding must fit in them, which leaves room for at most
+    /// `max_blocks * 128 - 17` bytes.
+    ///
+    /// `data` holds the message, and may be given up to that maximum size; only its
+    /// first `length` bytes are hashed. Returns the digest together with the assigned
+    /// length of the message in bytes, which the caller may expose as a public input.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Sha512Error::MalformedInput`] if `max_blocks` is zero, or `data` or a
+    /// known `length` does not fit in `max_blocks` blocks with its padding.
+    pub fn digest_var_len(
+        chip: Sha512Chip,
+        mut layouter: impl Layouter<F>,
+        data: &[Value<u8>],
+        length: Value<u64>,
+        max_blocks: usize,
+    ) -> Result<
+        (Sha512Digest<Sha512Chip::DigestWord>, Sha512Chip::AssignedWord),
+        Sha512Error,
+    > {
+        check_var_len(data.len(), max_blocks)?;
+        let block_bytes = BLOCK_SIZE * WORD_BYTES;
+        // No block would be flagged as the last one for a longer message.
+        let max_len = (max_blocks * block_bytes - MIN_PADDING_BYTES) as u64;
+        length
+            .error_if_known_and(|length| *length > max_len)
+            .map_err(|_| {
+                Sha512Error::MalformedInput(
+                    "the length and its padding must fit in max_blocks blocks",
+                )
+            })?;
+
+        let bytes: Vec<_> = data
+            .iter()
+            .copied()
+            .chain(iter::repeat(Value::known(0)))
+            .take(max_blocks * block_bytes)
+            .collect();
+        let (padded, length, is_last) = chip.pad_var_len(&mut layouter, &bytes, length)?;
+
+        let mut state = chip.initialization_vector(&mut layouter, IV)?;
+        let mut digests = Vec::with_capacity(max_blocks);
+        for (block, is_last) in padded.chunks_exact(block_bytes).zip(is_last.iter()) {
+            let mut words = block
+                .chunks_exact(WORD_BYTES)
+                .map(|word| {
+                    let word: Vec<_> = word.iter().cloned().map(ByteInput::Assigned).collect();
+                    chip.pack_bytes(
+                        &mut layouter,
+                        word.try_into().expect("word.len() == WORD_BYTES"),
+                    )
+                })
+                .collect::<Result<Vec<_>, Sha512Error>>()?;
+
+            // Only the final block carries the length. The high word of the 128-bit
+            // length is zero, as is every padding byte under it.
+            words[BLOCK_SIZE - 1] =
+                chip.append_length(&mut layouter, &words[BLOCK_SIZE - 1], is_last, &length)?;
+
+            let words: Vec<_> = words.into_iter().map(BlockInput::Assigned).collect();
+            state = chip.initialization(&mut layouter, &state)?;
+            state = chip.compress_block(
+                &mut layouter,
+                &state,
+                words.try_into().expect("words.len() == BLOCK_SIZE"),
+            )?;
+            digests.push(chip.digest(&mut layouter, &state)?);
+        }
+
+        let digest = chip.select_digest(&mut layouter, &digests, &is_last)?;
+        Ok((Sha512Digest(digest), length))
+    }
+}
+
+/// A member of the SHA-512 family: SHA-512 started from its own IV, with the digest
+/// truncated to whole words. SHA-512/224, whose digest ends halfway through a word, is
+/// the [`Sha512_224`] gadget instead.
+pub trait Sha512Variant<DigestWord> {
+    /// The initial hash value.
+    const IV: [u64; DIGEST_SIZE];
+    /// The output of a circuit invocation.
+    type Digest;
+
+    /// Truncates the words of a SHA-512 digest to the output of this variant.
+    fn truncate(digest: [DigestWord; DIGEST_SIZE]) -> Self::Digest;
+}
+
+/// SHA-512 itself, from FIPS 180-4 section 6.4, with the digest left whole.
+#[derive(Debug)]
+pub struct Sha512FullVariant;
+
+impl<DigestWord> Sha512Variant<DigestWord> for Sha512FullVariant {
+    const IV: [u64; DIGEST_SIZE] = IV;
+    type Digest = Sha512Digest<DigestWord>;
+
+    fn truncate(digest: [DigestWord; DIGEST_SIZE]) -> Self::Digest {
+        Sha512Digest(digest)
+    }
+}
+
+/// SHA-384, from FIPS 180-4 section 6.5.
+#[derive(Debug)]
+pub struct Sha384Variant;
+
+impl<DigestWord> Sha512Variant<DigestWord> for Sha384Variant {
+    const IV: [u64; DIGEST_SIZE] = IV_384;
+    type Digest = Sha384Digest<DigestWord>;
+
+    fn truncate(digest: [DigestWord; DIGEST_SIZE]) -> Self::Digest {
+        let [a, b, c, d, e, f, _, _] = digest;
+        Sha384Digest([a, b, c, d, e, f])
+    }
+}
+
+/// SHA-512/256, from FIPS 180-4 section 6.7.
+#[derive(Debug)]
+pub struct Sha512_256Variant;
+
+impl<DigestWord> Sha512Variant<DigestWord> for Sha512_256Variant {
+    const IV: [u64; DIGEST_SIZE] = IV_512_256;
+    type Digest = Sha512_256Digest<DigestWord>;
+
+    fn truncate(digest: [DigestWord; DIGEST_SIZE]) -> Self::Digest {
+        let [a, b, c, d, _, _, _, _] = digest;
+        Sha512_256Digest([a, b, c, d])
+    }
+}
+
+/// A gadget that constrains an invocation of a member of the SHA-512 family. These
+/// differ from SHA-512 only in their IV and the truncation of the digest, so they run
+/// on any [`Sha512Instructions`] chip.
+#[derive(Debug)]
+pub struct Sha512Truncated<F: FieldExt, CS: Sha512Instructions<F>, V> {
+    inner: Sha512<F, CS>,
+    _marker: PhantomData<V>,
+}
+
+/// A gadget that constrains a SHA-384 invocation.
+pub type Sha384<F, CS> = Sha512Truncated<F, CS, Sha384Variant>;
+/// A gadget that constrains a SHA-512/256 invocation.
+pub type Sha512_256<F, CS> = Sha512Truncated<F, CS, Sha512_256Variant>;
+
+impl<F, Sha512Chip, V> Sha512Truncated<F, Sha512Chip, V>
+where
+    F: FieldExt,
+    Sha512Chip: Sha512Instructions<F>,
+    V: Sha512Variant<Sha512Chip::DigestWord>,
+{
+    /// Create a new hasher instance.
+    pub fn new(chip: Sha512Chip, layouter: impl Layouter<F>) -> Result<Self, Sha512Error> {
+        Ok(Sha512Truncated {
+            inner: Sha512::with_iv(chip, layouter, V::IV)?,
+            _marker: PhantomData,
+        })
+    }
+
+    /// Digest data, updating the internal state.
+    pub fn update(
+        &mut self,
+        layouter: impl Layouter<F>,
+        data: &[Sha512Chip::BlockWord],
+    ) -> Result<(), Sha512Error> {
+        self.inner.update(layouter, data)
+    }
+
+    /// Digest data that has already been assigned in the circuit, updating the internal
+    /// state.
+    pub fn update_assigned(
+        &mut self,
+        layouter: impl Layouter<F>,
+        data: &[Sha512Chip::AssignedWord],
+    ) -> Result<(), Sha512Error> {
+        self.inner.update_assigned(layouter, data)
+    }
+
+    /// Digest bytes, updating the internal state.
+    pub fn update_bytes(
+        &mut self,
+        layouter: impl Layouter<F>,
+        data: &[Value<u8>],
+    ) -> Result<(), Sha512Error> {
+        self.inner.update_bytes(layouter, data)
+    }
+
+    /// Digest bytes that have already been assigned in the circuit, updating the
+    /// internal state.
+    pub fn update_assigned_bytes(
+        &mut self,
+        layouter: impl Layouter<F>,
+        data: &[Sha512Chip::AssignedByte],
+    ) -> Result<(), Sha512Error> {
+        self.inner.update_assigned_bytes(layouter, data)
+    }
+
+    /// Retrieve result and consume hasher instance.
+    pub fn finalize(self, layouter: impl Layouter<F>) -> Result<V::Digest, Sha512Error> {
+        self.inner
+            .finalize(layouter)
+            .map(|digest| V::truncate(digest.0))
+    }
+
+    /// Convenience function to compute hash of the data. It will handle hasher creation,
+    /// data feeding and finalization.
+    pub fn digest(
+        chip: Sha512Chip,
+        mut layouter: impl Layouter<F>,
+        data: &[Sha512Chip::BlockWord],
+    ) -> Result<V::Digest, Sha512Error> {
+        let mut hasher = Self::new(chip, layouter.namespace(|| "init"))?;
+        hasher.update(layouter.namespace(|| "update"), data)?;
+        hasher.finalize(layouter.namespace(|| "finalize"))
+    }
+
+    /// Convenience function to compute hash of a byte string.
+    pub fn digest_bytes(
+        chip: Sha512Chip,
+        mut layouter: impl Layouter<F>,
+        data: &[Value<u8>],
+    ) -> Result<V::Digest, Sha512Error> {
+        let mut hasher = Self::new(chip, layouter.namespace(|| "init"))?;
+        hasher.update_bytes(layouter.namespace(|| "update"), data)?;
+        hasher.finalize(layouter.namespace(|| "finalize"))
+    }
+
+    /// Convenience function to compute hash of data that has already been assigned in
+    /// the circuit.
+    pub fn digest_assigned(
+        chip: Sha512Chip,
+        mut layouter: impl Layouter<F>,
+        data: &[Sha512Chip::AssignedWord],
+    ) -> Result<V::Digest, Sha512Error> {
+        let mut hasher = Self::new(chip, layouter.namespace(|| "init"))?;
+        hasher.update_assigned(layouter.namespace(|| "update"), data)?;
+        hasher.finalize(layouter.namespace(|| "finalize"))
+    }
+}
+
+/// A gadget that constrains a SHA-512/t invocation, for any `t` SHA-512/t is defined for.
+/// The IV is computed with the IV generation function of FIPS 180-4 when the gadget is
+/// created, and fixed in the circuit.
+#[derive(Debug)]
+pub struct Sha512T<F: FieldExt, CS: Sha512TInstructions<F>> {
+    inner: Sha512<F, CS>,
+    t: usize,
+}
+
+impl<F: FieldExt, Sha512Chip: Sha512TInstructions<F>> Sha512T<F, Sha512Chip> {
+    /// Create a new SHA-512/t hasher instance.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Sha512Error::MalformedInput`] if `t` is not in `1..512`, or is 384.
+    pub fn new(
+        chip: Sha512Chip,
+        layouter: impl Layouter<F>,
+        t: usize,
+    ) -> Result<Self, Sha512Error> {
+        if t == 0 || t >= 512 || t == 384 {
+            return Err(Sha512Error::MalformedInput("SHA-512/t is not defined for this t"));
+        }
+        let iv = native::sha512_t_iv(t);
+        Ok(Sha512T {
+            inner: Sha512::with_iv(chip, layouter, iv)?,
+            t,
+        })
+    }
+
+    /// Digest data, updating the internal state.
+    pub fn update(
+        &mut self,
+        layouter: impl Layouter<F>,
+        data: &[Sha512Chip::BlockWord],
+    ) -> Result<(), Sha512Error> {
+        self.inner.update(layouter, data)
+    }
+
+    /// Digest data that has already been assigned in the circuit, updating the internal
+    /// state.
+    pub fn update_assigned(
+        &mut self,
+        layouter: impl Layouter<F>,
+        data: &[Sha512Chip::AssignedWord],
+    ) -> Result<(), Sha512Error> {
+        self.inner.update_assigned(layouter, data)
+    }
+
+    /// Digest bytes, updating the internal state.
+    pub fn update_bytes(
+        &mut self,
+        layouter: impl Layouter<F>,
+        data: &[Value<u8>],
+    ) -> Result<(), Sha512Error> {
+        self.inner.update_bytes(layouter, data)
+    }
+
+    /// Digest bytes that have already been assigned in the circuit, updating the
+    /// internal state.
+    pub fn update_assigned_bytes(
+        &mut self,
+        layouter: impl Layouter<F>,
+        data: &[Sha512Chip::AssignedByte],
+    ) -> Result<(), Sha512Error> {
+        self.inner.update_assigned_bytes(layouter, data)
+    }
+
+    /// Retrieve result and consume hasher instance.
+    #[allow(clippy::type_complexity)]
+    pub fn finalize(
+        self,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<
+        Sha512TDigest<Sha512Chip::DigestWord, Sha512Chip::AssignedWord>,
+        Sha512Error,
+    > {
+        let (words, bits) = (self.t / 64, self.t % 64);
+        let (chip, digest) = self
+            .inner
+            .finalize_with_chip(layouter.namespace(|| "finalize"))?;
+        let digest = digest.0;
+
+        let last = if bits > 0 {
+            Some(chip.truncate_word(&mut layouter, &digest[words], bits)?)
+        } else {
+            None
+        };
+        Ok(Sha512TDigest {
+            words: digest[..words].to_vec(),
+            last,
+        })
+    }
+
+    /// Convenience function to compute the SHA-512/t hash of a byte string.
+    #[allow(clippy::type_complexity)]
+    pub fn digest_bytes(
+        chip: Sha512Chip,
+        mut layouter: impl Layouter<F>,
+        t: usize,
+        data: &[Value<u8>],
+    ) -> Result<
+        Sha512TDigest<Sha512Chip::DigestWord, Sha512Chip::AssignedWord>,
+        Sha512Error,
+    > {
+        let mut hasher = Self::new(chip, layouter.namespace(|| "init"), t)?;
+        hasher.update_bytes(layouter.namespace(|| "update"), data)?;
+        hasher.finalize(layouter.namespace(|| "finalize"))
+    }
+}
+
+/// A gadget that constrains a SHA-512/224 invocation, from FIPS 180-4 section 6.6.
+///
+/// The digest ends halfway through its fourth word, which is cut to its high 32 bits by
+/// [`Sha512TInstructions::truncate_word`], so that the digest is the one [`Sha512T`]
+/// outputs for `t = 224`.
+#[derive(Debug)]
+pub struct Sha512_224<F: FieldExt, CS: Sha512TInstructions<F>> {
+    inner: Sha512T<F, CS>,
+}
+
+impl<F: FieldExt, Sha512Chip: Sha512TInstructions<F>> Sha512_224<F, Sha512Chip> {
+    /// Create a new hasher instance.
+    pub fn new(chip: Sha512Chip, layouter: impl Layouter<F>) -> Result<Self, Sha512Error> {
+        Ok(Sha512_224 {
+            inner: Sha512T {
+                inner: Sha512::with_iv(chip, layouter, IV_512_224)?,
+                t: 224,
+            },
+        })
+    }
+
+    /// Digest data, updating the internal state.
+    pub fn update(
+        &mut self,
+        layouter: impl Layouter<F>,
+        data: &[Sha512Chip::BlockWord],
+    ) -> Result<(), Sha512Error> {
+        self.inner.update(layouter, data)
+    }
+
+    /// Digest data that has already been assigned in the circuit, updating the internal
+    /// state.
+    pub fn update_assigned(
+        &mut self,
+        layouter: impl Layouter<F>,
+        data: &[Sha512Chip::AssignedWord],
+    ) -> Result<(), Sha512Error> {
+        self.inner.update_assigned(layouter, data)
+    }
+
+    /// Digest bytes, updating the internal state.
+    pub fn update_bytes(
+        &mut self,
+        layouter: impl Layouter<F>,
+        data: &[Value<u8>],
+    ) -> Result<(), Sha512Error> {
+        self.inner.update_bytes(layouter, data)
+    }
+
+    /// Digest bytes that have already been assigned in the circuit, updating the
+    /// internal state.
+    pub fn update_assigned_bytes(
+        &mut self,
+        layouter: impl Layouter<F>,
+        data: &[Sha512Chip::AssignedByte],
+    ) -> Result<(), Sha512Error> {
+        self.inner.update_assigned_bytes(layouter, data)
+    }
+
+    /// Retrieve result and consume hasher instance.
+    #[allow(clippy::type_complexity)]
+    pub fn finalize(
+        self,
+        layouter: impl Layouter<F>,
+    ) -> Result<
+        Sha512_224Digest<Sha512Chip::DigestWord, Sha512Chip::AssignedWord>,
+        Sha512Error,
+    > {
+        self.inner.finalize(layouter)
+    }
+
+    /// Convenience function to compute hash of the data. It will handle hasher creation,
+    /// data feeding and finalization.
+    #[allow(clippy::type_complexity)]
+    pub fn digest(
+        chip: Sha512Chip,
+        mut layouter: impl Layouter<F>,
+        data: &[Sha512Chip::BlockWord],
+    ) -> Result<
+        Sha512_224Digest<Sha512Chip::DigestWord, Sha512Chip::AssignedWord>,
+        Sha512Error,
+    > {
+        let mut hasher = Self::new(chip, layouter.namespace(|| "init"))?;
+        hasher.update(layouter.namespace(|| "update"), data)?;
+        hasher.finalize(layouter.namespace(|| "finalize"))
+    }
+
+    /// Convenience function to compute hash of a byte string.
+    #[allow(clippy::type_complexity)]
+    pub fn digest_bytes(
+        chip: Sha512Chip,
+        mut layouter: impl Layouter<F>,
+        data: &[Value<u8>],
+    ) -> Result<
+        Sha512_224Digest<Sha512Chip::DigestWord, Sha512Chip::AssignedWord>,
+        Sha512Error,
+    > {
+        let mut hasher = Self::new(chip, layouter.namespace(|| "init"))?;
+        hasher.update_bytes(layouter.namespace(|| "update"), data)?;
+        hasher.finalize(layouter.namespace(|| "finalize"))
+    }
+
+    /// Convenience function to compute hash of data that has already been assigned in
+    /// the circuit.
+    #[allow(clippy::type_complexity)]
+    pub fn digest_assigned(
+        chip: Sha512Chip,
+        mut layouter: impl Layouter<F>,
+        data: &[Sha512Chip::AssignedWord],
+    ) -> Result<
+        Sha512_224Digest<Sha512Chip::DigestWord, Sha512Chip::AssignedWord>,
+        Sha512Error,
+    > {
+        let mut hasher = Self::new(chip, layouter.namespace(|| "init"))?;
+        hasher.update_assigned(layouter.namespace(|| "update"), data)?;
+        hasher.finalize(layouter.namespace(|| "finalize"))
+    }
+}
@@ -0,0 +1,228 @@
+//! Chained proofs over a message too long to hash in one circuit. The message is split
+//! into chunks, and each proof resumes SHA-512 from the chaining value the proof of the
+//! previous chunk output, so that together they attest to the digest of the whole
+//! message.
+//!
+//! A verifier accepts a chain of proofs if the first starts from [`IV`], each starts
+//! from the chaining value the one before it output, and the last outputs the expected
+//! digest.
+
+use std::convert::TryInto;
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    plonk::{Advice, Circuit, Column, ConstraintSystem, Error, Instance},
+};
+
+use super::{
+    keys::CachedCircuit, native, BlockWord, MidstateWord, Sha512, Sha512Error, Table16Chip,
+    Table16Config, BLOCK_SIZE, DIGEST_SIZE, IV, WORD_BYTES,
+};
+
+/// A circuit proving one link of a chain: that hashing its chunk of the message from
+/// the chaining value in the first eight rows of the instance column yields the
+/// chaining value in the eight rows after it.
+///
+/// Every chunk but the last spans the same number of whole blocks, so that their
+/// proofs share a verifying key. The last chunk holds the rest of the message, which
+/// the circuit pads, and outputs the digest; its shape depends on the message length.
+#[derive(Clone, Debug)]
+pub struct Sha512ChunkCircuit {
+    /// The number of message bytes hashed by the chunks before this one.
+    processed_len: usize,
+    /// The bytes of the chunk.
+    chunk: Vec<Value<u8>>,
+    /// Whether the chunk ends the message.
+    is_last: bool,
+}
+
+/// Configuration for a [`Sha512ChunkCircuit`].
+#[derive(Clone, Debug)]
+pub struct Sha512ChunkConfig<F: FieldExt> {
+    table16: Table16Config<F>,
+    /// Holds the input chaining value, copied from the instance column.
+    chaining_value: Column<Advice>,
+    instance: Column<Instance>,
+}
+
+impl Sha512ChunkCircuit {
+    fn new(processed_len: usize, chunk: &[u8], is_last: bool) -> Self {
+        Sha512ChunkCircuit {
+            processed_len,
+            chunk: chunk.iter().copied().map(Value::known).collect(),
+            is_last,
+        }
+    }
+}
+
+/// The keys of a chunk circuit depend on the number of bytes in the chunk, and for the
+/// last chunk, which pads the message, also on the number of bytes hashed before it.
+impl CachedCircuit for Sha512ChunkCircuit {
+    fn shape(&self) -> String {
+        if self.is_last {
+            format!("last-p{}-c{}", self.processed_len, self.chunk.len())
+        } else {
+            format!("link-c{}", self.chunk.len())
+        }
+    }
+}
+
+impl<F: FieldExt> Circuit<F> for Sha512ChunkCircuit {
+    type Config = Sha512ChunkConfig<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        // The chunk length and, for the last chunk, the message length shape the
+        // circuit.
+        Sha512ChunkCircuit {
+            processed_len: self.processed_len,
+            chunk: vec![Value::unknown(); self.chunk.len()],
+            is_last: self.is_last,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let chaining_value = meta.advice_column();
+        meta.enable_equality(chaining_value);
+        let instance = meta.instance_column();
+        meta.enable_equality(instance);
+        Sha512ChunkConfig {
+            table16: Table16Chip::configure(meta),
+            chaining_value,
+            instance,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        Table16Chip::load(config.table16.clone(), &mut layouter)?;
+        let table16_chip = Table16Chip::construct(config.table16);
+
+        let input = layouter.assign_region(
+            || "input chaining value",
+            |mut region| {
+                (0..DIGEST_SIZE)
+                    .map(|i| {
+                        region
+                            .assign_advice_from_instance(
+                                || format!("input word {}", i),
+                                config.instance,
+                                i,
+                                config.chaining_value,
+                                i,
+                            )
+                            .map(MidstateWord::Assigned)
+                    })
+                    .collect::<Result<Vec<_>, _>>()
+            },
+        )?;
+        let mut hasher = Sha512::from_midstate(
+            table16_chip,
+            layouter.namespace(|| "resume"),
+            input.try_into().expect("input.len() == DIGEST_SIZE"),
+            self.processed_len,
+        )?;
+
+        // Whole words are witnessed as such, which spares packing them from bytes. Only
+        // the last chunk may end in a partial word.
+        let words = self.chunk.chunks_exact(WORD_BYTES);
+        let rem = words.remainder();
+        let words: Vec<_> = words
+            .map(|word| {
+                BlockWord(word.iter().fold(Value::known(0), |acc, byte| {
+                    acc.zip(*byte)
+                        .map(|(acc, byte)| (acc << 8) | u64::from(byte))
+                }))
+            })
+            .collect();
+        hasher.update(layouter.namespace(|| "chunk words"), &words)?;
+        hasher.update_bytes(layouter.namespace(|| "chunk bytes"), rem)?;
+
+        let output = if self.is_last {
+            hasher.finalize(layouter.namespace(|| "finalize"))?
+        } else {
+            hasher.midstate(layouter.namespace(|| "midstate"))?
+        };
+        output.expose_public(
+            layouter.namespace(|| "output chaining value"),
+            config.instance,
+            DIGEST_SIZE,
+        )
+    }
+}
+
+/// The public inputs of a [`Sha512ChunkCircuit`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ChainingValues {
+    /// The chaining value the chunk is hashed from.
+    pub input: [u64; DIGEST_SIZE],
+    /// The chaining value the chunk outputs, which for the last chunk is the digest.
+    pub output: [u64; DIGEST_SIZE],
+}
+
+impl ChainingValues {
+    /// Returns the instance column of the chunk's proof.
+    pub fn instance<F: FieldExt>(&self) -> Vec<F> {
+        self.input
+            .iter()
+            .chain(self.output.iter())
+            .map(|word| F::from(*word))
+            .collect()
+    }
+}
+
+/// Splits a message into chunks of `blocks` blocks, returning the circuit proving each
+/// together with its public inputs. The last chunk holds the rest of the message,
+/// which may be empty; with its padding, it spans up to `blocks + 1` blocks.
+///
+/// # Errors
+///
+/// Returns [`Sha512Error::MalformedInput`] if `blocks` is zero.
+pub fn split_message(
+    message: &[u8],
+    blocks: usize,
+) -> Result<Vec<(Sha512ChunkCircuit, ChainingValues)>, Sha512Error> {
+    if blocks == 0 {
+        return Err(Sha512Error::MalformedInput(
+            "a chunk spans at least one block",
+        ));
+    }
+    let block_bytes = BLOCK_SIZE * WORD_BYTES;
+    let chunk_len = blocks * block_bytes;
+
+    let mut links = vec![];
+    let mut state = IV;
+    let mut chunks = message.chunks_exact(chunk_len);
+    for (i, chunk) in chunks.by_ref().enumerate() {
+        let output = native::midstate(state, chunk);
+        links.push((
+            Sha512ChunkCircuit::new(i * chunk_len, chunk, false),
+            ChainingValues {
+                input: state,
+                output,
+            },
+        ));
+        state = output;
+    }
+
+    // The last chunk is hashed together with the padding of the whole message.
+    let rest = chunks.remainder();
+    let processed_len = message.len() - rest.len();
+    let digest = native::pad(message)[processed_len / block_bytes..]
+        .iter()
+        .copied()
+        .fold(state, native::compress);
+    links.push((
+        Sha512ChunkCircuit::new(processed_len, rest, true),
+        ChainingValues {
+            input: state,
+            output: digest,
+        },
+    ));
+
+    Ok(links)
+}
@@ -0,0 +1,305 @@
+//! Records the values the Table16 chip assigns while a circuit is synthesized, for
+//! debugging a circuit that fails to verify: every message schedule word and every
+//! compression round, with the region it is in and the rows it takes there. A
+//! recording can be written as CSV or JSON, and diffed against the native
+//! [`trace`](super::trace).
+//!
+//! Rows are offsets within their region, which is how `MockProver` reports the rows of
+//! failing constraints, as the floor planner only places regions once they are
+//! assigned. The message schedule of a block takes a region "process message block",
+//! and its rounds a region "compress"; the record of a word or round gives the name of
+//! its region, and the index of the block among those recorded tells which region of
+//! that name it is in.
+
+use std::cell::RefCell;
+use std::io::{self, Write};
+use std::ops::Range;
+
+use halo2_proofs::circuit::Value;
+
+use super::DIGEST_SIZE;
+
+/// The number of message schedule words and of rounds in a block.
+const ROUNDS: usize = 80;
+
+thread_local! {
+    static RECORDING: RefCell<Option<Recording>> = RefCell::new(None);
+}
+
+/// A message schedule word as assigned.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WordRecord {
+    /// The index of the block among those recorded.
+    pub block: usize,
+    /// The index t of the word W_t.
+    pub index: usize,
+    /// The word W_t.
+    pub w: u64,
+    /// The name of the region of the word, the `block`-th region of that name.
+    pub region: &'static str,
+    /// The rows of the word in its region.
+    pub rows: Range<usize>,
+}
+
+/// A compression round as assigned.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RoundRecord {
+    /// The index of the block among those recorded.
+    pub block: usize,
+    /// The index t of the round.
+    pub round: usize,
+    /// The message schedule word W_t.
+    pub w: u64,
+    /// The working variables A..H the round starts from.
+    pub state: [u64; DIGEST_SIZE],
+    /// T_1 = H + Σ_1(E) + Ch(E, F, G) + K_t + W_t.
+    pub t1: u64,
+    /// T_2 = Σ_0(A) + Maj(A, B, C).
+    pub t2: u64,
+    /// The name of the region of the round, the `block`-th region of that name.
+    pub region: &'static str,
+    /// The rows of the round in its region.
+    pub rows: Range<usize>,
+}
+
+/// The values recorded while synthesizing a circuit.
+///
+/// Only assignments whose values are known are recorded, so that the pass in which a
+/// floor planner measures a region, and key generation, record nothing.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Recording {
+    /// The message schedule words, in the order they were assigned.
+    pub words: Vec<WordRecord>,
+    /// The compression rounds, in the order they were assigned.
+    pub rounds: Vec<RoundRecord>,
+}
+
+/// Runs `synthesize`, typically `MockProver::run` on a circuit, recording the values
+/// the chip assigns on this thread meanwhile.
+pub fn record<R>(synthesize: impl FnOnce() -> R) -> (R, Recording) {
+    RECORDING.with(|recording| *recording.borrow_mut() = Some(Recording::default()));
+    let result = synthesize();
+    let recording = RECORDING.with(|recording| recording.borrow_mut().take());
+    (result, recording.expect("the recording is only taken here"))
+}
+
+/// Returns the value inside `value`, if it is known.
+fn known<T>(value: Value<T>) -> Option<T> {
+    let mut known = None;
+    value.map(|value| known = Some(value));
+    known
+}
+
+fn with_recording(f: impl FnOnce(&mut Recording)) {
+    RECORDING.with(|recording| {
+        if let Some(recording) = recording.borrow_mut().as_mut() {
+            f(recording)
+        }
+    })
+}
+
+/// Records a message schedule word assigned in the named region.
+pub(crate) fn record_word(region: &'static str, index: usize, w: Value<u64>, rows: Range<usize>) {
+    if let Some(w) = known(w) {
+        with_recording(|recording| {
+            recording.words.push(WordRecord {
+                block: recording.words.len() / ROUNDS,
+                index,
+                w,
+                region,
+                rows,
+            })
+        });
+    }
+}
+
+/// Records a compression round assigned in the named region, given the values of the
+/// state it starts from, its message schedule word, its H' (which is T_1) and the sum
+/// of its Σ_0(A) and Maj(A, B, C) cells.
+pub(crate) fn record_round(
+    region: &'static str,
+    round: usize,
+    state: [Value<u64>; DIGEST_SIZE],
+    w: Value<u64>,
+    h_prime: Value<u64>,
+    t2: Value<u64>,
+    rows: Range<usize>,
+) {
+    let state: Option<Vec<u64>> = state.into_iter().map(known).collect();
+    if let (Some(state), Some(w), Some(t1), Some(t2)) = (state, known(w), known(h_prime), known(t2))
+    {
+        with_recording(|recording| {
+            recording.rounds.push(RoundRecord {
+                block: recording.rounds.len() / ROUNDS,
+                round,
+                w,
+                state: state.try_into().expect("state.len() == DIGEST_SIZE"),
+                t1,
+                t2,
+                region,
+                rows,
+            })
+        });
+    }
+}
+
+impl Recording {
+    /// Writes the recording as CSV, one line for each word and each round. Words and
+    /// values are in hexadecimal.
+    pub fn write_csv(&self, mut writer: impl Write) -> io::Result<()> {
+        writeln!(
+            writer,
+            "kind,block,index,w,a,b,c,d,e,f,g,h,t1,t2,region,first_row,last_row"
+        )?;
+        for word in self.words.iter() {
+            writeln!(
+                writer,
+                "word,{},{},{:016x},,,,,,,,,,,{},{},{}",
+                word.block,
+                word.index,
+                word.w,
+                word.region,
+                word.rows.start,
+                word.rows.end - 1
+            )?;
+        }
+        for round in self.rounds.iter() {
+            let state: Vec<_> = round
+                .state
+                .iter()
+                .map(|word| format!("{:016x}", word))
+                .collect();
+            writeln!(
+                writer,
+                "round,{},{},{:016x},{},{:016x},{:016x},{},{},{}",
+                round.block,
+                round.round,
+                round.w,
+                state.join(","),
+                round.t1,
+                round.t2,
+                round.region,
+                round.rows.start,
+                round.rows.end - 1
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Writes the recording as a JSON object holding an array of words and an array
+    /// of rounds. Words and values are hexadecimal strings, as JSON numbers cannot
+    /// hold 64 bits exactly.
+    pub fn write_json(&self, mut writer: impl Write) -> io::Result<()> {
+        let hex = |word: &u64| format!("\"{:016x}\"", word);
+
+        writeln!(writer, "{{")?;
+        writeln!(writer, "  \"words\": [")?;
+        for (i, word) in self.words.iter().enumerate() {
+            let comma = if i + 1 < self.words.len() { "," } else { "" };
+            writeln!(
+                writer,
+                "    {{\"block\": {}, \"index\": {}, \"w\": {}, \"region\": \"{}\", \"rows\": [{}, {}]}}{}",
+                word.block,
+                word.index,
+                hex(&word.w),
+                word.region,
+                word.rows.start,
+                word.rows.end - 1,
+                comma
+            )?;
+        }
+        writeln!(writer, "  ],")?;
+        writeln!(writer, "  \"rounds\": [")?;
+        for (i, round) in self.rounds.iter().enumerate() {
+            let comma = if i + 1 < self.rounds.len() { "," } else { "" };
+            let state: Vec<_> = round.state.iter().map(hex).collect();
+            writeln!(
+                writer,
+                "    {{\"block\": {}, \"round\": {}, \"w\": {}, \"state\": [{}], \"t1\": {}, \"t2\": {}, \"region\": \"{}\", \"rows\": [{}, {}]}}{}",
+                round.block,
+                round.round,
+                hex(&round.w),
+                state.join(", "),
+                hex(&round.t1),
+                hex(&round.t2),
+                round.region,
+                round.rows.start,
+                round.rows.end - 1,
+                comma
+            )?;
+        }
+        writeln!(writer, "  ]")?;
+        writeln!(writer, "}}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{
+        native, trace::trace_block, BlockWord, Sha512, Table16Chip, Table16Config, IV,
+    };
+    use super::record;
+    use halo2_proofs::{
+        circuit::{Layouter, SimpleFloorPlanner, Value},
+        dev::MockProver,
+        halo2curves::bn256,
+        plonk::{Circuit, ConstraintSystem, Error},
+    };
+
+    /// Hashes "abc" as a single block of words.
+    struct MyCircuit {}
+
+    impl Circuit<bn256::Fr> for MyCircuit {
+        type Config = Table16Config<bn256::Fr>;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            MyCircuit {}
+        }
+
+        fn configure(meta: &mut ConstraintSystem<bn256::Fr>) -> Self::Config {
+            Table16Chip::configure(meta)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<bn256::Fr>,
+        ) -> Result<(), Error> {
+            Table16Chip::load(config.clone(), &mut layouter)?;
+            let table16_chip = Table16Chip::construct(config);
+
+            // The padding is written out, so that the message is a single block.
+            let block = native::pad(b"abc")[0].map(|word| BlockWord(Value::known(word)));
+            let mut hasher = Sha512::new(table16_chip, layouter.namespace(|| "init"))?;
+            hasher.update(layouter.namespace(|| "block"), &block)?;
+            hasher.midstate(layouter.namespace(|| "midstate"))?;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn recording_matches_trace() {
+        let (prover, recording) = record(|| MockProver::run(17, &MyCircuit {}, vec![]));
+        prover.unwrap().assert_satisfied();
+
+        let trace = trace_block(IV, native::pad(b"abc")[0]);
+        assert_eq!(recording.words.len(), 80);
+        assert_eq!(recording.rounds.len(), 80);
+        for (word, traced) in recording.words.iter().zip(trace.schedule.iter()) {
+            assert_eq!(word.w, *traced, "W_{}", word.index);
+            assert_eq!(word.region, "process message block", "W_{}", word.index);
+        }
+        for (round, traced) in recording.rounds.iter().zip(trace.rounds.iter()) {
+            let t2 = traced.upper_sigma_0.even.wrapping_add(traced.maj.odd);
+            assert_eq!(round.state, traced.state, "round {}", round.round);
+            assert_eq!(round.t1, traced.h_prime, "round {}", round.round);
+            assert_eq!(round.t2, t2, "round {}", round.round);
+            assert_eq!(round.region, "compress", "round {}", round.round);
+        }
+
+        let mut csv = vec![];
+        recording.write_csv(&mut csv).unwrap();
+        assert_eq!(String::from_utf8(csv).unwrap().lines().count(), 1 + 80 + 80);
+    }
+}
@@ -0,0 +1,242 @@
+//! Translates the failures `MockProver` reports for a circuit using the Table16 chip
+//! into the terms of the chip's layout. A failing row of the message schedule region
+//! is pinned to the word W_t whose rows it falls in, and one of the compression region
+//! to its round, so that a failure reads as "compression round 37, s_maj gate" rather
+//! than as an offset into a region of thousands of rows.
+
+use std::fmt;
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    dev::{FailureLocation, MockProver, VerifyFailure},
+};
+
+use super::table16::{
+    get_feed_forward_word, get_midstate_word, get_round_idx, get_word_idx, COMPRESS_REGION,
+    SCHEDULE_REGION,
+};
+
+/// The part of the Table16 layout a failure falls in.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Location {
+    /// The rows of the message schedule word W_t.
+    ScheduleWord(usize),
+    /// The rows of a compression round.
+    Round(usize),
+    /// The rows decomposing the state a compression starts from.
+    Initialization,
+    /// The rows witnessing a word of the intermediate hash value a compression resumes
+    /// from.
+    Midstate(usize),
+    /// The rows adding a word of the state a compression started from to its final
+    /// working variables.
+    FeedForward(usize),
+    /// A row of any other region, by the name of the region and the offset within it.
+    Region { name: String, offset: usize },
+    /// A row outside any region.
+    Row(usize),
+    /// A failure not tied to a row.
+    Unknown,
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Location::ScheduleWord(idx) => write!(f, "message schedule W[{}]", idx),
+            Location::Round(idx) => write!(f, "compression round {}", idx),
+            Location::Initialization => write!(f, "compression initialization"),
+            Location::Midstate(idx) => write!(f, "midstate word {}", idx),
+            Location::FeedForward(idx) => write!(f, "feed forward of state word {}", idx),
+            Location::Region { name, offset } => {
+                write!(f, "region '{}' at offset {}", name, offset)
+            }
+            Location::Row(row) => write!(f, "row {} outside any region", row),
+            Location::Unknown => write!(f, "unknown location"),
+        }
+    }
+}
+
+/// A verification failure in the terms of the Table16 layout.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Diagnosis {
+    /// Where the failure falls.
+    pub location: Location,
+    /// What failed, such as "s_maj gate" or "copy constraint".
+    pub check: String,
+}
+
+impl fmt::Display for Diagnosis {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}, {}", self.location, self.check)
+    }
+}
+
+/// Returns the selector enabling a gate of the chip, given the name of the gate. Most
+/// gates are named after their selector.
+fn selector(gate: &str) -> &str {
+    match gate {
+        "decompose ABCD" => "s_decompose_abcd",
+        "Decompose EFGH" => "s_decompose_efgh",
+        "s_word for W_[16..80]" => "s_word",
+        "sigma_0 v1" => "s_lower_sigma_0",
+        "sigma_0 v2" => "s_lower_sigma_0_v2",
+        "sigma_1 v1" => "s_lower_sigma_1",
+        "sigma_1 v2" => "s_lower_sigma_1_v2",
+        gate => gate,
+    }
+}
+
+/// Returns the name quoted after `prefix` in the display of a failure's metadata, which
+/// halo2 does not otherwise expose: a region displays as "Region 3 ('compress')", and a
+/// gate as "Gate 5 ('s_maj')".
+fn quoted_name(display: &str, prefix: &str) -> Option<String> {
+    let start = display.find(prefix)? + prefix.len();
+    let (_, rest) = display[start..].split_once("('")?;
+    let end = rest.find("')")?;
+    Some(rest[..end].to_string())
+}
+
+/// Locates a row of a region, given the selector of the failing gate, if any.
+fn locate(region: &str, offset: usize, selector: Option<&str>) -> Location {
+    // Region names may be prefixed by the namespaces they were assigned in.
+    let name = region.rsplit('/').next().unwrap_or(region).trim();
+    let location = match name {
+        SCHEDULE_REGION => get_word_idx(offset).map(|idx| {
+            // s_word is enabled in the rows of W_{t-16}, for the word W_t it computes.
+            if selector == Some("s_word") {
+                Location::ScheduleWord(idx + 16)
+            } else {
+                Location::ScheduleWord(idx)
+            }
+        }),
+        COMPRESS_REGION => get_round_idx(offset).map(Location::Round),
+        "initialize_with_iv" | "initialize_with_state" => Some(Location::Initialization),
+        "initialize_with_midstate" => get_midstate_word(offset).map(Location::Midstate),
+        "feed forward" => get_feed_forward_word(offset).map(Location::FeedForward),
+        _ => None,
+    };
+    location.unwrap_or_else(|| Location::Region {
+        name: region.to_string(),
+        offset,
+    })
+}
+
+fn locate_failure(location: &FailureLocation, selector: Option<&str>) -> Location {
+    match location {
+        FailureLocation::InRegion { region, offset } => {
+            match quoted_name(&region.to_string(), "Region ") {
+                Some(name) => locate(&name, *offset, selector),
+                None => Location::Unknown,
+            }
+        }
+        FailureLocation::OutsideRegion { row } => Location::Row(*row),
+    }
+}
+
+/// Translates a verification failure into the terms of the Table16 layout.
+pub fn diagnose(failure: &VerifyFailure) -> Diagnosis {
+    match failure {
+        VerifyFailure::ConstraintNotSatisfied {
+            constraint,
+            location,
+            ..
+        } => {
+            let gate = quoted_name(&constraint.to_string(), "in gate ").unwrap_or_default();
+            let selector = selector(&gate);
+            Diagnosis {
+                location: locate_failure(location, Some(selector)),
+                check: format!("{} gate", selector),
+            }
+        }
+        VerifyFailure::CellNotAssigned {
+            gate,
+            region,
+            gate_offset,
+            ..
+        } => {
+            let gate = quoted_name(&gate.to_string(), "Gate ").unwrap_or_default();
+            let selector = selector(&gate);
+            let location = match quoted_name(&region.to_string(), "Region ") {
+                Some(name) => locate(&name, *gate_offset, Some(selector)),
+                None => Location::Unknown,
+            };
+            Diagnosis {
+                location,
+                check: format!("{} gate reading an unassigned cell", selector),
+            }
+        }
+        VerifyFailure::Lookup { location, .. } => Diagnosis {
+            location: locate_failure(location, None),
+            check: "spread table lookup".to_string(),
+        },
+        VerifyFailure::Permutation { location, .. } => Diagnosis {
+            location: locate_failure(location, None),
+            check: "copy constraint".to_string(),
+        },
+        failure => Diagnosis {
+            location: Location::Unknown,
+            check: failure.to_string(),
+        },
+    }
+}
+
+/// Asserts that the circuit is satisfied, panicking with each failure and its
+/// diagnosis otherwise.
+pub fn assert_satisfied<F: FieldExt>(prover: &MockProver<F>) {
+    if let Err(failures) = prover.verify() {
+        let report: Vec<String> = failures
+            .iter()
+            .map(|failure| format!("{}:\n{}", diagnose(failure), failure))
+            .collect();
+        panic!("circuit was not satisfied:\n{}", report.join("\n"));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{locate, quoted_name, Location};
+
+    #[test]
+    fn names() {
+        let constraint = "Constraint 2 ('maj') in gate 7 ('s_maj')";
+        assert_eq!(
+            quoted_name(constraint, "in gate "),
+            Some("s_maj".to_string())
+        );
+        assert_eq!(
+            quoted_name("Region 12 ('compress')", "Region "),
+            Some("compress".to_string())
+        );
+        assert_eq!(quoted_name("Region 12", "Region "), None);
+    }
+
+    #[test]
+    fn rows() {
+        // W_52 starts on row 1025, and its σ_1 gate 14 rows below it.
+        assert_eq!(
+            locate("process message block", 1039, Some("s_lower_sigma_1_v2")),
+            Location::ScheduleWord(52)
+        );
+        // W_52 is computed by the s_word gate on the row after that of W_36.
+        assert_eq!(
+            locate("process message block", 674, Some("s_word")),
+            Location::ScheduleWord(52)
+        );
+        // Rounds take 54 rows each, the Maj gate being enabled on the 47th.
+        assert_eq!(
+            locate("compress", 37 * 54 + 46, Some("s_maj")),
+            Location::Round(37)
+        );
+        assert_eq!(
+            locate("feed forward", 13, Some("s_feed_forward")),
+            Location::FeedForward(3)
+        );
+        assert_eq!(
+            locate("compress", 80 * 54, None),
+            Location::Region {
+                name: "compress".to_string(),
+                offset: 80 * 54
+            }
+        );
+    }
+}
@@ -0,0 +1,119 @@
+//! The error returned by the gadgets of this crate, and by the helpers caching their
+//! keys.
+
+use std::fmt;
+use std::io;
+
+use halo2_proofs::plonk::{Any, Column, Error};
+
+/// An error raised while synthesizing a circuit with the SHA-512 gadgets, either by
+/// halo2 or by a gadget rejecting its input.
+///
+/// The gadgets return [`Sha512Error::MalformedInput`] and [`Sha512Error::LengthOverflow`]
+/// when they reject their input, before assigning anything for it, and the
+/// [`keys`](super::keys) cache returns [`Sha512Error::Io`].
+///
+/// The instructions of [`Table16Chip`](super::Table16Chip) return
+/// [`Sha512Error::InvalidColumn`] and [`Sha512Error::WrongLength`] when a witness does
+/// not fit the cells it is assigned to. halo2 only lets a region fail with a
+/// [`plonk::Error`](Error), so the chip holds such an error aside while halo2 unwinds
+/// the region, and returns it in place of the [`Error::Synthesis`] the region fails
+/// with.
+#[derive(Debug)]
+pub enum Sha512Error {
+    /// An error raised by halo2.
+    Synthesis(Error),
+    /// A cell was to be assigned in a column of a kind that cannot be assigned, such
+    /// as an instance column.
+    InvalidColumn(Column<Any>),
+    /// A witness was built from a different number of bits or words than it holds.
+    WrongLength {
+        /// The number of bits or words the witness holds.
+        expected: usize,
+        /// The number it was built from.
+        actual: usize,
+    },
+    /// The input to a gadget was malformed, for the reason given.
+    MalformedInput(&'static str),
+    /// The length of the message in bits overflowed.
+    LengthOverflow,
+    /// Parameters or keys could not be read or written.
+    Io(io::Error),
+}
+
+impl fmt::Display for Sha512Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Sha512Error::Synthesis(error) => write!(f, "synthesis failed: {}", error),
+            Sha512Error::InvalidColumn(column) => {
+                write!(f, "cannot assign a cell in column {:?}", column)
+            }
+            Sha512Error::WrongLength { expected, actual } => write!(
+                f,
+                "expected a witness of length {}, got one of length {}",
+                expected, actual
+            ),
+            Sha512Error::MalformedInput(reason) => write!(f, "malformed input: {}", reason),
+            Sha512Error::LengthOverflow => write!(f, "the message length overflowed"),
+            Sha512Error::Io(error) => write!(f, "i/o error: {}", error),
+        }
+    }
+}
+
+impl std::error::Error for Sha512Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Sha512Error::Synthesis(error) => Some(error),
+            Sha512Error::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<Error> for Sha512Error {
+    fn from(error: Error) -> Self {
+        Sha512Error::Synthesis(error)
+    }
+}
+
+impl From<io::Error> for Sha512Error {
+    fn from(error: io::Error) -> Self {
+        Sha512Error::Io(error)
+    }
+}
+
+/// Lets an error be returned from [`Circuit::synthesize`]. Errors other than those of
+/// halo2 become [`Error::Synthesis`], which does not carry their detail.
+///
+/// [`Circuit::synthesize`]: halo2_proofs::plonk::Circuit::synthesize
+impl From<Sha512Error> for Error {
+    fn from(error: Sha512Error) -> Self {
+        match error {
+            Sha512Error::Synthesis(error) => error,
+            _ => Error::Synthesis,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Sha512Error;
+    use halo2_proofs::plonk::Error;
+
+    #[test]
+    fn conversions() {
+        let error = Sha512Error::from(Error::BoundsFailure);
+        assert!(matches!(
+            error,
+            Sha512Error::Synthesis(Error::BoundsFailure)
+        ));
+        assert!(matches!(Error::from(error), Error::BoundsFailure));
+
+        let error = Sha512Error::MalformedInput("a midstate is taken between blocks");
+        assert_eq!(
+            error.to_string(),
+            "malformed input: a midstate is taken between blocks"
+        );
+        assert!(matches!(Error::from(error), Error::Synthesis));
+    }
+}
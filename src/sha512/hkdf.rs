@@ -0,0 +1,183 @@
+//! HKDF over the SHA-512 family, as specified in RFC 5869.
+
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{Layouter, Value},
+};
+
+use super::{
+    hmac::{Hmac, HmacInstructions, HmacVariant},
+    ByteInput, Sha512Error, Sha512TDigest, Sha512TInstructions, WORD_BYTES,
+};
+
+/// The largest number of HMAC outputs HKDF-Expand may concatenate, as the block counter
+/// is a single byte.
+const MAX_EXPAND_BLOCKS: usize = 255;
+
+/// The output keying material of [`HkdfExpand`]: the whole 64-bit words of the output,
+/// followed by the high bytes of the next word if the length is not a multiple of 8.
+pub type HkdfOutput<DigestWord, AssignedWord> = Sha512TDigest<DigestWord, AssignedWord>;
+
+/// A gadget that constrains HKDF-Extract, deriving a pseudorandom key from input keying
+/// material and a salt. The salt is the HMAC key, so an empty salt stands for the
+/// string of zeros the length of a digest that RFC 5869 substitutes for a missing one.
+#[derive(Debug)]
+pub struct HkdfExtract<F: FieldExt, CS: HmacInstructions<F>, V> {
+    mac: Hmac<F, CS, V>,
+}
+
+impl<F, HkdfChip, V> HkdfExtract<F, HkdfChip, V>
+where
+    F: FieldExt,
+    HkdfChip: HmacInstructions<F>,
+    V: HmacVariant<HkdfChip::DigestWord>,
+{
+    /// Create a new extraction instance with the given salt.
+    pub fn new(
+        chip: HkdfChip,
+        layouter: impl Layouter<F>,
+        salt: &[HkdfChip::AssignedByte],
+    ) -> Result<Self, Sha512Error> {
+        Ok(HkdfExtract {
+            mac: Hmac::new(chip, layouter, salt)?,
+        })
+    }
+
+    /// Absorb input keying material, updating the internal state.
+    pub fn update_bytes(
+        &mut self,
+        layouter: impl Layouter<F>,
+        ikm: &[Value<u8>],
+    ) -> Result<(), Sha512Error> {
+        self.mac.update_bytes(layouter, ikm)
+    }
+
+    /// Absorb input keying material that has already been assigned in the circuit,
+    /// updating the internal state.
+    pub fn update_assigned_bytes(
+        &mut self,
+        layouter: impl Layouter<F>,
+        ikm: &[HkdfChip::AssignedByte],
+    ) -> Result<(), Sha512Error> {
+        self.mac.update_assigned_bytes(layouter, ikm)
+    }
+
+    /// Retrieve the pseudorandom key and consume the extraction instance.
+    pub fn finalize(self, layouter: impl Layouter<F>) -> Result<V::Digest, Sha512Error> {
+        self.mac.finalize(layouter)
+    }
+
+    /// Convenience function to extract a pseudorandom key from assigned input keying
+    /// material.
+    pub fn extract(
+        chip: HkdfChip,
+        mut layouter: impl Layouter<F>,
+        salt: &[HkdfChip::AssignedByte],
+        ikm: &[HkdfChip::AssignedByte],
+    ) -> Result<V::Digest, Sha512Error> {
+        let mut extract = Self::new(chip, layouter.namespace(|| "init"), salt)?;
+        extract.update_assigned_bytes(layouter.namespace(|| "update"), ikm)?;
+        extract.finalize(layouter.namespace(|| "finalize"))
+    }
+}
+
+/// A gadget that constrains HKDF-Expand, stretching a pseudorandom key into output
+/// keying material bound to an `info` label. Each output block is the MAC of the
+/// previous block, the label and a block counter, keyed with the pseudorandom key.
+#[derive(Debug)]
+pub struct HkdfExpand<F: FieldExt, CS: HmacInstructions<F>, V> {
+    chip: CS,
+    prk: Vec<CS::AssignedWord>,
+    _marker: PhantomData<(F, V)>,
+}
+
+impl<F, HkdfChip, V> HkdfExpand<F, HkdfChip, V>
+where
+    F: FieldExt,
+    HkdfChip: HmacInstructions<F> + Sha512TInstructions<F>,
+    V: HmacVariant<HkdfChip::DigestWord>,
+{
+    /// Create a new expansion instance from a pseudorandom key, such as the output of
+    /// [`HkdfExtract`].
+    pub fn new(chip: HkdfChip, prk: &V::Digest) -> Self {
+        let prk = V::words(prk)
+            .iter()
+            .map(|word| chip.digest_word(word))
+            .collect();
+        HkdfExpand {
+            chip,
+            prk,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Returns `length` bytes of output keying material for the given label. The label
+    /// bytes may each be witnessed, assigned in the circuit or fixed.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Sha512Error::MalformedInput`] if `length` is zero or more than 255
+    /// digests long.
+    pub fn expand(
+        &self,
+        mut layouter: impl Layouter<F>,
+        info: &[ByteInput<HkdfChip::AssignedByte>],
+        length: usize,
+    ) -> Result<HkdfOutput<HkdfChip::DigestWord, HkdfChip::AssignedWord>, Sha512Error> {
+        // The pseudorandom key is a whole digest.
+        let digest_bytes = self.prk.len() * WORD_BYTES;
+        if length == 0 || length > MAX_EXPAND_BLOCKS * digest_bytes {
+            return Err(Sha512Error::MalformedInput(
+                "HKDF output length must be between 1 and 255 digests",
+            ));
+        }
+
+        let mut words = Vec::with_capacity(length / WORD_BYTES + 1);
+        let mut previous = vec![];
+        for counter in 1..=(length + digest_bytes - 1) / digest_bytes {
+            let mut mac = Hmac::<F, HkdfChip, V>::new_with_key_words(
+                self.chip.clone(),
+                layouter.namespace(|| format!("T({}) init", counter)),
+                &self.prk,
+            )?;
+            mac.update_assigned(
+                layouter.namespace(|| format!("T({}) previous", counter)),
+                &previous,
+            )?;
+            let label: Vec<_> = info
+                .iter()
+                .cloned()
+                .chain([ByteInput::Padding(counter as u8)])
+                .collect();
+            mac.update_byte_input(
+                layouter.namespace(|| format!("T({}) label", counter)),
+                &label,
+            )?;
+            let block = mac.finalize(layouter.namespace(|| format!("T({}) finalize", counter)))?;
+
+            previous = V::words(&block)
+                .iter()
+                .map(|word| self.chip.digest_word(word))
+                .collect();
+            words.extend(V::words(&block).iter().cloned());
+        }
+
+        // Keep the bytes of the output, truncating the word it ends in if it ends
+        // partway through one.
+        let tail_bytes = length % WORD_BYTES;
+        words.truncate((length + WORD_BYTES - 1) / WORD_BYTES);
+        let last = if tail_bytes > 0 {
+            let word = words.pop().expect("length > 0");
+            let last = self
+                .chip
+                .truncate_word(&mut layouter, &word, tail_bytes * 8)?;
+            Some(last)
+        } else {
+            None
+        };
+
+        Ok(HkdfOutput { words, last })
+    }
+}
@@ -0,0 +1,260 @@
+//! HMAC over the SHA-512 family, as specified in RFC 2104 and FIPS 198-1.
+
+use std::convert::TryInto;
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{Layouter, Value},
+};
+
+use super::{
+    BlockInput, ByteInput, Sha384Variant, Sha512, Sha512Error, Sha512FullVariant,
+    Sha512Instructions, Sha512Variant, Sha512_256Variant, BLOCK_SIZE, WORD_BYTES,
+};
+
+/// The byte the key is XORed with for the inner hash.
+pub(crate) const IPAD_BYTE: u8 = 0x36;
+/// The byte the key is XORed with for the outer hash.
+pub(crate) const OPAD_BYTE: u8 = 0x5c;
+
+/// The circuit instructions required to use the [`Hmac`] gadget.
+pub trait HmacInstructions<F: FieldExt>: Sha512Instructions<F> + Clone {
+    /// Returns the given key word XORed with the inner and outer pads, the words of
+    /// repeated `0x36` and `0x5c` bytes.
+    fn xor_pads(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        word: &Self::AssignedWord,
+    ) -> Result<(Self::AssignedWord, Self::AssignedWord), Sha512Error>;
+
+    /// Returns the cell holding the given digest word, for use as input to another hash.
+    fn digest_word(&self, word: &Self::DigestWord) -> Self::AssignedWord;
+}
+
+/// A member of the SHA-512 family whose digest is a whole number of words, so that HMAC
+/// can be instantiated with it.
+pub trait HmacVariant<DigestWord>: Sha512Variant<DigestWord> {
+    /// Returns the words of a digest.
+    fn words(digest: &Self::Digest) -> &[DigestWord];
+}
+
+impl<DigestWord> HmacVariant<DigestWord> for Sha512FullVariant {
+    fn words(digest: &Self::Digest) -> &[DigestWord] {
+        &digest.0
+    }
+}
+
+impl<DigestWord> HmacVariant<DigestWord> for Sha384Variant {
+    fn words(digest: &Self::Digest) -> &[DigestWord] {
+        &digest.0
+    }
+}
+
+impl<DigestWord> HmacVariant<DigestWord> for Sha512_256Variant {
+    fn words(digest: &Self::Digest) -> &[DigestWord] {
+        &digest.0
+    }
+}
+
+/// A gadget that constrains an HMAC invocation over a member of the SHA-512 family.
+/// The key is assigned in the circuit; keys longer than a block are hashed first.
+///
+/// The inner and outer hashes absorb their padded key blocks as soon as the MAC is
+/// keyed, so a cloned instance resumes from both midstates without recomputing them.
+#[derive(Debug)]
+pub struct Hmac<F: FieldExt, CS: HmacInstructions<F>, V> {
+    chip: CS,
+    inner: Sha512<F, CS>,
+    outer: Sha512<F, CS>,
+    _marker: PhantomData<V>,
+}
+
+impl<F: FieldExt, CS: HmacInstructions<F>, V> Clone for Hmac<F, CS, V> {
+    fn clone(&self) -> Self {
+        Hmac {
+            chip: self.chip.clone(),
+            inner: self.inner.clone(),
+            outer: self.outer.clone(),
+            _marker: PhantomData,
+        }
+    }
+}
+
+/// A gadget that constrains an HMAC-SHA512 invocation.
+pub type HmacSha512<F, CS> = Hmac<F, CS, Sha512FullVariant>;
+/// A gadget that constrains an HMAC-SHA384 invocation.
+pub type HmacSha384<F, CS> = Hmac<F, CS, Sha384Variant>;
+
+impl<F, HmacChip, V> Hmac<F, HmacChip, V>
+where
+    F: FieldExt,
+    HmacChip: HmacInstructions<F>,
+    V: HmacVariant<HmacChip::DigestWord>,
+{
+    /// Create a new MAC instance keyed with the given bytes.
+    pub fn new(
+        chip: HmacChip,
+        mut layouter: impl Layouter<F>,
+        key: &[HmacChip::AssignedByte],
+    ) -> Result<Self, Sha512Error> {
+        let key = Self::key_words(&chip, &mut layouter, key)?;
+        Self::new_with_key_words(chip, layouter, &key)
+    }
+
+    /// Create a new MAC instance keyed with words assigned in the circuit, such as the
+    /// tag of a previous MAC. The key is taken as the big-endian bytes of the words.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Sha512Error::MalformedInput`] if the key is longer than a block.
+    pub fn new_with_key_words(
+        chip: HmacChip,
+        mut layouter: impl Layouter<F>,
+        key: &[HmacChip::AssignedWord],
+    ) -> Result<Self, Sha512Error> {
+        if key.len() > BLOCK_SIZE {
+            return Err(Sha512Error::MalformedInput("key words must fit in a block"));
+        }
+
+        let mut ipad = Vec::with_capacity(BLOCK_SIZE);
+        let mut opad = Vec::with_capacity(BLOCK_SIZE);
+        for word in key.iter() {
+            let (i, o) = chip.xor_pads(&mut layouter, word)?;
+            ipad.push(BlockInput::Assigned(i));
+            opad.push(BlockInput::Assigned(o));
+        }
+        // The key is zero-padded to a block, so the pads are fixed past its end.
+        let pad_word = |pad: u8| BlockInput::Padding(u64::from_be_bytes([pad; WORD_BYTES]));
+        ipad.resize(BLOCK_SIZE, pad_word(IPAD_BYTE));
+        opad.resize(BLOCK_SIZE, pad_word(OPAD_BYTE));
+
+        let mut inner = Sha512::with_iv(chip.clone(), layouter.namespace(|| "inner init"), V::IV)?;
+        inner.update_block_input(&mut layouter, &ipad)?;
+        let mut outer = Sha512::with_iv(chip.clone(), layouter.namespace(|| "outer init"), V::IV)?;
+        outer.update_block_input(&mut layouter, &opad)?;
+
+        Ok(Hmac {
+            chip,
+            inner,
+            outer,
+            _marker: PhantomData,
+        })
+    }
+
+    /// Packs the key into words, hashing it first if it is longer than a block. Words
+    /// past the end of the key are left out.
+    fn key_words(
+        chip: &HmacChip,
+        layouter: &mut impl Layouter<F>,
+        key: &[HmacChip::AssignedByte],
+    ) -> Result<Vec<HmacChip::AssignedWord>, Sha512Error> {
+        if key.len() > BLOCK_SIZE * WORD_BYTES {
+            let mut hasher =
+                Sha512::with_iv(chip.clone(), layouter.namespace(|| "key init"), V::IV)?;
+            hasher.update_assigned_bytes(layouter.namespace(|| "key"), key)?;
+            let digest = hasher.finalize(layouter.namespace(|| "key finalize"))?;
+            let digest = V::truncate(digest.0);
+            return Ok(V::words(&digest)
+                .iter()
+                .map(|word| chip.digest_word(word))
+                .collect());
+        }
+
+        let words = key
+            .chunks(WORD_BYTES)
+            .map(|word| {
+                let word: Vec<_> = word
+                    .iter()
+                    .cloned()
+                    .map(ByteInput::Assigned)
+                    .chain(std::iter::repeat(ByteInput::Padding(0)))
+                    .take(WORD_BYTES)
+                    .collect();
+                chip.pack_bytes(
+                    layouter,
+                    word.try_into().expect("word.len() == WORD_BYTES"),
+                )
+            })
+            .collect::<Result<_, Sha512Error>>()?;
+        Ok(words)
+    }
+
+    /// MAC data, updating the internal state.
+    pub fn update(
+        &mut self,
+        layouter: impl Layouter<F>,
+        data: &[HmacChip::BlockWord],
+    ) -> Result<(), Sha512Error> {
+        self.inner.update(layouter, data)
+    }
+
+    /// MAC data that has already been assigned in the circuit, updating the internal
+    /// state.
+    pub fn update_assigned(
+        &mut self,
+        layouter: impl Layouter<F>,
+        data: &[HmacChip::AssignedWord],
+    ) -> Result<(), Sha512Error> {
+        self.inner.update_assigned(layouter, data)
+    }
+
+    /// MAC bytes, updating the internal state.
+    pub fn update_bytes(
+        &mut self,
+        layouter: impl Layouter<F>,
+        data: &[Value<u8>],
+    ) -> Result<(), Sha512Error> {
+        self.inner.update_bytes(layouter, data)
+    }
+
+    /// MAC bytes that have already been assigned in the circuit, updating the internal
+    /// state.
+    pub fn update_assigned_bytes(
+        &mut self,
+        layouter: impl Layouter<F>,
+        data: &[HmacChip::AssignedByte],
+    ) -> Result<(), Sha512Error> {
+        self.inner.update_assigned_bytes(layouter, data)
+    }
+
+    /// MAC bytes that are each witnessed, assigned in the circuit or fixed, such as a
+    /// label mixed with assigned data, updating the internal state.
+    pub fn update_byte_input(
+        &mut self,
+        mut layouter: impl Layouter<F>,
+        data: &[ByteInput<HmacChip::AssignedByte>],
+    ) -> Result<(), Sha512Error> {
+        self.inner.update_byte_input(&mut layouter, data)
+    }
+
+    /// Retrieve the tag and consume the MAC instance.
+    pub fn finalize(self, mut layouter: impl Layouter<F>) -> Result<V::Digest, Sha512Error> {
+        let inner = self
+            .inner
+            .finalize(layouter.namespace(|| "inner finalize"))?;
+        let inner = V::truncate(inner.0);
+        let inner: Vec<_> = V::words(&inner)
+            .iter()
+            .map(|word| self.chip.digest_word(word))
+            .collect();
+
+        let mut outer = self.outer;
+        outer.update_assigned(layouter.namespace(|| "outer"), &inner)?;
+        let outer = outer.finalize(layouter.namespace(|| "outer finalize"))?;
+        Ok(V::truncate(outer.0))
+    }
+
+    /// Convenience function to compute the MAC of a byte string. It will handle MAC
+    /// creation, data feeding and finalization.
+    pub fn mac_bytes(
+        chip: HmacChip,
+        mut layouter: impl Layouter<F>,
+        key: &[HmacChip::AssignedByte],
+        data: &[Value<u8>],
+    ) -> Result<V::Digest, Sha512Error> {
+        let mut mac = Self::new(chip, layouter.namespace(|| "init"), key)?;
+        mac.update_bytes(layouter.namespace(|| "update"), data)?;
+        mac.finalize(layouter.namespace(|| "finalize"))
+    }
+}
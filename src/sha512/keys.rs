@@ -0,0 +1,182 @@
+//! Writes and reads the KZG parameters and the keys of circuits using the Table16 chip,
+//! over BN254. The chip's spread table alone takes 2^16 rows, so that generating them
+//! is slow; a [`KeyCache`] generates them once and reads them from disk afterwards.
+//!
+//! Parameters and keys are written in the raw format of halo2, which is only meant to
+//! be read back by the same version of this crate and of halo2.
+
+use std::any::type_name;
+use std::fs::{create_dir_all, File};
+use std::io::{BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use halo2_proofs::{
+    halo2curves::bn256::{Bn256, Fr, G1Affine},
+    plonk::{keygen_pk, keygen_vk, Circuit, ConstraintSystem, ProvingKey, VerifyingKey},
+    poly::{commitment::Params, kzg::commitment::ParamsKZG},
+    SerdeFormat,
+};
+use rand::RngCore;
+
+use super::{native, Sha512Error, IV};
+
+/// The format parameters and keys are written in.
+const FORMAT: SerdeFormat = SerdeFormat::RawBytes;
+
+/// Writes `params` to the file at `path`.
+pub fn write_params(params: &ParamsKZG<Bn256>, path: &Path) -> Result<(), Sha512Error> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    params.write(&mut writer)?;
+    Ok(writer.flush()?)
+}
+
+/// Reads the parameters in the file at `path`.
+pub fn read_params(path: &Path) -> Result<ParamsKZG<Bn256>, Sha512Error> {
+    let mut reader = BufReader::new(File::open(path)?);
+    Ok(ParamsKZG::read(&mut reader)?)
+}
+
+/// Writes `vk` to the file at `path`.
+pub fn write_vk(vk: &VerifyingKey<G1Affine>, path: &Path) -> Result<(), Sha512Error> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    vk.write(&mut writer, FORMAT)?;
+    Ok(writer.flush()?)
+}
+
+/// Reads the verifying key of a circuit `C` in the file at `path`.
+pub fn read_vk<C: Circuit<Fr>>(path: &Path) -> Result<VerifyingKey<G1Affine>, Sha512Error> {
+    let mut reader = BufReader::new(File::open(path)?);
+    Ok(VerifyingKey::read::<_, C>(&mut reader, FORMAT)?)
+}
+
+/// Writes `pk`, which holds its verifying key, to the file at `path`.
+pub fn write_pk(pk: &ProvingKey<G1Affine>, path: &Path) -> Result<(), Sha512Error> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    pk.write(&mut writer, FORMAT)?;
+    Ok(writer.flush()?)
+}
+
+/// Reads the proving key of a circuit `C` in the file at `path`.
+pub fn read_pk<C: Circuit<Fr>>(path: &Path) -> Result<ProvingKey<G1Affine>, Sha512Error> {
+    let mut reader = BufReader::new(File::open(path)?);
+    Ok(ProvingKey::read::<_, C>(&mut reader, FORMAT)?)
+}
+
+/// Returns a fingerprint of the shape of a circuit `C`: of its type, and of the columns,
+/// gates and lookups it configures, as 16 hexadecimal digits.
+///
+/// The fingerprint does not cover the regions the circuit assigns, which for circuits
+/// using the Table16 chip depend on the number of blocks they hash; those are described
+/// by [`CachedCircuit::shape`].
+pub fn fingerprint<C: Circuit<Fr>>() -> String {
+    let mut meta = ConstraintSystem::default();
+    C::configure(&mut meta);
+    let shape = format!("{}\n{:?}", type_name::<C>(), meta.pinned());
+    format!("{:016x}", native::digest_with_iv(IV, shape.as_bytes())[0])
+}
+
+/// A circuit whose keys a [`KeyCache`] can hold. Its keys must be determined by its
+/// type, the columns, gates and lookups it configures, and its [`shape`](Self::shape).
+pub trait CachedCircuit: Circuit<Fr> {
+    /// Describes what the regions the circuit assigns depend on, such as the number of
+    /// blocks it hashes or how it lays out its public inputs, in characters fit for a
+    /// file name. Circuits whose keys differ must have different shapes.
+    fn shape(&self) -> String;
+}
+
+/// A directory caching parameters, by `k`, and the keys generated from them, by `k`,
+/// the [`shape`](CachedCircuit::shape) of the circuit and its [`fingerprint`].
+///
+/// The keys are only valid with the parameters cached beside them: deleting the
+/// parameters of a `k` calls for deleting the keys generated from them.
+#[derive(Clone, Debug)]
+pub struct KeyCache {
+    dir: PathBuf,
+}
+
+impl KeyCache {
+    /// Returns the cache in `dir`, which is created when first written to.
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        KeyCache { dir: dir.into() }
+    }
+
+    /// The directory of the cache.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// The file the parameters for circuits of 2^k rows are cached in.
+    pub fn params_path(&self, k: u32) -> PathBuf {
+        self.dir.join(format!("params-k{}.bin", k))
+    }
+
+    /// The file the verifying key of `circuit` is cached in, for circuits of 2^k rows.
+    pub fn vk_path<C: CachedCircuit>(&self, k: u32, circuit: &C) -> PathBuf {
+        self.key_path("vk", k, circuit)
+    }
+
+    /// The file the proving key of `circuit` is cached in, for circuits of 2^k rows.
+    pub fn pk_path<C: CachedCircuit>(&self, k: u32, circuit: &C) -> PathBuf {
+        self.key_path("pk", k, circuit)
+    }
+
+    fn key_path<C: CachedCircuit>(&self, kind: &str, k: u32, circuit: &C) -> PathBuf {
+        self.dir.join(format!(
+            "{}-k{}-{}-{}.bin",
+            kind,
+            k,
+            circuit.shape(),
+            fingerprint::<C>()
+        ))
+    }
+
+    /// Returns the parameters for circuits of 2^k rows, setting them up from `rng` and
+    /// caching them if they are not cached yet.
+    ///
+    /// Parameters set up from `rng` are only fit for testing; production parameters
+    /// come from a trusted setup ceremony, and can be cached with [`write_params`].
+    pub fn params(&self, k: u32, rng: impl RngCore) -> Result<ParamsKZG<Bn256>, Sha512Error> {
+        let path = self.params_path(k);
+        if path.exists() {
+            return read_params(&path);
+        }
+        let params = ParamsKZG::setup(k, rng);
+        create_dir_all(&self.dir)?;
+        write_params(&params, &path)?;
+        Ok(params)
+    }
+
+    /// Returns the verifying key of `circuit`, generating and caching it if it is not
+    /// cached yet.
+    pub fn vk<C: CachedCircuit>(
+        &self,
+        params: &ParamsKZG<Bn256>,
+        circuit: &C,
+    ) -> Result<VerifyingKey<G1Affine>, Sha512Error> {
+        let path = self.vk_path(params.k(), circuit);
+        if path.exists() {
+            return read_vk::<C>(&path);
+        }
+        let vk = keygen_vk(params, circuit)?;
+        create_dir_all(&self.dir)?;
+        write_vk(&vk, &path)?;
+        Ok(vk)
+    }
+
+    /// Returns the proving key of `circuit`, generating and caching it, and its
+    /// verifying key, if it is not cached yet.
+    pub fn pk<C: CachedCircuit>(
+        &self,
+        params: &ParamsKZG<Bn256>,
+        circuit: &C,
+    ) -> Result<ProvingKey<G1Affine>, Sha512Error> {
+        let path = self.pk_path(params.k(), circuit);
+        if path.exists() {
+            return read_pk::<C>(&path);
+        }
+        let vk = self.vk(params, circuit)?;
+        let pk = keygen_pk(params, vk, circuit)?;
+        write_pk(&pk, &path)?;
+        Ok(pk)
+    }
+}
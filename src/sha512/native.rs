@@ -0,0 +1,157 @@
+//! A native reference implementation of the SHA-512 family, used to compute values the
+//! circuit fixes at configure time, such as the SHA-512/t initial hash values.
+
+use std::convert::TryInto;
+
+use super::table16::{IV, ROUNDS, ROUND_CONSTANTS};
+use super::{BLOCK_SIZE, DIGEST_SIZE, WORD_BYTES};
+
+/// The value every IV word is XORed with before hashing the name of a SHA-512/t
+/// variant, from FIPS 180-4 section 5.3.6.
+const IV_GENERATION_MASK: u64 = 0xa5a5a5a5a5a5a5a5;
+
+/// σ_0 of the message schedule.
+pub(crate) fn lower_sigma_0(w: u64) -> u64 {
+    w.rotate_right(1) ^ w.rotate_right(8) ^ (w >> 7)
+}
+
+/// σ_1 of the message schedule.
+pub(crate) fn lower_sigma_1(w: u64) -> u64 {
+    w.rotate_right(19) ^ w.rotate_right(61) ^ (w >> 6)
+}
+
+/// Expands a message block into the message schedule W_0..W_79.
+pub(crate) fn schedule(block: [u64; BLOCK_SIZE]) -> [u64; ROUNDS] {
+    let mut w = [0u64; ROUNDS];
+    w[..BLOCK_SIZE].copy_from_slice(&block);
+    for i in BLOCK_SIZE..ROUNDS {
+        w[i] = w[i - 16]
+            .wrapping_add(lower_sigma_0(w[i - 15]))
+            .wrapping_add(w[i - 7])
+            .wrapping_add(lower_sigma_1(w[i - 2]));
+    }
+    w
+}
+
+/// Applies a compression round with round constant `k` and message schedule word `w`
+/// to the working variables a..h.
+pub(crate) fn round(state: [u64; DIGEST_SIZE], k: u64, w: u64) -> [u64; DIGEST_SIZE] {
+    let [a, b, c, d, e, f, g, h] = state;
+    let sigma_1 = e.rotate_right(14) ^ e.rotate_right(18) ^ e.rotate_right(41);
+    let ch = (e & f) ^ (!e & g);
+    let t_1 = h
+        .wrapping_add(sigma_1)
+        .wrapping_add(ch)
+        .wrapping_add(k)
+        .wrapping_add(w);
+    let sigma_0 = a.rotate_right(28) ^ a.rotate_right(34) ^ a.rotate_right(39);
+    let maj = (a & b) ^ (a & c) ^ (b & c);
+    let t_2 = sigma_0.wrapping_add(maj);
+
+    [t_1.wrapping_add(t_2), a, b, c, d.wrapping_add(t_1), e, f, g]
+}
+
+/// Processes a message block, returning the intermediate hash value
+/// H_i = H_{i-1} + (a..h).
+pub fn compress(state: [u64; DIGEST_SIZE], block: [u64; BLOCK_SIZE]) -> [u64; DIGEST_SIZE] {
+    let working = ROUND_CONSTANTS
+        .iter()
+        .zip(schedule(block))
+        .fold(state, |working, (k, w)| round(working, *k, w));
+
+    let mut out = state;
+    for (out, v) in out.iter_mut().zip(working) {
+        *out = out.wrapping_add(v);
+    }
+    out
+}
+
+/// Pads a message as specified in FIPS 180-4 section 5.1.2, splitting it into blocks.
+pub fn pad(message: &[u8]) -> Vec<[u64; BLOCK_SIZE]> {
+    let block_bytes = BLOCK_SIZE * WORD_BYTES;
+    let length = (message.len() as u128) * 8;
+
+    let mut bytes = message.to_vec();
+    bytes.push(0x80);
+    while bytes.len() % block_bytes != block_bytes - 16 {
+        bytes.push(0);
+    }
+    bytes.extend_from_slice(&length.to_be_bytes());
+
+    bytes
+        .chunks_exact(block_bytes)
+        .map(|block| {
+            let words: Vec<_> = block
+                .chunks_exact(WORD_BYTES)
+                .map(|word| u64::from_be_bytes(word.try_into().unwrap()))
+                .collect();
+            words.try_into().unwrap()
+        })
+        .collect()
+}
+
+/// Hashes whole blocks of a message prefix starting from the given IV, without
+/// padding, returning the intermediate hash value to pass to
+/// [`Sha512::from_midstate`](super::Sha512::from_midstate).
+///
+/// # Panics
+///
+/// Panics if `prefix` is not a whole number of blocks.
+pub fn midstate(iv: [u64; DIGEST_SIZE], prefix: &[u8]) -> [u64; DIGEST_SIZE] {
+    let block_bytes = BLOCK_SIZE * WORD_BYTES;
+    assert_eq!(
+        prefix.len() % block_bytes,
+        0,
+        "a midstate is taken between blocks"
+    );
+    prefix.chunks_exact(block_bytes).fold(iv, |state, block| {
+        let words: Vec<_> = block
+            .chunks_exact(WORD_BYTES)
+            .map(|word| u64::from_be_bytes(word.try_into().unwrap()))
+            .collect();
+        compress(state, words.try_into().unwrap())
+    })
+}
+
+/// Hashes a message starting from the given IV, returning the untruncated digest.
+pub fn digest_with_iv(iv: [u64; DIGEST_SIZE], message: &[u8]) -> [u64; DIGEST_SIZE] {
+    pad(message).into_iter().fold(iv, compress)
+}
+
+/// Computes the SHA-512/t initial hash value with the IV generation function of FIPS
+/// 180-4 section 5.3.6: SHA-512 of the string "SHA-512/t", started from the SHA-512 IV
+/// with every word XORed with `0xa5a5a5a5a5a5a5a5`.
+///
+/// # Panics
+///
+/// Panics if `t` is not in `1..512`, or is 384.
+pub fn sha512_t_iv(t: usize) -> [u64; DIGEST_SIZE] {
+    assert!(t > 0 && t < 512 && t != 384, "SHA-512/{} is not defined", t);
+    let iv = IV.map(|word| word ^ IV_GENERATION_MASK);
+    digest_with_iv(iv, format!("SHA-512/{}", t).as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::table16::{IV, IV_512_224, IV_512_256};
+    use super::{digest_with_iv, sha512_t_iv};
+    use sha2::{Digest, Sha512};
+
+    #[test]
+    fn digest_matches_sha2() {
+        for len in [0, 3, 111, 112, 128, 300] {
+            let message = "1".repeat(len).into_bytes();
+            let digest: Vec<u8> = digest_with_iv(IV, &message)
+                .iter()
+                .flat_map(|word| word.to_be_bytes())
+                .collect();
+            assert_eq!(digest, Sha512::digest(&message).to_vec());
+        }
+    }
+
+    #[test]
+    fn sha512_t_iv_generation() {
+        assert_eq!(sha512_t_iv(256), IV_512_256);
+        assert_eq!(sha512_t_iv(224), IV_512_224);
+    }
+}
@@ -0,0 +1,136 @@
+//! PBKDF2 over HMAC with a member of the SHA-512 family, as specified in RFC 8018
+//! section 5.2.
+
+use halo2_proofs::{arithmetic::FieldExt, circuit::Layouter};
+
+use super::{
+    hmac::{Hmac, HmacInstructions, HmacVariant},
+    ByteInput, Sha512Error, Sha512FullVariant,
+};
+
+/// The circuit instructions required to use the [`Pbkdf2`] gadget.
+pub trait Pbkdf2Instructions<F: FieldExt>: HmacInstructions<F> {
+    /// Returns the XOR of two assigned words.
+    fn xor_words(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        x: &Self::AssignedWord,
+        y: &Self::AssignedWord,
+    ) -> Result<Self::AssignedWord, Sha512Error>;
+}
+
+/// A gadget that constrains a PBKDF2 key derivation, such as the PBKDF2-HMAC-SHA512
+/// with 2048 iterations that derives a BIP39 seed from its mnemonic.
+///
+/// The iteration count is fixed when the gadget is created, and so is part of the
+/// circuit's shape. The password keys a single [`Hmac`] instance that every iteration
+/// clones, so the inner and outer key blocks are compressed once and each iteration
+/// costs one compression for each of its two hashes.
+#[derive(Debug)]
+pub struct Pbkdf2<F: FieldExt, CS: Pbkdf2Instructions<F>, V> {
+    chip: CS,
+    prf: Hmac<F, CS, V>,
+    iterations: usize,
+}
+
+/// A gadget that constrains a PBKDF2-HMAC-SHA512 key derivation.
+pub type Pbkdf2Sha512<F, CS> = Pbkdf2<F, CS, Sha512FullVariant>;
+
+impl<F, Pbkdf2Chip, V> Pbkdf2<F, Pbkdf2Chip, V>
+where
+    F: FieldExt,
+    Pbkdf2Chip: Pbkdf2Instructions<F>,
+    V: HmacVariant<Pbkdf2Chip::DigestWord>,
+{
+    /// Create a new key derivation instance for the given password and iteration
+    /// count.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Sha512Error::MalformedInput`] if `iterations` is zero.
+    pub fn new(
+        chip: Pbkdf2Chip,
+        layouter: impl Layouter<F>,
+        password: &[Pbkdf2Chip::AssignedByte],
+        iterations: usize,
+    ) -> Result<Self, Sha512Error> {
+        if iterations == 0 {
+            return Err(Sha512Error::MalformedInput(
+                "PBKDF2 needs at least one iteration",
+            ));
+        }
+        let prf = Hmac::new(chip.clone(), layouter, password)?;
+        Ok(Pbkdf2 {
+            chip,
+            prf,
+            iterations,
+        })
+    }
+
+    /// Returns the first `blocks` blocks of the derived key for the given salt, each
+    /// a digest long, as their words. The salt bytes may each be witnessed, assigned in
+    /// the circuit or fixed.
+    pub fn derive(
+        &self,
+        mut layouter: impl Layouter<F>,
+        salt: &[ByteInput<Pbkdf2Chip::AssignedByte>],
+        blocks: usize,
+    ) -> Result<Vec<Pbkdf2Chip::AssignedWord>, Sha512Error> {
+        let mut key = vec![];
+        for index in 1..=blocks {
+            // U_1 is the MAC of the salt and the big-endian block index.
+            let salt: Vec<_> = salt
+                .iter()
+                .cloned()
+                .chain((index as u32).to_be_bytes().map(ByteInput::Padding))
+                .collect();
+            let mut prf = self.prf.clone();
+            prf.update_byte_input(layouter.namespace(|| format!("T_{} salt", index)), &salt)?;
+            let mut u = self.words(prf.finalize(layouter.namespace(|| format!("U_{}_1", index)))?);
+            let mut block = u.clone();
+
+            // U_j is the MAC of U_{j-1}, and the block is the XOR of them all.
+            for iteration in 2..=self.iterations {
+                let mut prf = self.prf.clone();
+                prf.update_assigned(
+                    layouter.namespace(|| format!("U_{}_{} input", index, iteration)),
+                    &u,
+                )?;
+                u = self.words(
+                    prf.finalize(layouter.namespace(|| format!("U_{}_{}", index, iteration)))?,
+                );
+                block = block
+                    .iter()
+                    .zip(u.iter())
+                    .map(|(x, y)| self.chip.xor_words(&mut layouter, x, y))
+                    .collect::<Result<_, _>>()?;
+            }
+
+            key.extend(block);
+        }
+
+        Ok(key)
+    }
+
+    /// Returns the cells holding the words of a MAC.
+    fn words(&self, tag: V::Digest) -> Vec<Pbkdf2Chip::AssignedWord> {
+        V::words(&tag)
+            .iter()
+            .map(|word| self.chip.digest_word(word))
+            .collect()
+    }
+
+    /// Convenience function to derive a key of `blocks` digests from a password. It
+    /// will handle keying, iterating and combining the MACs.
+    pub fn derive_key(
+        chip: Pbkdf2Chip,
+        mut layouter: impl Layouter<F>,
+        password: &[Pbkdf2Chip::AssignedByte],
+        salt: &[ByteInput<Pbkdf2Chip::AssignedByte>],
+        iterations: usize,
+        blocks: usize,
+    ) -> Result<Vec<Pbkdf2Chip::AssignedWord>, Sha512Error> {
+        let pbkdf2 = Self::new(chip, layouter.namespace(|| "init"), password, iterations)?;
+        pbkdf2.derive(layouter.namespace(|| "derive"), salt, blocks)
+    }
+}
@@ -0,0 +1,226 @@
+//! A ready-made circuit proving knowledge of a preimage: "I know a message of N bytes
+//! whose SHA-512 is this digest", with the digest and N public and the message private.
+//!
+//! The message is hashed with [`Sha512::digest_var_len`], so that the circuit, and its
+//! keys, depend only on the number of blocks it can hash and not on the message. The
+//! instance column holds the digest, laid out as [`DigestLayout`] says, followed by N.
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    plonk::{Circuit, Column, ConstraintSystem, Error, Instance},
+};
+
+use super::{
+    check_var_len, keys::CachedCircuit, native, Sha512, Sha512Error, Table16Chip, Table16Config,
+    DIGEST_SIZE, IV,
+};
+
+/// How a digest is laid out in the instance column.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DigestLayout {
+    /// The eight 64-bit words of the digest.
+    Words,
+    /// The sixteen 32-bit halves of the words of the digest, the high half of each word
+    /// first, for a verifier whose own arithmetic is limited to 32 bits.
+    HalfWords,
+}
+
+impl DigestLayout {
+    /// The number of rows of the instance column the digest takes.
+    pub fn rows(&self) -> usize {
+        match self {
+            DigestLayout::Words => DIGEST_SIZE,
+            DigestLayout::HalfWords => 2 * DIGEST_SIZE,
+        }
+    }
+
+    /// The name of the layout: `words` or `half-words`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            DigestLayout::Words => "words",
+            DigestLayout::HalfWords => "half-words",
+        }
+    }
+
+    /// Returns the layout named `name`, as by [`name`](Self::name).
+    pub fn from_name(name: &str) -> Option<Self> {
+        [DigestLayout::Words, DigestLayout::HalfWords]
+            .into_iter()
+            .find(|layout| layout.name() == name)
+    }
+}
+
+/// A circuit proving that the SHA-512 digest of a private message is the digest in the
+/// instance column, and its length in bytes the row after it.
+#[derive(Clone, Debug)]
+pub struct Sha512PreimageCircuit {
+    /// The bytes of the message.
+    message: Vec<Value<u8>>,
+    /// The length of the message in bytes.
+    length: Value<u64>,
+    /// The number of blocks the circuit hashes, which bounds the message length.
+    max_blocks: usize,
+    layout: DigestLayout,
+}
+
+/// Configuration for a [`Sha512PreimageCircuit`].
+#[derive(Clone, Debug)]
+pub struct Sha512PreimageConfig<F: FieldExt> {
+    table16: Table16Config<F>,
+    instance: Column<Instance>,
+}
+
+impl Sha512PreimageCircuit {
+    /// Returns the circuit proving knowledge of `message`, sized to the blocks the
+    /// message spans once padded and exposing the digest as eight words.
+    pub fn new(message: &[u8]) -> Self {
+        let max_blocks = native::pad(message).len();
+        Sha512PreimageCircuit {
+            message: message.iter().copied().map(Value::known).collect(),
+            length: Value::known(message.len() as u64),
+            max_blocks,
+            layout: DigestLayout::Words,
+        }
+    }
+
+    /// Returns the circuit proving knowledge of `message`, sized to hash any message
+    /// spanning up to `max_blocks` blocks once padded, so that messages of different
+    /// lengths share its keys.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Sha512Error::MalformedInput`] if the message and its padding do not fit
+    /// in `max_blocks` blocks.
+    pub fn with_max_blocks(message: &[u8], max_blocks: usize) -> Result<Self, Sha512Error> {
+        check_var_len(message.len(), max_blocks)?;
+        Ok(Sha512PreimageCircuit {
+            max_blocks,
+            ..Self::new(message)
+        })
+    }
+
+    /// Returns the circuit hashing an unknown message of up to `max_blocks` blocks, with
+    /// which to generate the keys every message of that size is proven with.
+    pub fn unknown(max_blocks: usize) -> Self {
+        Sha512PreimageCircuit {
+            message: vec![],
+            length: Value::unknown(),
+            max_blocks,
+            layout: DigestLayout::Words,
+        }
+    }
+
+    /// Lays the digest out in the instance column as `layout` says.
+    pub fn with_layout(self, layout: DigestLayout) -> Self {
+        Sha512PreimageCircuit { layout, ..self }
+    }
+
+    /// The number of blocks the circuit hashes.
+    pub fn max_blocks(&self) -> usize {
+        self.max_blocks
+    }
+
+    /// How the circuit lays the digest out in the instance column.
+    pub fn layout(&self) -> DigestLayout {
+        self.layout
+    }
+}
+
+/// The keys of the circuit depend on the number of blocks it hashes and on the layout
+/// of the digest.
+impl CachedCircuit for Sha512PreimageCircuit {
+    fn shape(&self) -> String {
+        format!("b{}-{}", self.max_blocks, self.layout.name())
+    }
+}
+
+impl<F: FieldExt> Circuit<F> for Sha512PreimageCircuit {
+    type Config = Sha512PreimageConfig<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        // The message is padded to max_blocks blocks, whatever its length.
+        Sha512PreimageCircuit::unknown(self.max_blocks).with_layout(self.layout)
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let instance = meta.instance_column();
+        meta.enable_equality(instance);
+        Sha512PreimageConfig {
+            table16: Table16Chip::configure(meta),
+            instance,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        Table16Chip::load(config.table16.clone(), &mut layouter)?;
+        let table16_chip = Table16Chip::construct(config.table16);
+
+        let (digest, length) = Sha512::digest_var_len(
+            table16_chip,
+            layouter.namespace(|| "message"),
+            &self.message,
+            self.length,
+            self.max_blocks,
+        )?;
+        match self.layout {
+            DigestLayout::Words => {
+                digest.expose_public(layouter.namespace(|| "expose digest"), config.instance, 0)?
+            }
+            DigestLayout::HalfWords => digest.expose_public_halves(
+                layouter.namespace(|| "expose digest"),
+                config.instance,
+                0,
+            )?,
+        }
+        layouter.constrain_instance(length.cell(), config.instance, self.layout.rows())
+    }
+}
+
+/// The public inputs of a [`Sha512PreimageCircuit`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PreimageDigest {
+    /// The SHA-512 digest of the message.
+    pub digest: [u64; DIGEST_SIZE],
+    /// The length of the message in bytes.
+    pub length: u64,
+}
+
+impl PreimageDigest {
+    /// Hashes `message` natively.
+    pub fn of(message: &[u8]) -> Self {
+        PreimageDigest {
+            digest: native::digest_with_iv(IV, message),
+            length: message.len() as u64,
+        }
+    }
+
+    /// Returns the instance column of the proof, with the digest laid out as `layout`
+    /// says.
+    pub fn instance<F: FieldExt>(&self, layout: DigestLayout) -> Vec<F> {
+        let digest: Vec<u64> = match layout {
+            DigestLayout::Words => self.digest.to_vec(),
+            DigestLayout::HalfWords => self
+                .digest
+                .iter()
+                .flat_map(|word| [word >> 32, word & 0xffffffff])
+                .collect(),
+        };
+        digest
+            .into_iter()
+            .chain(Some(self.length))
+            .map(F::from)
+            .collect()
+    }
+}
+
+/// Returns the instance column of the proof that a [`Sha512PreimageCircuit`] knows
+/// `message`, with the digest laid out as `layout` says.
+pub fn public_inputs<F: FieldExt>(message: &[u8], layout: DigestLayout) -> Vec<F> {
+    PreimageDigest::of(message).instance(layout)
+}
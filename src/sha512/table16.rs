@@ -1,26 +1,48 @@
 use std::convert::TryInto;
 use std::marker::PhantomData;
 
-use super::Sha512Instructions;
+use super::{
+    hmac::HmacInstructions, pbkdf2::Pbkdf2Instructions, BlockInput, MidstateWord, ByteInput, Sha384Digest, Sha512Digest,
+    Sha512Error, Sha512Instructions, Sha512TDigest, Sha512TInstructions, Sha512VarLenInstructions,
+    Sha512_256Digest, WORD_BYTES,
+};
 use halo2_proofs::{
+    arithmetic::FieldExt,
     circuit::{AssignedCell, Chip, Layouter, Region, Value},
-    halo2curves::bn256,
-    plonk::{Advice, Any, Assigned, Column, ConstraintSystem, Error},
+    plonk::{Advice, Any, Assigned, Column, ConstraintSystem, Error, Instance},
 };
 
+mod byte_packing;
 mod compression;
 mod gates;
 mod message_schedule;
 mod spread_table;
+mod truncate;
 mod util;
+mod var_len;
+mod word_bits;
+mod xor;
+mod xor_pad;
 
+use byte_packing::*;
 use compression::*;
 use gates::*;
 use message_schedule::*;
 use spread_table::*;
+use truncate::*;
 use util::*;
+use var_len::*;
+use word_bits::*;
+use xor::*;
+use xor_pad::*;
+
+pub use compression::State;
+pub(crate) use compression::{
+    get_feed_forward_word, get_midstate_word, get_round_idx, COMPRESS_REGION,
+};
+pub(crate) use message_schedule::{get_word_idx, SCHEDULE_REGION};
 
-const ROUNDS: usize = 80;
+pub(crate) const ROUNDS: usize = 80;
 const STATE: usize = 8;
 
 #[allow(clippy::unreadable_literal)]
@@ -54,11 +76,59 @@ pub const IV: [u64; STATE] = [
     0x5be0cd19137e2179,
 ];
 
+/// The SHA-384 initial hash value, from FIPS 180-4 section 5.3.4.
+pub const IV_384: [u64; STATE] = [
+    0xcbbb9d5dc1059ed8,
+    0x629a292a367cd507,
+    0x9159015a3070dd17,
+    0x152fecd8f70e5939,
+    0x67332667ffc00b31,
+    0x8eb44a8768581511,
+    0xdb0c2e0d64f98fa7,
+    0x47b5481dbefa4fa4,
+];
+
+/// The SHA-512/256 initial hash value, from FIPS 180-4 section 5.3.6.2.
+pub const IV_512_256: [u64; STATE] = [
+    0x22312194fc2bf72c,
+    0x9f555fa3c84c64c2,
+    0x2393b86b6f53b151,
+    0x963877195940eabd,
+    0x96283ee2a88effe3,
+    0xbe5e1e2553863992,
+    0x2b0199fc2c85b8aa,
+    0x0eb72ddc81c52ca2,
+];
+
+/// The SHA-512/224 initial hash value, from FIPS 180-4 section 5.3.6.1.
+pub const IV_512_224: [u64; STATE] = [
+    0x8c3d37c819544da2,
+    0x73e1996689dcd4d6,
+    0x1dfab7ae32ff9c82,
+    0x679dd514582f9fcf,
+    0x0f6d2b697bd44da8,
+    0x77e36f7304c48942,
+    0x3f9d85a86a1d36c8,
+    0x1112e6ad91d692a1,
+];
+
 #[derive(Clone, Copy, Debug, Default)]
 /// A word in a `Table16` message block.
 // TODO: Make the internals of this struct private.
 pub struct BlockWord(pub Value<u64>);
 
+/// A 64-bit word assigned elsewhere in the circuit, for use as `Table16` message input.
+///
+/// The cell is copy-constrained into the message schedule, where it is decomposed and
+/// range-checked like any other message word.
+pub type AssignedWord<F> = AssignedCell<F, F>;
+
+/// A byte assigned elsewhere in the circuit, for use as `Table16` message input.
+///
+/// The cell is copy-constrained into the byte packing region, where it is range-checked
+/// to 8 bits.
+pub type AssignedByte<F> = AssignedCell<F, F>;
+
 #[derive(Clone, Debug)]
 /// Little-endian bits (up to 64 bits)
 pub struct Bits<const LEN: usize>([bool; LEN]);
@@ -89,10 +159,10 @@ impl<const LEN: usize> From<&Bits<LEN>> for [bool; LEN] {
     }
 }
 
-impl<const LEN: usize> From<&Bits<LEN>> for Assigned<bn256::Fr> {
-    fn from(bits: &Bits<LEN>) -> Assigned<bn256::Fr> {
+impl<F: FieldExt, const LEN: usize> From<&Bits<LEN>> for Assigned<F> {
+    fn from(bits: &Bits<LEN>) -> Assigned<F> {
         assert!(LEN <= 64);
-        bn256::Fr::from(lebs2ip(&bits.0) as u64).into()
+        F::from(lebs2ip(&bits.0) as u64).into()
     }
 }
 impl From<&Bits<16>> for u16 {
@@ -126,169 +196,296 @@ impl From<u64> for Bits<64> {
     }
 }    
 
+/// A cell holding `LEN` little-endian bits, such as a half of a [`DigestWord`].
 #[derive(Clone, Debug)]
-pub struct AssignedBits<const LEN: usize>(AssignedCell<Bits<LEN>, bn256::Fr>);
+pub struct AssignedBits<F: FieldExt, const LEN: usize>(AssignedCell<Bits<LEN>, F>);
 
-impl<const LEN: usize> std::ops::Deref for AssignedBits<LEN> {
-    type Target = AssignedCell<Bits<LEN>, bn256::Fr>;
+impl<F: FieldExt, const LEN: usize> std::ops::Deref for AssignedBits<F, LEN> {
+    type Target = AssignedCell<Bits<LEN>, F>;
 
     fn deref(&self) -> &Self::Target {
         &self.0
     }
 }
 
-impl<const LEN: usize> AssignedBits<LEN> {
-    fn assign_bits<A, AR, T: TryInto<[bool; LEN]> + std::fmt::Debug + Clone>(
-        region: &mut Region<'_, bn256::Fr>,
+impl<F: FieldExt, const LEN: usize> AssignedBits<F, LEN> {
+    fn assign_bits<A, AR, T: TryInto<[bool; LEN]> + AsRef<[bool]>>(
+        region: &mut Region<'_, F>,
         annotation: A,
         column: impl Into<Column<Any>>,
         offset: usize,
         value: Value<T>,
-    ) -> Result<Self, Error>
+    ) -> Result<Self, Sha512Error>
     where
         A: Fn() -> AR,
         AR: Into<String>,
-        <T as TryInto<[bool; LEN]>>::Error: std::fmt::Debug,
     {
-        let value: Value<[bool; LEN]> = value.map(|v| v.try_into().unwrap());
+        let value: Value<[bool; LEN]> = transpose_result(value.map(|v| {
+            let actual = v.as_ref().len();
+            v.try_into().map_err(|_| Sha512Error::WrongLength {
+                expected: LEN,
+                actual,
+            })
+        }))?;
         let value: Value<Bits<LEN>> = value.map(|v| v.into());
 
         let column: Column<Any> = column.into();
-        match column.column_type() {
+        let cell = match column.column_type() {
             Any::Advice(_) => {
-                region.assign_advice(annotation, column.try_into().unwrap(), offset, || {
-                    value.clone()
-                })
+                let column = column.try_into().map_err(|_| Sha512Error::InvalidColumn(column))?;
+                region.assign_advice(annotation, column, offset, || value.clone())?
             }
             Any::Fixed => {
-                region.assign_fixed(annotation, column.try_into().unwrap(), offset, || {
-                    value.clone()
-                })
+                let column = column.try_into().map_err(|_| Sha512Error::InvalidColumn(column))?;
+                region.assign_fixed(annotation, column, offset, || value.clone())?
             }
-            _ => panic!("Cannot assign to instance column"),
-        }
-        .map(AssignedBits)
+            Any::Instance => return Err(Sha512Error::InvalidColumn(column)),
+        };
+        Ok(AssignedBits(cell))
     }
 }
 #[allow(dead_code)]
-impl AssignedBits<16> {
+impl<F: FieldExt> AssignedBits<F, 16> {
     fn value_u16(&self) -> Value<u16> {
         self.value().map(|v| v.into())
     }
     fn assign<A, AR>(
-        region: &mut Region<'_, bn256::Fr>,
+        region: &mut Region<'_, F>,
         annotation: A,
         column: impl Into<Column<Any>>,
         offset: usize,
         value: Value<u16>,
-    ) -> Result<Self, Error>
+    ) -> Result<Self, Sha512Error>
     where
         A: Fn() -> AR,
         AR: Into<String>,
     {
         let column: Column<Any> = column.into();
         let value: Value<Bits<16>> = value.map(|v| v.into());
-        match column.column_type() {
+        let cell = match column.column_type() {
             Any::Advice(_) => {
-                region.assign_advice(annotation, column.try_into().unwrap(), offset, || {
-                    value.clone()
-                })
+                let column = column.try_into().map_err(|_| Sha512Error::InvalidColumn(column))?;
+                region.assign_advice(annotation, column, offset, || value.clone())?
             }
             Any::Fixed => {
-                region.assign_fixed(annotation, column.try_into().unwrap(), offset, || {
-                    value.clone()
-                })
+                let column = column.try_into().map_err(|_| Sha512Error::InvalidColumn(column))?;
+                region.assign_fixed(annotation, column, offset, || value.clone())?
             }
-            _ => panic!("Cannot assign to instance column"),
-        }
-        .map(AssignedBits)
+            Any::Instance => return Err(Sha512Error::InvalidColumn(column)),
+        };
+        Ok(AssignedBits(cell))
     }
 }
-impl AssignedBits<32> {
+impl<F: FieldExt> AssignedBits<F, 32> {
     fn value_u32(&self) -> Value<u32> {
         self.value().map(|v| v.into())
     }
     fn assign<A, AR>(
-        region: &mut Region<'_, bn256::Fr>,
+        region: &mut Region<'_, F>,
         annotation: A,
         column: impl Into<Column<Any>>,
         offset: usize,
         value: Value<u32>,
-    ) -> Result<Self, Error>
+    ) -> Result<Self, Sha512Error>
     where
         A: Fn() -> AR,
         AR: Into<String>,
     {
         let column: Column<Any> = column.into();
         let value: Value<Bits<32>> = value.map(|v| v.into());
-        match column.column_type() {
+        let cell = match column.column_type() {
             Any::Advice(_) => {
-                region.assign_advice(annotation, column.try_into().unwrap(), offset, || {
-                    value.clone()
-                })
+                let column = column.try_into().map_err(|_| Sha512Error::InvalidColumn(column))?;
+                region.assign_advice(annotation, column, offset, || value.clone())?
             }
             Any::Fixed => {
-                region.assign_fixed(annotation, column.try_into().unwrap(), offset, || {
-                    value.clone()
-                })
+                let column = column.try_into().map_err(|_| Sha512Error::InvalidColumn(column))?;
+                region.assign_fixed(annotation, column, offset, || value.clone())?
             }
-            _ => panic!("Cannot assign to instance column"),
-        }
-        .map(AssignedBits)
+            Any::Instance => return Err(Sha512Error::InvalidColumn(column)),
+        };
+        Ok(AssignedBits(cell))
     }
 }
-impl AssignedBits<64> {
+impl<F: FieldExt> AssignedBits<F, 64> {
     fn value_u64(&self) -> Value<u64> {
         self.value().map(|v| v.into())
     }
     fn assign<A, AR>(
-        region: &mut Region<'_, bn256::Fr>,
+        region: &mut Region<'_, F>,
         annotation: A,
         column: impl Into<Column<Any>>,
         offset: usize,
         value: Value<u64>,
-    ) -> Result<Self, Error>
+    ) -> Result<Self, Sha512Error>
     where
         A: Fn() -> AR,
         AR: Into<String>,
     {
         let column: Column<Any> = column.into();
         let value: Value<Bits<64>> = value.map(|v| v.into());
-        match column.column_type() {
+        let cell = match column.column_type() {
             Any::Advice(_) => {
-                region.assign_advice(annotation, column.try_into().unwrap(), offset, || {
-                    value.clone()
-                })
+                let column = column.try_into().map_err(|_| Sha512Error::InvalidColumn(column))?;
+                region.assign_advice(annotation, column, offset, || value.clone())?
             }
             Any::Fixed => {
-                region.assign_fixed(annotation, column.try_into().unwrap(), offset, || {
-                    value.clone()
-                })
+                let column = column.try_into().map_err(|_| Sha512Error::InvalidColumn(column))?;
+                region.assign_fixed(annotation, column, offset, || value.clone())?
             }
-            _ => panic!("Cannot assign to instance column"),
+            Any::Instance => return Err(Sha512Error::InvalidColumn(column)),
+        };
+        Ok(AssignedBits(cell))
+    }
+}
+
+
+
+/// A 64-bit word of a SHA-512 digest, together with the cells holding it.
+#[derive(Clone, Debug)]
+pub struct DigestWord<F: FieldExt> {
+    word: AssignedCell<F, F>,
+    lo: AssignedBits<F, 32>,
+    hi: AssignedBits<F, 32>,
+}
+
+impl<F: FieldExt> DigestWord<F> {
+    /// The value of this word.
+    pub fn value(&self) -> Value<u64> {
+        self.lo
+            .value_u32()
+            .zip(self.hi.value_u32())
+            .map(|(lo, hi)| lo as u64 + (1 << 32) * hi as u64)
+    }
+
+    /// The cell holding the full 64-bit word.
+    pub fn word(&self) -> &AssignedCell<F, F> {
+        &self.word
+    }
+
+    /// The cell holding the low 32 bits of the word.
+    pub fn lo(&self) -> &AssignedBits<F, 32> {
+        &self.lo
+    }
+
+    /// The cell holding the high 32 bits of the word.
+    pub fn hi(&self) -> &AssignedBits<F, 32> {
+        &self.hi
+    }
+}
+
+impl<F: FieldExt> Sha512Digest<DigestWord<F>> {
+    /// Exposes the digest as public inputs, constraining its eight 64-bit words to
+    /// consecutive rows of `instance` starting at `row`.
+    pub fn expose_public(
+        &self,
+        mut layouter: impl Layouter<F>,
+        instance: Column<Instance>,
+        row: usize,
+    ) -> Result<(), Error> {
+        for (i, word) in self.0.iter().enumerate() {
+            layouter.constrain_instance(word.word.cell(), instance, row + i)?;
+        }
+        Ok(())
+    }
+
+    /// Exposes the digest as public inputs, constraining the high and then the low 32
+    /// bits of each of its eight words to consecutive rows of `instance` starting at
+    /// `row`. The sixteen rows read as the digest bytes in big-endian 32-bit chunks.
+    pub fn expose_public_halves(
+        &self,
+        mut layouter: impl Layouter<F>,
+        instance: Column<Instance>,
+        row: usize,
+    ) -> Result<(), Error> {
+        // Each half is range-checked to 32 bits and tied to its word by the digest
+        // gate.
+        for (i, word) in self.0.iter().enumerate() {
+            layouter.constrain_instance(word.hi.cell(), instance, row + 2 * i)?;
+            layouter.constrain_instance(word.lo.cell(), instance, row + 2 * i + 1)?;
         }
-        .map(AssignedBits)
+        Ok(())
     }
 }
 
+impl<F: FieldExt> Sha384Digest<DigestWord<F>> {
+    /// Exposes the digest as public inputs, constraining its six 64-bit words to
+    /// consecutive rows of `instance` starting at `row`.
+    pub fn expose_public(
+        &self,
+        mut layouter: impl Layouter<F>,
+        instance: Column<Instance>,
+        row: usize,
+    ) -> Result<(), Error> {
+        for (i, word) in self.0.iter().enumerate() {
+            layouter.constrain_instance(word.word.cell(), instance, row + i)?;
+        }
+        Ok(())
+    }
+}
 
+impl<F: FieldExt> Sha512_256Digest<DigestWord<F>> {
+    /// Exposes the digest as public inputs, constraining its four 64-bit words to
+    /// consecutive rows of `instance` starting at `row`.
+    pub fn expose_public(
+        &self,
+        mut layouter: impl Layouter<F>,
+        instance: Column<Instance>,
+        row: usize,
+    ) -> Result<(), Error> {
+        for (i, word) in self.0.iter().enumerate() {
+            layouter.constrain_instance(word.word.cell(), instance, row + i)?;
+        }
+        Ok(())
+    }
+}
+
+impl<F: FieldExt> Sha512TDigest<DigestWord<F>, AssignedWord<F>> {
+    /// Exposes the digest as public inputs, constraining its whole 64-bit words and then
+    /// its truncated last word, if any, to consecutive rows of `instance` starting at
+    /// `row`. For SHA-512/224, these are three words and the high 32 bits of the fourth.
+    pub fn expose_public(
+        &self,
+        mut layouter: impl Layouter<F>,
+        instance: Column<Instance>,
+        row: usize,
+    ) -> Result<(), Error> {
+        let cells = self
+            .words
+            .iter()
+            .map(|word| word.word.cell())
+            .chain(self.last.iter().map(|last| last.cell()));
+        for (i, cell) in cells.enumerate() {
+            layouter.constrain_instance(cell, instance, row + i)?;
+        }
+        Ok(())
+    }
+}
 
 /// Configuration for a [`Table16Chip`].
 #[derive(Clone, Debug)]
-pub struct Table16Config {
+pub struct Table16Config<F: FieldExt> {
     lookup: SpreadTableConfig,
-    message_schedule: MessageScheduleConfig,
-    compression: CompressionConfig,
+    message_schedule: MessageScheduleConfig<F>,
+    compression: CompressionConfig<F>,
+    byte_packing: BytePackingConfig<F>,
+    var_len: VarLenConfig<F>,
+    truncate: TruncateConfig<F>,
+    xor_pad: XorPadConfig<F>,
+    xor: XorConfig<F>,
 }
 /// A chip that implements SHA-512 with a maximum lookup table size of $2^16$.
+///
+/// The chip works over any prime field of at least 130 bits, so that sums of the
+/// 64-bit words and their spread forms in its gates never wrap around the modulus.
 #[derive(Clone, Debug)]
-pub struct Table16Chip {
-    config: Table16Config,
-    _marker: PhantomData<bn256::Fr>,
+pub struct Table16Chip<F: FieldExt> {
+    config: Table16Config<F>,
+    _marker: PhantomData<F>,
 }
 
-impl Chip<bn256::Fr> for Table16Chip {
-    type Config = Table16Config;
+impl<F: FieldExt> Chip<F> for Table16Chip<F> {
+    type Config = Table16Config<F>;
     type Loaded = ();
 
     fn config(&self) -> &Self::Config {
@@ -301,9 +498,9 @@ impl Chip<bn256::Fr> for Table16Chip {
 }
 
 
-impl Table16Chip {
+impl<F: FieldExt> Table16Chip<F> {
     /// Reconstructs this chip from the given config.
-    pub fn construct(config: <Self as Chip<bn256::Fr>>::Config) -> Self {
+    pub fn construct(config: <Self as Chip<F>>::Config) -> Self {
         Self {
             config,
             _marker: PhantomData,
@@ -311,9 +508,19 @@ impl Table16Chip {
     }
 
     /// Configures a circuit to include this chip.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the field has fewer than 130 bits.
     pub fn configure(
-        meta: &mut ConstraintSystem<bn256::Fr>,
-    ) -> <Self as Chip<bn256::Fr>>::Config {
+        meta: &mut ConstraintSystem<F>,
+    ) -> <Self as Chip<F>>::Config {
+        assert!(
+            F::NUM_BITS >= 130,
+            "the Table16 chip needs a field of at least 130 bits, got one of {}",
+            F::NUM_BITS
+        );
+
         // Columns required by this chip:
         let message_schedule = meta.advice_column();
         let extras = [
@@ -350,9 +557,23 @@ impl Table16Chip {
             meta.enable_equality(*column);
         }
 
+        // - One fixed column to hold constants such as padding words.
+        let constants = meta.fixed_column();
+        meta.enable_constant(constants);
+
         let compression =
             CompressionConfig::configure(meta, lookup_inputs.clone(), message_schedule, extras);
 
+        let byte_packing = BytePackingConfig::configure(meta, message_schedule, extras);
+
+        let var_len = VarLenConfig::configure(meta, extras);
+
+        let truncate = TruncateConfig::configure(meta, extras);
+
+        let xor_pad = XorPadConfig::configure(meta, extras);
+
+        let xor = XorConfig::configure(meta, extras);
+
         let message_schedule =
             MessageScheduleConfig::configure(meta, lookup_inputs, message_schedule, extras);
 
@@ -360,34 +581,53 @@ impl Table16Chip {
             lookup,
             message_schedule,
             compression,
+            byte_packing,
+            var_len,
+            truncate,
+            xor_pad,
+            xor,
         }
     }
 
     /// Loads the lookup table required by this chip into the circuit.
     pub fn load(
-        config: Table16Config,
-        layouter: &mut impl Layouter<bn256::Fr>,
+        config: Table16Config<F>,
+        layouter: &mut impl Layouter<F>,
     ) -> Result<(), Error> {
         SpreadTableChip::load(config.lookup, layouter)
     }
 }
 
-impl Sha512Instructions<bn256::Fr> for Table16Chip {
-    type State = State;
+impl<F: FieldExt> Sha512Instructions<F> for Table16Chip<F> {
+    type State = State<F>;
     type BlockWord = BlockWord;
+    type AssignedWord = AssignedWord<F>;
+    type AssignedByte = AssignedByte<F>;
+    type DigestWord = DigestWord<F>;
 
     fn initialization_vector(
         &self,
-        layouter: &mut impl Layouter<bn256::Fr>,
-    ) -> Result<State, Error> {
-        self.config().compression.initialize_with_iv(layouter, IV)
+        layouter: &mut impl Layouter<F>,
+        iv: [u64; super::DIGEST_SIZE],
+    ) -> Result<State<F>, Sha512Error> {
+        self.config().compression.initialize_with_iv(layouter, iv)
+    }
+
+    fn initialization_from_midstate(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        midstate: [MidstateWord<Self::AssignedWord>; super::DIGEST_SIZE],
+    ) -> Result<State<F>, Sha512Error> {
+        self.config()
+            .compression
+            .initialize_with_midstate(layouter, midstate)
     }
 
     fn initialization(
         &self,
-        layouter: &mut impl Layouter<bn256::Fr>,
+        layouter: &mut impl Layouter<F>,
         init_state: &Self::State,
-    ) -> Result<Self::State, Error> {
+    ) -> Result<Self::State, Sha512Error> {
         self.config()
             .compression
             .initialize_with_state(layouter, init_state.clone())
@@ -397,10 +637,19 @@ impl Sha512Instructions<bn256::Fr> for Table16Chip {
     // message block and return the final state.
     fn compress(
         &self,
-        layouter: &mut impl Layouter<bn256::Fr>,
+        layouter: &mut impl Layouter<F>,
         initialized_state: &Self::State,
         input: [Self::BlockWord; super::BLOCK_SIZE],
-    ) -> Result<Self::State, Error> {
+    ) -> Result<Self::State, Sha512Error> {
+        self.compress_block(layouter, initialized_state, input.map(BlockInput::Message))
+    }
+
+    fn compress_block(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        initialized_state: &Self::State,
+        input: [BlockInput<Self::BlockWord, Self::AssignedWord>; super::BLOCK_SIZE],
+    ) -> Result<Self::State, Sha512Error> {
         let config = self.config();
         let (_, w_halves) = config.message_schedule.process(layouter, input)?;
         config
@@ -408,11 +657,19 @@ impl Sha512Instructions<bn256::Fr> for Table16Chip {
             .compress(layouter, initialized_state.clone(), w_halves)
     }
 
+    fn pack_bytes(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        bytes: [ByteInput<Self::AssignedByte>; WORD_BYTES],
+    ) -> Result<Self::AssignedWord, Sha512Error> {
+        self.config().byte_packing.pack(layouter, bytes)
+    }
+
     fn digest(
         &self,
-        layouter: &mut impl Layouter<bn256::Fr>,
+        layouter: &mut impl Layouter<F>,
         state: &Self::State,
-    ) -> Result<[Self::BlockWord; super::DIGEST_SIZE], Error> {
+    ) -> Result<[Self::DigestWord; super::DIGEST_SIZE], Sha512Error> {
         // Copy the dense forms of the state variable chunks down to this gate.
         // Reconstruct the 64-bit dense words.
         self.config().compression.digest(layouter, state.clone())
@@ -420,35 +677,101 @@ impl Sha512Instructions<bn256::Fr> for Table16Chip {
 }
 
 
+impl<F: FieldExt> Sha512VarLenInstructions<F> for Table16Chip<F> {
+    type Flag = AssignedCell<F, F>;
+
+    fn pad_var_len(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        bytes: &[Value<u8>],
+        length: Value<u64>,
+    ) -> Result<(Vec<Self::AssignedByte>, Self::AssignedWord, Vec<Self::Flag>), Sha512Error> {
+        self.config().var_len.pad(layouter, bytes, length)
+    }
+
+    fn append_length(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        word: &Self::AssignedWord,
+        is_last: &Self::Flag,
+        length: &Self::AssignedWord,
+    ) -> Result<Self::AssignedWord, Sha512Error> {
+        self.config()
+            .var_len
+            .append_length(layouter, word, is_last, length)
+    }
+
+    fn select_digest(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        digests: &[[Self::DigestWord; super::DIGEST_SIZE]],
+        is_last: &[Self::Flag],
+    ) -> Result<[Self::DigestWord; super::DIGEST_SIZE], Sha512Error> {
+        self.config().var_len.select_digest(layouter, digests, is_last)
+    }
+}
+
+impl<F: FieldExt> Sha512TInstructions<F> for Table16Chip<F> {
+    fn truncate_word(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        word: &Self::DigestWord,
+        bits: usize,
+    ) -> Result<Self::AssignedWord, Sha512Error> {
+        self.config().truncate.truncate(layouter, word, bits)
+    }
+}
+
+impl<F: FieldExt> HmacInstructions<F> for Table16Chip<F> {
+    fn xor_pads(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        word: &Self::AssignedWord,
+    ) -> Result<(Self::AssignedWord, Self::AssignedWord), Sha512Error> {
+        self.config().xor_pad.xor_pads(layouter, word)
+    }
+
+    fn digest_word(&self, word: &Self::DigestWord) -> Self::AssignedWord {
+        word.word.clone()
+    }
+}
+
+impl<F: FieldExt> Pbkdf2Instructions<F> for Table16Chip<F> {
+    fn xor_words(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        x: &Self::AssignedWord,
+        y: &Self::AssignedWord,
+    ) -> Result<Self::AssignedWord, Sha512Error> {
+        self.config().xor.xor(layouter, x, y)
+    }
+}
+
 /// Common assignment patterns used by Table16 regions.
-trait Table16Assignment {
-    fn joindense(x :&SpreadVar<16,32>,y : &SpreadVar<16,32>) -> Value<[bool; 32]> {
+trait Table16Assignment<F: FieldExt> {
+    fn joindense(x :&SpreadVar<F, 16,32>,y : &SpreadVar<F, 16,32>) -> Value<[bool; 32]> {
         x
         .dense
         .value()
         .zip(y.dense.value())
         .map(|(x, y)| {
-            x.iter()
-                .chain(y.iter())
-                .copied()
-                .collect::<Vec<_>>()
-                .try_into()
-                .unwrap()
+            let mut joined = [false; 32];
+            joined[..16].copy_from_slice(&x[..]);
+            joined[16..].copy_from_slice(&y[..]);
+            joined
         })
     }
     
-    fn joinspread(x :&SpreadVar<16,32>,y : &SpreadVar<16,32>) -> Value<[bool; 64]> {
+    fn joinspread(x :&SpreadVar<F, 16,32>,y : &SpreadVar<F, 16,32>) -> Value<[bool; 64]> {
         x
         .spread
         .value()
         .zip(y.spread.value())
         .map(|(x, y)| {
-            x.iter()
-                .chain(y.iter())
-                .copied()
-                .collect::<Vec<_>>()
-                .try_into()
-                .unwrap()
+            let mut joined = [false; 64];
+            joined[..32].copy_from_slice(&x[..]);
+            joined[32..].copy_from_slice(&y[..]);
+            joined
         })
     }
     /// Assign cells for general spread computation used in sigma, ch, ch_neg, maj gates
@@ -456,7 +779,7 @@ trait Table16Assignment {
     #[allow(clippy::type_complexity)]
     fn assign_spread_outputs(
         &self,
-        region: &mut Region<'_, bn256::Fr>,
+        region: &mut Region<'_, F>,
         lookup: &SpreadInputs,
         a_3: Column<Advice>,
         row: usize,
@@ -466,15 +789,17 @@ trait Table16Assignment {
         r_1_odd: Value<[bool; 32]>,
     ) -> Result<
         (
-            (AssignedBits<32>, AssignedBits<32>),
-            (AssignedBits<32>, AssignedBits<32>),
+            (AssignedBits<F, 32>, AssignedBits<F, 32>),
+            (AssignedBits<F, 32>, AssignedBits<F, 32>),
         ),
-        Error,
+        Sha512Error,
     > {
 
          // Lookup R_0^{even}, R_0^{odd}, R_1^{even}, R_1^{odd}
-        let r_0_even_lo: Value<[bool; 16]> = r_0_even.map(|r_0_even| r_0_even[..16].try_into().unwrap());
-        let r_0_even_hi: Value<[bool; 16]> = r_0_even.map(|r_0_even| r_0_even[16..32].try_into().unwrap());
+        let r_0_even_lo: Value<[bool; 16]> =
+            transpose_result(r_0_even.map(|r_0_even| try_into_bits(&r_0_even[..16])))?;
+        let r_0_even_hi: Value<[bool; 16]> =
+            transpose_result(r_0_even.map(|r_0_even| try_into_bits(&r_0_even[16..32])))?;
        
         let r_0_even_lo = SpreadVar::with_lookup(
             region,
@@ -490,10 +815,12 @@ trait Table16Assignment {
         )?;
 
         let r_0_even_dense = Self::joindense(&r_0_even_lo,&r_0_even_hi);
-        let r_0_even_d = AssignedBits::<32>::assign_bits(region, || "r_0_even_d", a_3, row + 2, r_0_even_dense)?;
+        let r_0_even_d = AssignedBits::<F, 32>::assign_bits(region, || "r_0_even_d", a_3, row + 2, r_0_even_dense)?;
 
-        let r_0_odd_lo: Value<[bool; 16]> = r_0_odd.map(|r_0_odd| r_0_odd[..16].try_into().unwrap());
-        let r_0_odd_hi: Value<[bool; 16]> = r_0_odd.map(|r_0_odd| r_0_odd[16..32].try_into().unwrap());
+        let r_0_odd_lo: Value<[bool; 16]> =
+            transpose_result(r_0_odd.map(|r_0_odd| try_into_bits(&r_0_odd[..16])))?;
+        let r_0_odd_hi: Value<[bool; 16]> =
+            transpose_result(r_0_odd.map(|r_0_odd| try_into_bits(&r_0_odd[16..32])))?;
        
         let r_0_odd_lo = SpreadVar::with_lookup(
             region,
@@ -509,10 +836,12 @@ trait Table16Assignment {
         )?;
 
         let r_0_odd_dense = Self::joindense(&r_0_odd_lo,&r_0_odd_hi);
-        let r_0_odd_d = AssignedBits::<32>::assign_bits(region, || "r_0_odd_d", a_3, row + 3, r_0_odd_dense)?;
+        let r_0_odd_d = AssignedBits::<F, 32>::assign_bits(region, || "r_0_odd_d", a_3, row + 3, r_0_odd_dense)?;
 
-        let r_1_even_lo: Value<[bool; 16]> = r_1_even.map(|r_1_even| r_1_even[..16].try_into().unwrap());
-        let r_1_even_hi: Value<[bool; 16]> = r_1_even.map(|r_1_even| r_1_even[16..32].try_into().unwrap());
+        let r_1_even_lo: Value<[bool; 16]> =
+            transpose_result(r_1_even.map(|r_1_even| try_into_bits(&r_1_even[..16])))?;
+        let r_1_even_hi: Value<[bool; 16]> =
+            transpose_result(r_1_even.map(|r_1_even| try_into_bits(&r_1_even[16..32])))?;
        
         let r_1_even_lo = SpreadVar::with_lookup(
             region,
@@ -528,10 +857,12 @@ trait Table16Assignment {
         )?;
 
         let r_1_even_dense = Self::joindense(&r_1_even_lo,&r_1_even_hi);
-        let r_1_even_d = AssignedBits::<32>::assign_bits(region, || "r_1_even_d", a_3, row + 4, r_1_even_dense)?;
+        let r_1_even_d = AssignedBits::<F, 32>::assign_bits(region, || "r_1_even_d", a_3, row + 4, r_1_even_dense)?;
 
-        let r_1_odd_lo: Value<[bool; 16]> = r_1_odd.map(|r_1_odd| r_1_odd[..16].try_into().unwrap());
-        let r_1_odd_hi: Value<[bool; 16]> = r_1_odd.map(|r_1_odd| r_1_odd[16..32].try_into().unwrap());
+        let r_1_odd_lo: Value<[bool; 16]> =
+            transpose_result(r_1_odd.map(|r_1_odd| try_into_bits(&r_1_odd[..16])))?;
+        let r_1_odd_hi: Value<[bool; 16]> =
+            transpose_result(r_1_odd.map(|r_1_odd| try_into_bits(&r_1_odd[16..32])))?;
        
         let r_1_odd_lo = SpreadVar::with_lookup(
             region,
@@ -547,10 +878,10 @@ trait Table16Assignment {
         )?;
 
         let r_1_odd_dense = Self::joindense(&r_1_odd_lo,&r_1_odd_hi);
-        let r_1_odd_d = AssignedBits::<32>::assign_bits(region, || "r_1_odd_d", a_3, row + 5, r_1_odd_dense)?;
+        let r_1_odd_d = AssignedBits::<F, 32>::assign_bits(region, || "r_1_odd_d", a_3, row + 5, r_1_odd_dense)?;
 
         let r_1_odd_spread = Self::joinspread(&r_1_odd_lo,&r_1_odd_hi);
-        let _r_1_odd_s = AssignedBits::<64>::assign_bits(region, || "r_1_odd_d", a_3, row + 6, r_1_odd_spread)?;
+        let _r_1_odd_s = AssignedBits::<F, 64>::assign_bits(region, || "r_1_odd_d", a_3, row + 6, r_1_odd_spread)?;
 
 
 
@@ -564,7 +895,7 @@ trait Table16Assignment {
     #[allow(clippy::too_many_arguments)]
     fn assign_sigma_outputs(
         &self,
-        region: &mut Region<'_, bn256::Fr>,
+        region: &mut Region<'_, F>,
         lookup: &SpreadInputs,
         a_3: Column<Advice>,
         row: usize,
@@ -572,7 +903,7 @@ trait Table16Assignment {
         r_0_odd: Value<[bool; 32]>,
         r_1_even: Value<[bool; 32]>,
         r_1_odd: Value<[bool; 32]>,
-    ) -> Result<(AssignedBits<32>, AssignedBits<32>), Error> {
+    ) -> Result<(AssignedBits<F, 32>, AssignedBits<F, 32>), Sha512Error> {
         let (even, _odd) = self.assign_spread_outputs(
             region, lookup, a_3, row, r_0_even, r_0_odd, r_1_even, r_1_odd,
         )?;
@@ -587,6 +918,7 @@ mod tests {
     use super::super::{Sha512, BLOCK_SIZE};
     use super::{message_schedule::msg_schedule_test_input, Table16Chip, Table16Config};
     use halo2_proofs::{
+        arithmetic::FieldExt,
         circuit::{Layouter, SimpleFloorPlanner},
         halo2curves::bn256,
         dev::MockProver,
@@ -598,22 +930,22 @@ mod tests {
         use plotters::prelude::*;
         struct MyCircuit {}
 
-        impl Circuit<bn256::Fr> for MyCircuit {
-            type Config = Table16Config;
+        impl<F: FieldExt> Circuit<F> for MyCircuit {
+            type Config = Table16Config<F>;
             type FloorPlanner = SimpleFloorPlanner;
 
             fn without_witnesses(&self) -> Self {
                 MyCircuit {}
             }
 
-            fn configure(meta: &mut ConstraintSystem<bn256::Fr>) -> Self::Config {
+            fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
                 Table16Chip::configure(meta)
             }
 
             fn synthesize(
                 &self,
                 config: Self::Config,
-                mut layouter: impl Layouter<bn256::Fr>,
+                mut layouter: impl Layouter<F>,
             ) -> Result<(), Error> {
                 let table16_chip = Table16Chip::construct(config.clone());
                 Table16Chip::load(config, &mut layouter)?;
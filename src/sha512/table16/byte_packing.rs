@@ -0,0 +1,179 @@
+use super::{
+    super::{ByteInput, WORD_BYTES},
+    assign_region, AssignedByte, AssignedWord, Gate, Sha512Error,
+};
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{Layouter, Region, Value},
+    plonk::{Advice, Column, ConstraintSystem, Constraints, Error, Expression, Selector},
+    poly::Rotation,
+};
+use std::marker::PhantomData;
+
+/// Packs bytes into the 64-bit words consumed by the message schedule.
+///
+/// Each word occupies [`WORD_BYTES`] rows. Byte `i` of the word (in big-endian order)
+/// is placed on row `i` alongside its (3, 3, 2)-bit decomposition, and the packed word
+/// is placed on the first row:
+///
+/// | a_3    | a_4   | a_5  | a_6   | a_7   |
+/// |--------|-------|------|-------|-------|
+/// | byte_0 | c_0   | word | c_1   | c_2   |
+/// | byte_1 | c_0   |      | c_1   | c_2   |
+/// | ...    | ...   |      | ...   | ...   |
+/// | byte_7 | c_0   |      | c_1   | c_2   |
+#[derive(Clone, Debug)]
+pub(super) struct BytePackingConfig<F: FieldExt> {
+    extras: [Column<Advice>; 6],
+    message_schedule: Column<Advice>,
+
+    /// Range-checks a byte to 8 bits.
+    s_byte: Selector,
+    /// Packs eight bytes into a big-endian 64-bit word.
+    s_pack: Selector,
+
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> BytePackingConfig<F> {
+    pub(super) fn configure(
+        meta: &mut ConstraintSystem<F>,
+        message_schedule: Column<Advice>,
+        extras: [Column<Advice>; 6],
+    ) -> Self {
+        let s_byte = meta.selector();
+        let s_pack = meta.selector();
+
+        // Rename these here for ease of matching the gates to the layout.
+        let a_3 = extras[0];
+        let a_4 = extras[1];
+        let a_5 = message_schedule;
+        let a_6 = extras[2];
+        let a_7 = extras[3];
+
+        // byte = c_0 + 2^3 c_1 + 2^6 c_2, with c_0 and c_1 3-bit and c_2 2-bit chunks.
+        meta.create_gate("s_byte", |meta| {
+            let s_byte = meta.query_selector(s_byte);
+            let byte = meta.query_advice(a_3, Rotation::cur());
+            let c_0 = meta.query_advice(a_4, Rotation::cur());
+            let c_1 = meta.query_advice(a_6, Rotation::cur());
+            let c_2 = meta.query_advice(a_7, Rotation::cur());
+
+            let check = c_0.clone()
+                + c_1.clone() * F::from(1 << 3)
+                + c_2.clone() * F::from(1 << 6)
+                - byte;
+
+            Constraints::with_selector(
+                s_byte,
+                [
+                    ("byte_decomposition", check),
+                    ("range_check_c_0", Gate::range_check(c_0, 0, (1 << 3) - 1)),
+                    ("range_check_c_1", Gate::range_check(c_1, 0, (1 << 3) - 1)),
+                    ("range_check_c_2", Gate::range_check(c_2, 0, (1 << 2) - 1)),
+                ],
+            )
+        });
+
+        // word = sum_i byte_i * 2^(8 * (7 - i))
+        meta.create_gate("s_pack", |meta| {
+            let s_pack = meta.query_selector(s_pack);
+            let word = meta.query_advice(a_5, Rotation::cur());
+            let zero = Expression::Constant(F::from(0));
+            let packed = (0..WORD_BYTES).fold(zero, |acc, i| {
+                let byte = meta.query_advice(a_3, Rotation(i as i32));
+                acc * F::from(1 << 8) + byte
+            });
+
+            Constraints::with_selector(s_pack, Some(("pack", packed - word)))
+        });
+
+        BytePackingConfig {
+            extras,
+            message_schedule,
+            s_byte,
+            s_pack,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Packs the given bytes, most significant first, into a 64-bit word.
+    pub(super) fn pack(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        bytes: [ByteInput<AssignedByte<F>>; WORD_BYTES],
+    ) -> Result<AssignedWord<F>, Sha512Error> {
+        assign_region(
+            layouter,
+            || "pack bytes",
+            |mut region| {
+                let bytes = bytes
+                    .iter()
+                    .enumerate()
+                    .map(|(row, byte)| self.assign_byte(&mut region, row, byte))
+                    .collect::<Result<Vec<_>, Error>>()?;
+
+                self.s_pack.enable(&mut region, 0)?;
+                let word = bytes.iter().fold(Value::known(0u64), |acc, byte| {
+                    acc.zip(*byte).map(|(acc, byte)| (acc << 8) + byte as u64)
+                });
+                Ok(region.assign_advice(
+                    || "word",
+                    self.message_schedule,
+                    0,
+                    || word.map(F::from),
+                )?)
+            },
+        )
+    }
+
+    /// Assigns a byte and its decomposition on the given row, returning its value.
+    fn assign_byte(
+        &self,
+        region: &mut Region<'_, F>,
+        row: usize,
+        byte: &ByteInput<AssignedByte<F>>,
+    ) -> Result<Value<u8>, Error> {
+        let a_3 = self.extras[0];
+        let a_4 = self.extras[1];
+        let a_6 = self.extras[2];
+        let a_7 = self.extras[3];
+
+        self.s_byte.enable(region, row)?;
+
+        let value = match byte {
+            ByteInput::Message(byte) => {
+                region.assign_advice(
+                    || "byte",
+                    a_3,
+                    row,
+                    || byte.map(|byte| F::from(byte as u64)),
+                )?;
+                *byte
+            }
+            ByteInput::Assigned(cell) => {
+                let cell = cell.copy_advice(|| "byte", region, a_3, row)?;
+                cell.value().map(|v| v.get_lower_128() as u8)
+            }
+            // Padding is not chosen by the prover, so pin it to the constant.
+            ByteInput::Padding(byte) => {
+                region.assign_advice_from_constant(
+                    || "padding byte",
+                    a_3,
+                    row,
+                    F::from(*byte as u64),
+                )?;
+                Value::known(*byte)
+            }
+        };
+
+        let chunk = |shift: u8, mask: u8| {
+            value.map(|byte| F::from(((byte >> shift) & mask) as u64))
+        };
+        region.assign_advice(|| "c_0", a_4, row, || chunk(0, 0b111))?;
+        region.assign_advice(|| "c_1", a_6, row, || chunk(3, 0b111))?;
+        region.assign_advice(|| "c_2", a_7, row, || chunk(6, 0b11))?;
+
+        Ok(value)
+    }
+}
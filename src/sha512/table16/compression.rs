@@ -1,26 +1,33 @@
 use super::{
-    super::DIGEST_SIZE,
-    util::{i2lebsp, lebs2ip},
-    AssignedBits, BlockWord, SpreadInputs, SpreadVar, Table16Assignment, ROUNDS, STATE,
+    super::{MidstateWord, DIGEST_SIZE},
+    util::{assign_region, i2lebsp, lebs2ip},
+    AssignedBits, AssignedWord, DigestWord, Sha512Error, SpreadInputs, SpreadVar, Table16Assignment,
+    ROUNDS, STATE,
 };
 
 use halo2_proofs::{
-    circuit::{Layouter, Value},
-    halo2curves::bn256,
-    plonk::{Advice, Column, ConstraintSystem, Error, Selector},
+    arithmetic::FieldExt,
+    circuit::{Layouter, Region, Value},
+    plonk::{Advice, Column, ConstraintSystem, Selector},
     poly::Rotation,
 };
 
 use std::convert::TryInto;
+use std::marker::PhantomData;
 use std::ops::Range;
 
 mod compression_gates;
 mod compression_util;
 mod subregion_digest;
+mod subregion_feed_forward;
 mod subregion_initial;
 mod subregion_main;
 
 use compression_gates::CompressionGate;
+pub(crate) use compression_util::{get_feed_forward_word, get_midstate_word, get_round_idx};
+
+/// The name of the region the rounds of a compression are assigned in.
+pub(crate) const COMPRESS_REGION: &str = "compress";
 
 pub trait UpperSigmaVar<
     const A_LEN: usize,
@@ -82,18 +89,18 @@ pub trait UpperSigmaVar<
 ///   We align the columns to make it efficient to copy-constrain these forms where they
 ///   are needed.
 #[derive(Clone, Debug)]
-pub struct AbcdVar {
-    a_lo: SpreadVar<14, 28>,
-    a_hi: SpreadVar<14, 28>,
-    b_lo: SpreadVar<3,6>,
-    b_hi: SpreadVar<3,6>,
-    c_lo: SpreadVar<2, 4>,
-    c_hi: SpreadVar<3, 6>,
-    d_lo: SpreadVar<14, 28>,
-    d_hi: SpreadVar<11, 22>,
+pub struct AbcdVar<F: FieldExt> {
+    a_lo: SpreadVar<F, 14, 28>,
+    a_hi: SpreadVar<F, 14, 28>,
+    b_lo: SpreadVar<F, 3,6>,
+    b_hi: SpreadVar<F, 3,6>,
+    c_lo: SpreadVar<F, 2, 4>,
+    c_hi: SpreadVar<F, 3, 6>,
+    d_lo: SpreadVar<F, 14, 28>,
+    d_hi: SpreadVar<F, 11, 22>,
 }
 
-impl AbcdVar {
+impl<F: FieldExt> AbcdVar<F> {
     fn a_lo_range() -> Range<usize> {
         0..14
     }
@@ -139,7 +146,7 @@ impl AbcdVar {
     }
 }
 
-impl UpperSigmaVar<56,12,10,50> for AbcdVar {
+impl<F: FieldExt> UpperSigmaVar<56,12,10,50> for AbcdVar<F> {
     fn spread_a(&self) -> Value<[bool; 56]> {
         self.a_lo
         .spread
@@ -212,17 +219,17 @@ impl UpperSigmaVar<56,12,10,50> for AbcdVar {
 ///   We align the columns to make it efficient to copy-constrain these forms where they
 ///   are needed.
 #[derive(Clone, Debug)]
-pub struct EfghVar {
-    a: SpreadVar<14, 28>,
-    b_lo: SpreadVar<2, 4>,
-    b_hi: SpreadVar<2, 4>,
-    c_lo: SpreadVar<13, 26>,
-    c_hi: SpreadVar<10, 20>,
-    d_lo: SpreadVar<13, 26>,
-    d_hi: SpreadVar<10, 20>,
+pub struct EfghVar<F: FieldExt> {
+    a: SpreadVar<F, 14, 28>,
+    b_lo: SpreadVar<F, 2, 4>,
+    b_hi: SpreadVar<F, 2, 4>,
+    c_lo: SpreadVar<F, 13, 26>,
+    c_hi: SpreadVar<F, 10, 20>,
+    d_lo: SpreadVar<F, 13, 26>,
+    d_hi: SpreadVar<F, 10, 20>,
 }
 
-impl EfghVar {
+impl<F: FieldExt> EfghVar<F> {
     fn a_range() -> Range<usize> {
         0..14
     }
@@ -262,7 +269,7 @@ impl EfghVar {
         ]
     }
 }
-impl UpperSigmaVar<28, 8, 46, 46> for EfghVar {
+impl<F: FieldExt> UpperSigmaVar<28, 8, 46, 46> for EfghVar<F> {
     fn spread_a(&self) -> Value<[bool; 28]> {
         self.a.spread.value().map(|v| v.0)
     }
@@ -314,15 +321,15 @@ impl UpperSigmaVar<28, 8, 46, 46> for EfghVar {
 }
 
 #[derive(Clone, Debug)]
-pub struct RoundWordDense(AssignedBits<32>, AssignedBits<32>);
+pub struct RoundWordDense<F: FieldExt>(AssignedBits<F, 32>, AssignedBits<F, 32>);
 
-impl From<(AssignedBits<32>, AssignedBits<32>)> for RoundWordDense {
-    fn from(halves: (AssignedBits<32>, AssignedBits<32>)) -> Self {
+impl<F: FieldExt> From<(AssignedBits<F, 32>, AssignedBits<F, 32>)> for RoundWordDense<F> {
+    fn from(halves: (AssignedBits<F, 32>, AssignedBits<F, 32>)) -> Self {
         Self(halves.0, halves.1)
     }
 }
 
-impl RoundWordDense {
+impl<F: FieldExt> RoundWordDense<F> {
     pub fn value(&self) -> Value<u64> {
         self.0
             .value_u32()
@@ -332,15 +339,15 @@ impl RoundWordDense {
 }
 
 #[derive(Clone, Debug)]
-pub struct RoundWordSpread(AssignedBits<64>, AssignedBits<64>);
+pub struct RoundWordSpread<F: FieldExt>(AssignedBits<F, 64>, AssignedBits<F, 64>);
 
-impl From<(AssignedBits<64>, AssignedBits<64>)> for RoundWordSpread {
-    fn from(halves: (AssignedBits<64>, AssignedBits<64>)) -> Self {
+impl<F: FieldExt> From<(AssignedBits<F, 64>, AssignedBits<F, 64>)> for RoundWordSpread<F> {
+    fn from(halves: (AssignedBits<F, 64>, AssignedBits<F, 64>)) -> Self {
         Self(halves.0, halves.1)
     }
 }
 
-impl RoundWordSpread {
+impl<F: FieldExt> RoundWordSpread<F> {
     pub fn value(&self) -> Value<u128> {
         self.0
             .value_u64()
@@ -351,17 +358,17 @@ impl RoundWordSpread {
 
 
 #[derive(Clone, Debug)]
-pub struct RoundWordA {
-    pieces: Option<AbcdVar>,
-    dense_halves: RoundWordDense,
-    spread_halves: Option<RoundWordSpread>,
+pub struct RoundWordA<F: FieldExt> {
+    pieces: Option<AbcdVar<F>>,
+    dense_halves: RoundWordDense<F>,
+    spread_halves: Option<RoundWordSpread<F>>,
 }
 
-impl RoundWordA {
+impl<F: FieldExt> RoundWordA<F> {
     pub fn new(
-        pieces: AbcdVar,
-        dense_halves: RoundWordDense,
-        spread_halves: RoundWordSpread,
+        pieces: AbcdVar<F>,
+        dense_halves: RoundWordDense<F>,
+        spread_halves: RoundWordSpread<F>,
     ) -> Self {
         RoundWordA {
             pieces: Some(pieces),
@@ -370,7 +377,7 @@ impl RoundWordA {
         }
     }
 
-    pub fn new_dense(dense_halves: RoundWordDense) -> Self {
+    pub fn new_dense(dense_halves: RoundWordDense<F>) -> Self {
         RoundWordA {
             pieces: None,
             dense_halves,
@@ -380,17 +387,17 @@ impl RoundWordA {
 }
 
 #[derive(Clone, Debug)]
-pub struct RoundWordE {
-    pieces: Option<EfghVar>,
-    dense_halves: RoundWordDense,
-    spread_halves: Option<RoundWordSpread>,
+pub struct RoundWordE<F: FieldExt> {
+    pieces: Option<EfghVar<F>>,
+    dense_halves: RoundWordDense<F>,
+    spread_halves: Option<RoundWordSpread<F>>,
 }
 
-impl RoundWordE {
+impl<F: FieldExt> RoundWordE<F> {
     pub fn new(
-        pieces: EfghVar,
-        dense_halves: RoundWordDense,
-        spread_halves: RoundWordSpread,
+        pieces: EfghVar<F>,
+        dense_halves: RoundWordDense<F>,
+        spread_halves: RoundWordSpread<F>,
     ) -> Self {
         RoundWordE {
             pieces: Some(pieces),
@@ -399,7 +406,7 @@ impl RoundWordE {
         }
     }
 
-    pub fn new_dense(dense_halves: RoundWordDense) -> Self {
+    pub fn new_dense(dense_halves: RoundWordDense<F>) -> Self {
         RoundWordE {
             pieces: None,
             dense_halves,
@@ -410,45 +417,52 @@ impl RoundWordE {
 
 
 #[derive(Clone, Debug)]
-pub struct RoundWord {
-    dense_halves: RoundWordDense,
-    spread_halves: RoundWordSpread,
+pub struct RoundWord<F: FieldExt> {
+    dense_halves: RoundWordDense<F>,
+    spread_halves: Option<RoundWordSpread<F>>,
 }
 
-impl RoundWord {
-    pub fn new(dense_halves: RoundWordDense, spread_halves: RoundWordSpread) -> Self {
+impl<F: FieldExt> RoundWord<F> {
+    pub fn new(dense_halves: RoundWordDense<F>, spread_halves: RoundWordSpread<F>) -> Self {
         RoundWord {
             dense_halves,
-            spread_halves,
+            spread_halves: Some(spread_halves),
+        }
+    }
+
+    pub fn new_dense(dense_halves: RoundWordDense<F>) -> Self {
+        RoundWord {
+            dense_halves,
+            spread_halves: None,
         }
     }
 }
 
 /// The internal state for SHA-512.
 #[derive(Clone, Debug)]
-pub struct State {
-    a: Option<StateWord>,
-    b: Option<StateWord>,
-    c: Option<StateWord>,
-    d: Option<StateWord>,
-    e: Option<StateWord>,
-    f: Option<StateWord>,
-    g: Option<StateWord>,
-    h: Option<StateWord>,
+pub struct State<F: FieldExt> {
+    a: Option<StateWord<F>>,
+    b: Option<StateWord<F>>,
+    c: Option<StateWord<F>>,
+    d: Option<StateWord<F>>,
+    e: Option<StateWord<F>>,
+    f: Option<StateWord<F>>,
+    g: Option<StateWord<F>>,
+    h: Option<StateWord<F>>,
 }
 
-impl State {
+impl<F: FieldExt> State<F> {
     #[allow(clippy::many_single_char_names)]
     #[allow(clippy::too_many_arguments)]
     pub fn new(
-        a: StateWord,
-        b: StateWord,
-        c: StateWord,
-        d: StateWord,
-        e: StateWord,
-        f: StateWord,
-        g: StateWord,
-        h: StateWord,
+        a: StateWord<F>,
+        b: StateWord<F>,
+        c: StateWord<F>,
+        d: StateWord<F>,
+        e: StateWord<F>,
+        f: StateWord<F>,
+        g: StateWord<F>,
+        h: StateWord<F>,
     ) -> Self {
         State {
             a: Some(a),
@@ -474,23 +488,39 @@ impl State {
             h: None,
         }
     }
+
+    /// The values of the working variables A..H.
+    #[cfg(any(test, feature = "dev-trace"))]
+    fn values(&self) -> [Value<u64>; STATE] {
+        let (a, b, c, d, e, f, g, h) = compression_util::match_state(self.clone());
+        [
+            a.dense_halves.value(),
+            b.dense_halves.value(),
+            c.dense_halves.value(),
+            d.value(),
+            e.dense_halves.value(),
+            f.dense_halves.value(),
+            g.dense_halves.value(),
+            h.value(),
+        ]
+    }
 }
 
 #[derive(Clone, Debug)]
-pub enum StateWord {
-    A(RoundWordA),
-    B(RoundWord),
-    C(RoundWord),
-    D(RoundWordDense),
-    E(RoundWordE),
-    F(RoundWord),
-    G(RoundWord),
-    H(RoundWordDense),
+pub enum StateWord<F: FieldExt> {
+    A(RoundWordA<F>),
+    B(RoundWord<F>),
+    C(RoundWord<F>),
+    D(RoundWordDense<F>),
+    E(RoundWordE<F>),
+    F(RoundWord<F>),
+    G(RoundWord<F>),
+    H(RoundWordDense<F>),
 }
 
 
 #[derive(Clone, Debug)]
-pub(super) struct CompressionConfig {
+pub(super) struct CompressionConfig<F: FieldExt> {
     lookup: SpreadInputs,
     message_schedule: Column<Advice>,
     extras: [Column<Advice>; 6],
@@ -511,13 +541,17 @@ pub(super) struct CompressionConfig {
     s_decompose_efgh: Selector,
 
     s_digest: Selector,
+    s_feed_forward: Selector,
+    s_midstate: Selector,
+
+    _marker: PhantomData<F>,
 }
 
-impl Table16Assignment for CompressionConfig {}
+impl<F: FieldExt> Table16Assignment<F> for CompressionConfig<F> {}
 
-impl CompressionConfig {
+impl<F: FieldExt> CompressionConfig<F> {
     pub(super) fn configure(
-        meta: &mut ConstraintSystem<bn256::Fr>,
+        meta: &mut ConstraintSystem<F>,
         lookup: SpreadInputs,
         message_schedule: Column<Advice>,
         extras: [Column<Advice>; 6],
@@ -538,6 +572,8 @@ impl CompressionConfig {
         let s_decompose_efgh = meta.selector();
 
         let s_digest = meta.selector();
+        let s_feed_forward = meta.selector();
+        let s_midstate = meta.selector();
 
         // Rename these here for ease of matching the gates to the specification.
         let a_0 = lookup.tag;
@@ -975,6 +1011,51 @@ impl CompressionConfig {
             )
         });
 
+        // s_feed_forward after the final round
+        meta.create_gate("s_feed_forward", |meta| {
+            let s_feed_forward = meta.query_selector(s_feed_forward);
+            let prev_lo = meta.query_advice(a_3, Rotation::cur());
+            let prev_hi = meta.query_advice(a_3, Rotation::next());
+            let cur_lo = meta.query_advice(a_4, Rotation::cur());
+            let cur_hi = meta.query_advice(a_4, Rotation::next());
+            let out_lo = meta.query_advice(a_5, Rotation::cur());
+            let out_hi = meta.query_advice(a_5, Rotation::next());
+            let carry = meta.query_advice(a_6, Rotation::cur());
+            let out_lo_lo = meta.query_advice(a_1, Rotation::cur()); // 16-bit chunk
+            let out_lo_hi = meta.query_advice(a_1, Rotation::next()); // 16-bit chunk
+            let out_hi_lo = meta.query_advice(a_1, Rotation(2)); // 16-bit chunk
+            let out_hi_hi = meta.query_advice(a_1, Rotation(3)); // 16-bit chunk
+
+            CompressionGate::s_feed_forward(
+                s_feed_forward,
+                prev_lo,
+                prev_hi,
+                cur_lo,
+                cur_hi,
+                out_lo,
+                out_hi,
+                carry,
+                out_lo_lo,
+                out_lo_hi,
+                out_hi_lo,
+                out_hi_hi,
+            )
+        });
+
+        // s_midstate to take a word of an external intermediate hash value
+        meta.create_gate("s_midstate", |meta| {
+            let s_midstate = meta.query_selector(s_midstate);
+            let word = meta.query_advice(a_4, Rotation::cur());
+            let lo = meta.query_advice(a_3, Rotation::cur());
+            let hi = meta.query_advice(a_3, Rotation::next());
+            let lo_lo = meta.query_advice(a_1, Rotation::cur()); // 16-bit chunk
+            let lo_hi = meta.query_advice(a_1, Rotation::next()); // 16-bit chunk
+            let hi_lo = meta.query_advice(a_1, Rotation(2)); // 16-bit chunk
+            let hi_hi = meta.query_advice(a_1, Rotation(3)); // 16-bit chunk
+
+            CompressionGate::s_midstate(s_midstate, word, lo, hi, lo_lo, lo_hi, hi_lo, hi_hi)
+        });
+
         CompressionConfig {
             lookup,
             message_schedule,
@@ -990,6 +1071,9 @@ impl CompressionConfig {
             s_decompose_abcd,
             s_decompose_efgh,
             s_digest,
+            s_feed_forward,
+            s_midstate,
+            _marker: PhantomData,
         }
     }
 
@@ -997,11 +1081,12 @@ impl CompressionConfig {
     /// Returns an initialized state.
     pub(super) fn initialize_with_iv(
         &self,
-        layouter: &mut impl Layouter<bn256::Fr>,
+        layouter: &mut impl Layouter<F>,
         init_state: [u64; STATE],
-    ) -> Result<State, Error> {
+    ) -> Result<State<F>, Sha512Error> {
         let mut new_state = State::empty_state();
-        layouter.assign_region(
+        assign_region(
+            layouter,
             || "initialize_with_iv",
             |mut region| {
                 new_state = self.initialize_iv(&mut region, init_state)?;
@@ -1011,15 +1096,31 @@ impl CompressionConfig {
         Ok(new_state)
     }
 
+    /// Initialize compression with an intermediate hash value whose words are assigned
+    /// elsewhere in the circuit or fixed, such as the midstate of a message prefix
+    /// hashed outside the circuit. Returns an initialized state.
+    pub(super) fn initialize_with_midstate(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        midstate: [MidstateWord<AssignedWord<F>>; STATE],
+    ) -> Result<State<F>, Sha512Error> {
+        assign_region(
+            layouter,
+            || "initialize_with_midstate",
+            |mut region| self.assign_midstate(&mut region, midstate.clone()),
+        )
+    }
+
     /// Initialize compression with some initialized state. This could be a state
     /// output from a previous compression round.
     pub(super) fn initialize_with_state(
         &self,
-        layouter: &mut impl Layouter<bn256::Fr>,
-        init_state: State,
-    ) -> Result<State, Error> {
+        layouter: &mut impl Layouter<F>,
+        init_state: State<F>,
+    ) -> Result<State<F>, Sha512Error> {
         let mut new_state = State::empty_state();
-        layouter.assign_region(
+        assign_region(
+            layouter,
             || "initialize_with_state",
             |mut region| {
                 new_state = self.initialize_state(&mut region, init_state.clone())?;
@@ -1029,112 +1130,774 @@ impl CompressionConfig {
         Ok(new_state)
     }
 
-    /// Given an initialized state and a message schedule, perform 80 compression rounds.
+    /// Given an initialized state and a message schedule, perform 80 compression rounds
+    /// and add the result to the initialized state. Returns the intermediate hash value.
     pub(super) fn compress(
         &self,
-        layouter: &mut impl Layouter<bn256::Fr>,
-        initialized_state: State,
-        w_halves: [(AssignedBits<32>, AssignedBits<32>); ROUNDS],
-    ) -> Result<State, Error> {
+        layouter: &mut impl Layouter<F>,
+        initialized_state: State<F>,
+        w_halves: [(AssignedBits<F, 32>, AssignedBits<F, 32>); ROUNDS],
+    ) -> Result<State<F>, Sha512Error> {
+        let final_state = assign_region(
+            layouter,
+            || COMPRESS_REGION,
+            |mut region| self.assign_rounds(&mut region, initialized_state.clone(), &w_halves),
+        )?;
+
         let mut state = State::empty_state();
-        layouter.assign_region(
-            || "compress",
+        assign_region(
+            layouter,
+            || "feed forward",
             |mut region| {
-                state = initialized_state.clone();
-                for (idx, w_halves) in w_halves.iter().enumerate() {
-                    state = self.assign_round(&mut region, idx.into(), state.clone(), w_halves)?;
-                }
+                state = self.assign_feed_forward(
+                    &mut region,
+                    initialized_state.clone(),
+                    final_state.clone(),
+                )?;
                 Ok(())
             },
         )?;
         Ok(state)
     }
 
+    /// Assigns the 80 compression rounds from an initialized state and a message
+    /// schedule. Returns the state after the last round.
+    #[cfg_attr(not(feature = "dev-trace"), allow(unused_variables))]
+    pub(super) fn assign_rounds(
+        &self,
+        region: &mut Region<'_, F>,
+        initialized_state: State<F>,
+        w_halves: &[(AssignedBits<F, 32>, AssignedBits<F, 32>); ROUNDS],
+    ) -> Result<State<F>, Sha512Error> {
+        let mut state = initialized_state;
+        for (idx, w_halves) in w_halves.iter().enumerate() {
+            #[cfg(feature = "dev-trace")]
+            let state_values = state.values();
+            let (next, cells) = self.assign_round(region, idx.into(), state, w_halves)?;
+            #[cfg(feature = "dev-trace")]
+            {
+                let halves = |(lo, hi): &(AssignedBits<F, 32>, AssignedBits<F, 32>)| {
+                    lo.value_u32()
+                        .zip(hi.value_u32())
+                        .map(|(lo, hi)| lo as u64 + ((hi as u64) << 32))
+                };
+                let t2 = halves(&cells.sigma_0)
+                    .zip(halves(&cells.maj))
+                    .map(|(sigma_0, maj)| sigma_0.wrapping_add(maj));
+                let round_idx = compression_util::MainRoundIdx::from(idx);
+                let row = compression_util::get_round_row(round_idx.into());
+                crate::sha512::dev_trace::record_round(
+                    COMPRESS_REGION,
+                    idx,
+                    state_values,
+                    halves(w_halves),
+                    cells.h_prime.value(),
+                    t2,
+                    row..row + compression_util::SUBREGION_MAIN_WORD,
+                );
+            }
+            state = next;
+        }
+        Ok(state)
+    }
+
     /// After the final round, convert the state into the final digest.
     pub(super) fn digest(
         &self,
-        layouter: &mut impl Layouter<bn256::Fr>,
-        state: State,
-    ) -> Result<[BlockWord; DIGEST_SIZE], Error> {
-        let mut digest = [BlockWord(Value::known(0)); DIGEST_SIZE];
-        layouter.assign_region(
+        layouter: &mut impl Layouter<F>,
+        state: State<F>,
+    ) -> Result<[DigestWord<F>; DIGEST_SIZE], Sha512Error> {
+        assign_region(
+            layouter,
             || "digest",
-            |mut region| {
-                digest = self.assign_digest(&mut region, state.clone())?;
-                
-                Ok(())
-            },
-        )?;
-        Ok(digest)
+            |mut region| self.assign_digest(&mut region, state.clone()),
+        )
     }
 }
 #[cfg(test)]
 mod tests {
     use super::super::{
-        super::BLOCK_SIZE, msg_schedule_test_input, BlockWord, Table16Chip, Table16Config, IV,
+        super::{BlockInput, BLOCK_SIZE},
+        msg_schedule_test_input,
+        spread_table::SpreadVar,
+        util::{i2lebsp, lebs2ip},
+        AssignedBits, BlockWord, Table16Chip, Table16Config, IV, ROUND_CONSTANTS, STATE,
+    };
+    use super::{
+        compression_util::{
+            get_a_new_row, get_ch_neg_row, get_ch_row, get_d_row, get_decompose_a_row,
+            get_decompose_e_row, get_digest_abcd_row, get_digest_efgh_row, get_e_new_row,
+            get_feed_forward_row, get_h_prime_row, get_maj_row, get_upper_sigma_0_row,
+            get_upper_sigma_1_row, match_state, MainRoundIdx, RoundIdx,
+        },
+        AbcdVar, CompressionConfig, EfghVar, RoundWordA, RoundWordE, State, StateWord,
+        COMPRESS_REGION,
+    };
+    use crate::sha512::{
+        diagnostics::{diagnose, Diagnosis, Location},
+        native::pad,
+        trace::{spread, trace_block, BlockTrace, Piece},
     };
     use halo2_proofs::{
-        circuit::{Layouter, SimpleFloorPlanner},
-        dev::MockProver,
+        arithmetic::FieldExt,
+        circuit::{Layouter, Region, SimpleFloorPlanner, Value},
+        dev::{MockProver, VerifyFailure},
+        halo2curves::{bn256, pasta::pallas},
         plonk::{Circuit, ConstraintSystem, Error},
     };
-    use halo2_proofs::halo2curves::bn256;
+
+    /// Compresses the "abc" test block, then overwrites the low half of the IV word D,
+    /// or the high half of a round constant, with a value the prover substitutes for
+    /// the constant.
+    #[derive(Default)]
+    struct MyCircuit {
+        iv_d_lo: Option<u64>,
+        k_hi: Option<(usize, u64)>,
+    }
+
+    impl<F: FieldExt> Circuit<F> for MyCircuit {
+        type Config = Table16Config<F>;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            MyCircuit {
+                iv_d_lo: self.iv_d_lo,
+                k_hi: self.k_hi,
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            Table16Chip::configure(meta)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            Table16Chip::load(config.clone(), &mut layouter)?;
+
+            // Test vector: "abc"
+            let input: [BlockWord; BLOCK_SIZE] = msg_schedule_test_input();
+
+            let (_, w_halves) = config
+                .message_schedule
+                .process(&mut layouter, input.map(BlockInput::Message))?;
+
+            // Both the IV word D and the round constants are witnessed in a_7 and a_6 as
+            // a low half on the row above a high half.
+            let compression = config.compression.clone();
+            let initial_state = layouter.assign_region(
+                || "initialize_with_iv",
+                |mut region| {
+                    let state = compression.initialize_iv(&mut region, IV)?;
+                    if let Some(value) = self.iv_d_lo {
+                        let row = get_d_row(RoundIdx::Init);
+                        region.assign_advice(
+                            || "tampered iv",
+                            compression.extras[3],
+                            row,
+                            || Value::known(F::from(value)),
+                        )?;
+                    }
+                    Ok(state)
+                },
+            )?;
+            let final_state = layouter.assign_region(
+                || "compress",
+                |mut region| {
+                    let state =
+                        compression.assign_rounds(&mut region, initial_state.clone(), &w_halves)?;
+                    if let Some((round, value)) = self.k_hi {
+                        let row = get_h_prime_row(MainRoundIdx::from(round));
+                        region.assign_advice(
+                            || "tampered k",
+                            compression.extras[2],
+                            row,
+                            || Value::known(F::from(value)),
+                        )?;
+                    }
+                    Ok(state)
+                },
+            )?;
+            let state = layouter.assign_region(
+                || "feed forward",
+                |mut region| {
+                    Ok(compression.assign_feed_forward(
+                        &mut region,
+                        initial_state.clone(),
+                        final_state.clone(),
+                    )?)
+                },
+            )?;
+
+            let digest = config.compression.digest(&mut layouter, state)?;
+            if self.iv_d_lo.is_none() && self.k_hi.is_none() {
+                for (idx, digest_word) in digest.iter().enumerate() {
+                    digest_word.value().assert_if_known(|digest_word| {
+                        *digest_word == super::compression_util::COMPRESSION_OUTPUT[idx]
+                    });
+                }
+            }
+
+            Ok(())
+        }
+    }
+
+    fn assert_permutation_failure(circuit: MyCircuit) {
+        let prover = match MockProver::<bn256::Fr>::run(19, &circuit, vec![]) {
+            Ok(prover) => prover,
+            Err(e) => panic!("{:?}", e),
+        };
+        let failures = prover.verify().unwrap_err();
+        assert!(failures
+            .iter()
+            .any(|failure| matches!(failure, VerifyFailure::Permutation { .. })));
+    }
+
+    fn compress<F: FieldExt>() {
+        let circuit = MyCircuit::default();
+
+        let prover = match MockProver::<F>::run(19, &circuit, vec![]) {
+            Ok(prover) => prover,
+            Err(e) => panic!("{:?}", e),
+        };
+        assert_eq!(prover.verify(), Ok(()));
+    }
+
+    #[test]
+    fn compress_bn256() {
+        compress::<bn256::Fr>();
+    }
+
+    #[test]
+    fn compress_pallas() {
+        compress::<pallas::Base>();
+    }
+
+    #[test]
+    fn tampered_iv() {
+        assert_permutation_failure(MyCircuit {
+            iv_d_lo: Some((IV[3] & 0xffffffff) ^ 1),
+            k_hi: None,
+        });
+    }
 
     #[test]
-    fn compress() {
-        struct MyCircuit {}
+    fn tampered_round_constant() {
+        assert_permutation_failure(MyCircuit {
+            iv_d_lo: None,
+            k_hi: Some((17, (ROUND_CONSTANTS[17] >> 32) ^ (1 << 8))),
+        });
+    }
+
+    #[test]
+    fn tampered_round_constant_is_diagnosed() {
+        let circuit = MyCircuit {
+            iv_d_lo: None,
+            k_hi: Some((17, (ROUND_CONSTANTS[17] >> 32) ^ (1 << 8))),
+        };
+        let prover = match MockProver::<bn256::Fr>::run(19, &circuit, vec![]) {
+            Ok(prover) => prover,
+            Err(e) => panic!("{:?}", e),
+        };
+        let failures = prover.verify().unwrap_err();
+        assert!(failures
+            .iter()
+            .map(diagnose)
+            .any(|diagnosis| diagnosis.location == Location::Round(17)));
+    }
+
+    /// Compresses the traced block one round at a time, checking against the trace the
+    /// message schedule, the pieces of A and E each round starts from, the values each
+    /// round assigns, and the working variables after each round.
+    struct TraceCircuit {
+        trace: BlockTrace,
+    }
+
+    const WORKING_VARIABLES: [&str; STATE] = ["A", "B", "C", "D", "E", "F", "G", "H"];
+
+    /// Asserts that an assigned value, if known, equals the traced one.
+    fn assert_traced<T: PartialEq + std::fmt::Debug>(
+        value: Value<T>,
+        traced: T,
+        cell: impl Fn() -> String,
+    ) {
+        value.assert_if_known(|value| {
+            assert_eq!(*value, traced, "{} differs from the trace", cell());
+            true
+        });
+    }
 
-        impl Circuit<bn256::Fr> for MyCircuit {
-            type Config = Table16Config;
-            type FloorPlanner = SimpleFloorPlanner;
+    /// Returns the values of a piece of a word and of its spread form.
+    fn piece_values<F: FieldExt, const DENSE: usize, const SPREAD: usize>(
+        piece: &SpreadVar<F, DENSE, SPREAD>,
+    ) -> (Value<u64>, Value<u128>) {
+        (
+            piece.dense.value().map(|bits| lebs2ip(&bits.0) as u64),
+            piece.spread.value().map(|bits| lebs2ip(&bits.0)),
+        )
+    }
+
+    /// Returns the values of the pieces of A, least significant first.
+    fn abcd_values<F: FieldExt>(word: &AbcdVar<F>) -> Vec<(Value<u64>, Value<u128>)> {
+        vec![
+            piece_values(&word.a_lo),
+            piece_values(&word.a_hi),
+            piece_values(&word.b_lo),
+            piece_values(&word.b_hi),
+            piece_values(&word.c_lo),
+            piece_values(&word.c_hi),
+            piece_values(&word.d_lo),
+            piece_values(&word.d_hi),
+        ]
+    }
+
+    /// Returns the values of the pieces of E, least significant first.
+    fn efgh_values<F: FieldExt>(word: &EfghVar<F>) -> Vec<(Value<u64>, Value<u128>)> {
+        vec![
+            piece_values(&word.a),
+            piece_values(&word.b_lo),
+            piece_values(&word.b_hi),
+            piece_values(&word.c_lo),
+            piece_values(&word.c_hi),
+            piece_values(&word.d_lo),
+            piece_values(&word.d_hi),
+        ]
+    }
 
-            fn without_witnesses(&self) -> Self {
-                MyCircuit {}
+    fn assert_pieces_traced(
+        word: &str,
+        round: usize,
+        values: Vec<(Value<u64>, Value<u128>)>,
+        traced: &[Piece],
+    ) {
+        assert_eq!(values.len(), traced.len());
+        for (i, ((dense, spread), traced)) in values.into_iter().zip(traced).enumerate() {
+            let cell = || format!("piece {} of {} in round {}", i, word, round);
+            assert_traced(dense, traced.dense, cell);
+            assert_traced(spread, traced.spread, || format!("spread {}", cell()));
+        }
+    }
+
+    /// Returns the value of a word assigned as its low and high halves.
+    fn halves_value<F: FieldExt>(
+        halves: &(AssignedBits<F, 32>, AssignedBits<F, 32>),
+    ) -> Value<u64> {
+        halves
+            .0
+            .value_u32()
+            .zip(halves.1.value_u32())
+            .map(|(lo, hi)| lo as u64 + ((hi as u64) << 32))
+    }
+
+    impl<F: FieldExt> Circuit<F> for TraceCircuit {
+        type Config = Table16Config<F>;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            TraceCircuit {
+                trace: self.trace.clone(),
             }
+        }
 
-            fn configure(meta: &mut ConstraintSystem<bn256::Fr>) -> Self::Config {
-                Table16Chip::configure(meta)
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            Table16Chip::configure(meta)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            Table16Chip::load(config.clone(), &mut layouter)?;
+
+            let input: Vec<_> = self.trace.schedule[..BLOCK_SIZE]
+                .iter()
+                .map(|word| BlockInput::Message(BlockWord(Value::known(*word))))
+                .collect();
+            let (w, w_halves) = config
+                .message_schedule
+                .process(&mut layouter, input.try_into().unwrap())?;
+            for (t, (word, traced)) in w.iter().zip(self.trace.schedule.iter()).enumerate() {
+                assert_traced(word.value_u64(), *traced, || format!("W_{}", t));
             }
 
-            fn synthesize(
-                &self,
-                config: Self::Config,
-                mut layouter: impl Layouter<bn256::Fr>,
-            ) -> Result<(), Error> {
-                Table16Chip::load(config.clone(), &mut layouter)?;
+            let compression = config.compression.clone();
+            let initial_state = config.compression.initialize_with_iv(&mut layouter, IV)?;
+            layouter.assign_region(
+                || "compress",
+                |mut region| {
+                    let mut state = initial_state.clone();
+                    let rounds = w_halves.iter().zip(self.trace.rounds.iter());
+                    for (idx, (w_halves, traced)) in rounds.enumerate() {
+                        let (a, _, _, _, e, _, _, _) = match_state(state.clone());
+                        let a_values = abcd_values(a.pieces.as_ref().unwrap());
+                        assert_pieces_traced("A", idx, a_values, &traced.a_pieces);
+                        let e_values = efgh_values(e.pieces.as_ref().unwrap());
+                        assert_pieces_traced("E", idx, e_values, &traced.e_pieces);
+
+                        let (next, cells) =
+                            compression.assign_round(&mut region, idx.into(), state, w_halves)?;
+                        let spread_sums = [
+                            ("Σ_0(A)", &cells.sigma_0, traced.upper_sigma_0.even),
+                            ("Σ_1(E)", &cells.sigma_1, traced.upper_sigma_1.even),
+                            ("E ∧ F", &cells.ch, traced.ch.odd),
+                            ("¬E ∧ G", &cells.ch_neg, traced.ch_neg.odd),
+                            ("Maj(A, B, C)", &cells.maj, traced.maj.odd),
+                        ];
+                        for (name, halves, traced) in spread_sums {
+                            assert_traced(halves_value(halves), traced, || {
+                                format!("{} in round {}", name, idx)
+                            });
+                        }
+                        let sums = [
+                            ("H'", &cells.h_prime, traced.h_prime),
+                            ("A_new", &cells.a_new, traced.a_new),
+                            ("E_new", &cells.e_new, traced.e_new),
+                        ];
+                        for (name, word, traced) in sums {
+                            assert_traced(word.value(), traced, || {
+                                format!("{} in round {}", name, idx)
+                            });
+                        }
+                        state = next;
+
+                        // The state after a round is the one the next round starts from.
+                        let traced = match self.trace.rounds.get(idx + 1) {
+                            Some(round) => round.state,
+                            None => self.trace.final_state,
+                        };
+                        for (i, (value, traced)) in
+                            state.values().into_iter().zip(traced).enumerate()
+                        {
+                            assert_traced(value, traced, || {
+                                format!("{} after round {}", WORKING_VARIABLES[i], idx)
+                            });
+                        }
+                    }
+                    Ok(())
+                },
+            )?;
+
+            Ok(())
+        }
+    }
 
-                // Test vector: "abc"
-                let input: [BlockWord; BLOCK_SIZE] = msg_schedule_test_input();
+    /// Compresses the "abc" test block, then overwrites a cell of the compression with
+    /// `value`, or with -1 if `value` is `None`. The cell is given by the region it is
+    /// in, its column a_i in the Table16 layout, and its row in the region.
+    ///
+    /// If `forward` is set, the cell is the low half of the final A or E, and the
+    /// feed-forward adds the overwritten cell in its place, as a prover changing the
+    /// digest would.
+    struct TamperCircuit {
+        region: &'static str,
+        column: usize,
+        row: usize,
+        value: Option<u128>,
+        forward: bool,
+    }
 
-                let (_, w_halves) = config.message_schedule.process(&mut layouter, input)?;
+    impl TamperCircuit {
+        /// Overwrites the tampered cell, if it is in the named region.
+        fn tamper<F: FieldExt>(
+            &self,
+            config: &CompressionConfig<F>,
+            region: &mut Region<'_, F>,
+            name: &str,
+        ) -> Result<(), Error> {
+            if name != self.region {
+                return Ok(());
+            }
+            let column = match self.column {
+                0 => config.lookup.tag,
+                1 => config.lookup.dense,
+                2 => config.lookup.spread,
+                3 | 4 => config.extras[self.column - 3],
+                5 => config.message_schedule,
+                _ => config.extras[self.column - 4],
+            };
+            let value = self.value.map_or(-F::one(), F::from_u128);
+            region
+                .assign_advice(|| "tampered", column, self.row, || Value::known(value))
+                .map(|_| ())
+        }
 
-                let compression = config.compression.clone();
-                let initial_state = compression.initialize_with_iv(&mut layouter, IV)?;
+        /// Overwrites the low half of the final A or E with `value`, and returns the final
+        /// state holding the overwritten cell.
+        fn tamper_final_state<F: FieldExt>(
+            &self,
+            config: &CompressionConfig<F>,
+            region: &mut Region<'_, F>,
+            state: State<F>,
+        ) -> Result<State<F>, Error> {
+            let value = self.value.expect("a half word is fed forward");
+            let lo = AssignedBits::<F, 32>::assign_bits(
+                region,
+                || "tampered",
+                config.extras[0],
+                self.row,
+                Value::known(i2lebsp::<32>(value)),
+            )?;
+            let (a, b, c, d, e, f, g, h) = match_state(state);
+            let (a, e) = if self.row == get_digest_abcd_row() {
+                (RoundWordA::new_dense((lo, a.dense_halves.1).into()), e)
+            } else {
+                (a, RoundWordE::new_dense((lo, e.dense_halves.1).into()))
+            };
+            Ok(State::new(
+                StateWord::A(a),
+                StateWord::B(b),
+                StateWord::C(c),
+                StateWord::D(d),
+                StateWord::E(e),
+                StateWord::F(f),
+                StateWord::G(g),
+                StateWord::H(h),
+            ))
+        }
+    }
 
-                let state = config
-                    .compression
-                    .compress(&mut layouter, initial_state, w_halves)?;
+    impl<F: FieldExt> Circuit<F> for TamperCircuit {
+        type Config = Table16Config<F>;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            TamperCircuit {
+                region: self.region,
+                column: self.column,
+                row: self.row,
+                value: self.value,
+                forward: self.forward,
+            }
+        }
 
-                let digest = config.compression.digest(&mut layouter, state)?;
-                println!("{:?}",digest);
-                for (idx, digest_word) in digest.iter().enumerate() {
-                    digest_word.0.assert_if_known(|digest_word| {
-                        println!("{:?},  {:?}",*digest_word,IV[idx]);
-                        (*digest_word as u128 + IV[idx] as u128) as u64
-                            == super::compression_util::COMPRESSION_OUTPUT[idx]
-                    });
-                }
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            Table16Chip::configure(meta)
+        }
 
-                Ok(())
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            Table16Chip::load(config.clone(), &mut layouter)?;
+
+            let input: [BlockWord; BLOCK_SIZE] = msg_schedule_test_input();
+            let (_, w_halves) = config
+                .message_schedule
+                .process(&mut layouter, input.map(BlockInput::Message))?;
+
+            let compression = config.compression.clone();
+            let initial_state = compression.initialize_with_iv(&mut layouter, IV)?;
+            let final_state = layouter.assign_region(
+                || COMPRESS_REGION,
+                |mut region| {
+                    let state =
+                        compression.assign_rounds(&mut region, initial_state.clone(), &w_halves)?;
+                    if self.forward {
+                        return self.tamper_final_state(&compression, &mut region, state);
+                    }
+                    self.tamper(&compression, &mut region, COMPRESS_REGION)?;
+                    Ok(state)
+                },
+            )?;
+            let state = layouter.assign_region(
+                || "feed forward",
+                |mut region| {
+                    let state = compression.assign_feed_forward(
+                        &mut region,
+                        initial_state.clone(),
+                        final_state.clone(),
+                    )?;
+                    self.tamper(&compression, &mut region, "feed forward")?;
+                    Ok(state)
+                },
+            )?;
+            layouter.assign_region(
+                || "digest",
+                |mut region| {
+                    compression.assign_digest(&mut region, state.clone())?;
+                    self.tamper(&compression, &mut region, "digest")
+                },
+            )
+        }
+    }
+
+    /// Asserts that the mock prover rejects `circuit` with the failure `expected`.
+    fn assert_rejected(circuit: &TamperCircuit, expected: Diagnosis) {
+        let prover = match MockProver::<bn256::Fr>::run(19, circuit, vec![]) {
+            Ok(prover) => prover,
+            Err(e) => panic!("{:?}", e),
+        };
+        let failures = prover.verify().unwrap_err();
+        assert!(
+            failures
+                .iter()
+                .map(diagnose)
+                .any(|diagnosis| diagnosis == expected),
+            "{} was not reported for a tampered cell {:?}: {:?}",
+            expected,
+            circuit.value,
+            failures
+        );
+    }
+
+    #[test]
+    fn tampered_cells() {
+        const MASK: u64 = 0xffffffff;
+        let trace = trace_block(IV, pad(b"abc")[0]);
+        let traced = &trace.rounds[10];
+        // The spread form of bits 16..32 of the even or odd bits of a spread sum, which
+        // its row holds in a_2.
+        let spread_hi = |bits: u64| spread((bits >> 16) & 0xffff);
+
+        // The selector whose gate must reject each tampered cell, where the failure is
+        // located, the region, column a_i and row of the cell, and the value the cell
+        // holds. The cells are taken in round 10, from a column each gate queries on
+        // the row it is enabled on.
+        let round = MainRoundIdx::from(10);
+        let mut cases: Vec<_> = [
+            (
+                "s_decompose_abcd",
+                7,
+                get_decompose_a_row(round.into()),
+                (traced.state[0] & MASK) as u128,
+            ),
+            (
+                "s_decompose_efgh",
+                7,
+                get_decompose_e_row(round.into()),
+                (traced.state[4] & MASK) as u128,
+            ),
+            (
+                "s_upper_sigma_0",
+                2,
+                get_upper_sigma_0_row(round),
+                spread_hi(traced.upper_sigma_0.even),
+            ),
+            (
+                "s_upper_sigma_1",
+                2,
+                get_upper_sigma_1_row(round),
+                spread_hi(traced.upper_sigma_1.even),
+            ),
+            ("s_ch", 2, get_ch_row(round), spread_hi(traced.ch.even)),
+            (
+                "s_ch_neg",
+                2,
+                get_ch_neg_row(round),
+                spread_hi(traced.ch_neg.even),
+            ),
+            ("s_maj", 2, get_maj_row(round), spread_hi(traced.maj.even)),
+            (
+                "s_h_prime",
+                7,
+                get_h_prime_row(round),
+                (traced.state[7] >> 32) as u128,
+            ),
+            (
+                "s_a_new",
+                8,
+                get_a_new_row(round),
+                (traced.a_new & MASK) as u128,
+            ),
+            (
+                "s_e_new",
+                8,
+                get_e_new_row(round),
+                (traced.e_new & MASK) as u128,
+            ),
+        ]
+        .into_iter()
+        .map(|(selector, column, row, honest)| {
+            (
+                selector,
+                Location::Round(10),
+                COMPRESS_REGION,
+                column,
+                row,
+                honest,
+            )
+        })
+        .collect();
+        cases.push((
+            "s_feed_forward",
+            Location::FeedForward(3),
+            "feed forward",
+            5,
+            get_feed_forward_row(3),
+            (trace.output[3] & MASK) as u128,
+        ));
+        let digest = Location::Region {
+            name: "digest".to_string(),
+            offset: 0,
+        };
+        cases.push(("s_digest", digest, "digest", 5, 0, trace.output[0] as u128));
+
+        // Each cell is overwritten with -1, which no honest cell holds, and with its
+        // honest value with the lowest bit flipped.
+        for (selector, location, region, column, row, honest) in cases {
+            for value in [None, Some(honest ^ 1)] {
+                let circuit = TamperCircuit {
+                    region,
+                    column,
+                    row,
+                    value,
+                    forward: false,
+                };
+                let expected = Diagnosis {
+                    location: location.clone(),
+                    check: format!("{} gate", selector),
+                };
+                assert_rejected(&circuit, expected);
             }
         }
 
-        let circuit: MyCircuit = MyCircuit {};
+        // The feed-forward adds copies of the last round's A_new and E_new, in a_3 and
+        // a_4 of the digest rows, which no gate queries: the copy constraints must reject
+        // them overwritten, and still when the feed-forward adds the overwritten cell,
+        // which changes the digest consistently.
+        for (row, honest) in [
+            (get_digest_abcd_row(), trace.final_state[0] & MASK),
+            (get_digest_efgh_row(), trace.final_state[4] & MASK),
+        ] {
+            let honest = honest as u128;
+            for (value, forward) in [
+                (None, false),
+                (Some(honest ^ 1), false),
+                (Some(honest ^ 1), true),
+            ] {
+                let circuit = TamperCircuit {
+                    region: COMPRESS_REGION,
+                    column: 3,
+                    row,
+                    value,
+                    forward,
+                };
+                let expected = Diagnosis {
+                    location: Location::Region {
+                        name: COMPRESS_REGION.to_string(),
+                        offset: row,
+                    },
+                    check: "copy constraint".to_string(),
+                };
+                assert_rejected(&circuit, expected);
+            }
+        }
+    }
 
+    #[test]
+    fn rounds_match_trace() {
+        // The "abc" test block.
+        let circuit = TraceCircuit {
+            trace: trace_block(IV, pad(b"abc")[0]),
+        };
         let prover = match MockProver::<bn256::Fr>::run(19, &circuit, vec![]) {
             Ok(prover) => prover,
             Err(e) => panic!("{:?}", e),
         };
         assert_eq!(prover.verify(), Ok(()));
     }
-}
\ No newline at end of file
+}
@@ -507,4 +507,69 @@ impl<F: FieldExt> CompressionGate<F> {
             ],
         )
     }
-}
\ No newline at end of file
+
+    // s_feed_forward to get H_i = H_{i-1} + (a..h), with the 32-bit halves of the
+    // output range-checked by 16-bit lookups
+    #[allow(clippy::too_many_arguments)]
+    pub fn s_feed_forward(
+        s_feed_forward: Expression<F>,
+        prev_lo: Expression<F>,
+        prev_hi: Expression<F>,
+        cur_lo: Expression<F>,
+        cur_hi: Expression<F>,
+        out_lo: Expression<F>,
+        out_hi: Expression<F>,
+        carry: Expression<F>,
+        out_lo_lo: Expression<F>,
+        out_lo_hi: Expression<F>,
+        out_hi_lo: Expression<F>,
+        out_hi_hi: Expression<F>,
+    ) -> impl IntoIterator<Item = Constraint<F>> {
+        let lo = prev_lo + cur_lo;
+        let hi = prev_hi + cur_hi;
+        let sum = lo + hi * F::from(1 << 32);
+        let out = out_lo.clone() + out_hi.clone() * F::from(1 << 32);
+
+        let check = sum - (carry.clone() * F::from_u128(1 << 64)) - out;
+        let range_check_carry = Gate::range_check(carry, 0, 1);
+        let check_out_lo = out_lo_lo + out_lo_hi * F::from(1 << 16) - out_lo;
+        let check_out_hi = out_hi_lo + out_hi_hi * F::from(1 << 16) - out_hi;
+
+        Constraints::with_selector(
+            s_feed_forward,
+            [
+                ("s_feed_forward", check),
+                ("range_check_carry", range_check_carry),
+                ("check_out_lo", check_out_lo),
+                ("check_out_hi", check_out_hi),
+            ],
+        )
+    }
+
+    // s_midstate to take a word of an intermediate hash value supplied from outside
+    // the circuit's compressions, with its 32-bit halves range-checked by 16-bit lookups
+    #[allow(clippy::too_many_arguments)]
+    pub fn s_midstate(
+        s_midstate: Expression<F>,
+        word: Expression<F>,
+        lo: Expression<F>,
+        hi: Expression<F>,
+        lo_lo: Expression<F>,
+        lo_hi: Expression<F>,
+        hi_lo: Expression<F>,
+        hi_hi: Expression<F>,
+    ) -> impl IntoIterator<Item = Constraint<F>> {
+        let check = lo.clone() + hi.clone() * F::from(1 << 32) - word;
+        let check_lo = lo_lo + lo_hi * F::from(1 << 16) - lo;
+        let check_hi = hi_lo + hi_hi * F::from(1 << 16) - hi;
+
+        Constraints::with_selector(
+            s_midstate,
+            [
+                ("s_midstate", check),
+                ("check_lo", check_lo),
+                ("check_hi", check_hi),
+            ],
+        )
+    }
+}
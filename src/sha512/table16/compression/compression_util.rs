@@ -3,14 +3,14 @@ use super::{
     RoundWordSpread, State, UpperSigmaVar,
 };
 use crate::sha512::table16::{
-    util::*, AssignedBits, SpreadVar, SpreadWord, StateWord, Table16Assignment,
+    util::*, AssignedBits, Sha512Error, SpreadVar, SpreadWord, StateWord, Table16Assignment,
+    ROUND_CONSTANTS, STATE,
 };
 use halo2_proofs::{
     circuit::{Region, Value},
-    halo2curves::bn256,
-    plonk::{Advice, Column, Error},
+    arithmetic::FieldExt,
+    plonk::{Advice, Column},
 };
-use std::convert::TryInto;
 
 // Test vector 'abc'
 #[cfg(test)]
@@ -33,6 +33,8 @@ pub const CH_ROWS: usize = 18;
 pub const MAJ_ROWS: usize = 9;
 pub const DECOMPOSE_ABCD: usize = 4;
 pub const DECOMPOSE_EFGH: usize = 5;
+pub const FEED_FORWARD_ROWS: usize = 4;
+pub const MIDSTATE_ROWS: usize = 4;
 
 // Rows needed for main subregion
 pub const SUBREGION_MAIN_LEN: usize = 80;
@@ -199,13 +201,54 @@ pub fn get_digest_efgh_row() -> usize {
     get_digest_abcd_row() + 4
 }
 
-impl CompressionConfig {
+/// Returns the starting row of the feed-forward addition for the given state word,
+/// relative to the feed-forward region.
+pub fn get_feed_forward_row(word_idx: usize) -> usize {
+    assert!(word_idx < STATE);
+    word_idx * FEED_FORWARD_ROWS
+}
+
+/// Returns the starting row of the given word of an intermediate hash value, relative
+/// to the midstate region.
+pub fn get_midstate_row(word_idx: usize) -> usize {
+    assert!(word_idx < STATE);
+    word_idx * MIDSTATE_ROWS
+}
+
+/// Returns the index of the main round whose rows include the given row of the
+/// compression region.
+pub fn get_round_idx(row: usize) -> Option<usize> {
+    (0..SUBREGION_MAIN_LEN).find(|&idx| {
+        let start = get_round_row(MainRoundIdx(idx).into());
+        (start..start + SUBREGION_MAIN_WORD).contains(&row)
+    })
+}
+
+/// Returns the index of the state word whose feed-forward addition takes the given row
+/// of the feed-forward region.
+pub fn get_feed_forward_word(row: usize) -> Option<usize> {
+    (0..STATE).find(|&idx| {
+        let start = get_feed_forward_row(idx);
+        (start..start + FEED_FORWARD_ROWS).contains(&row)
+    })
+}
+
+/// Returns the index of the word of an intermediate hash value taking the given row of
+/// the midstate region.
+pub fn get_midstate_word(row: usize) -> Option<usize> {
+    (0..STATE).find(|&idx| {
+        let start = get_midstate_row(idx);
+        (start..start + MIDSTATE_ROWS).contains(&row)
+    })
+}
+
+impl<F: FieldExt> CompressionConfig<F> {
     pub(super) fn decompose_abcd(
         &self,
-        region: &mut Region<'_, bn256::Fr>,
+        region: &mut Region<'_, F>,
         row: usize,
         val: Value<u64>,
-    ) -> Result<AbcdVar, Error> {
+    ) -> Result<AbcdVar<F>, Sha512Error> {
         self.s_decompose_abcd.enable(region, row)?;
 
         let a_3 = self.extras[0];
@@ -220,13 +263,13 @@ impl CompressionConfig {
             region,
             &self.lookup,
             row,
-            spread_pieces[0].clone().map(SpreadWord::<14,28>::try_new),
+            SpreadWord::<14,28>::try_from_value(spread_pieces[0].clone())?,
         )?;
         let a_hi = SpreadVar::with_lookup(
             region,
             &self.lookup,
             row + 1,
-            spread_pieces[1].clone().map(SpreadWord::<14,28>::try_new),
+            SpreadWord::<14,28>::try_from_value(spread_pieces[1].clone())?,
         )?;
         let b_lo = SpreadVar::without_lookup(
             region,
@@ -234,7 +277,7 @@ impl CompressionConfig {
             row,
             a_4,
             row,
-            spread_pieces[2].clone().map(SpreadWord::<3, 6>::try_new),
+            SpreadWord::<3, 6>::try_from_value(spread_pieces[2].clone())?,
         )?;
         let b_hi = SpreadVar::without_lookup(
             region,
@@ -242,7 +285,7 @@ impl CompressionConfig {
             row+1,
             a_4,
             row+1,
-            spread_pieces[3].clone().map(SpreadWord::<3, 6>::try_new),
+            SpreadWord::<3, 6>::try_from_value(spread_pieces[3].clone())?,
         )?;
         let c_lo = SpreadVar::without_lookup(
             region,
@@ -250,7 +293,7 @@ impl CompressionConfig {
             row+2,
             a_4,
             row+2,
-            spread_pieces[4].clone().map(SpreadWord::<2, 4>::try_new),
+            SpreadWord::<2, 4>::try_from_value(spread_pieces[4].clone())?,
         )?;
         let c_hi = SpreadVar::without_lookup(
             region,
@@ -258,19 +301,19 @@ impl CompressionConfig {
             row + 3,
             a_4,
             row + 3,
-            spread_pieces[5].clone().map(SpreadWord::<3, 6>::try_new),
+            SpreadWord::<3, 6>::try_from_value(spread_pieces[5].clone())?,
         )?;
         let d_lo = SpreadVar::with_lookup(
             region,
             &self.lookup,
             row+2,
-            spread_pieces[6].clone().map(SpreadWord::<14, 28>::try_new),
+            SpreadWord::<14, 28>::try_from_value(spread_pieces[6].clone())?,
         )?;
         let d_hi = SpreadVar::with_lookup(
             region,
             &self.lookup,
             row+3,
-            spread_pieces[7].clone().map(SpreadWord::<11, 22>::try_new),
+            SpreadWord::<11, 22>::try_from_value(spread_pieces[7].clone())?,
         )?;
 
         Ok(AbcdVar {
@@ -287,10 +330,10 @@ impl CompressionConfig {
 
     pub(super) fn decompose_efgh(
         &self,
-        region: &mut Region<'_, bn256::Fr>,
+        region: &mut Region<'_, F>,
         row: usize,
         val: Value<u64>,
-    ) -> Result<EfghVar, Error> {
+    ) -> Result<EfghVar<F>, Sha512Error> {
         self.s_decompose_efgh.enable(region, row)?;
 
         let a_3 = self.extras[0];
@@ -304,7 +347,7 @@ impl CompressionConfig {
             region,
             &self.lookup,
             row,
-            spread_pieces[0].clone().map(SpreadWord::try_new),
+            SpreadWord::try_from_value(spread_pieces[0].clone())?,
         )?;
         let b_lo = SpreadVar::without_lookup(
             region,
@@ -312,7 +355,7 @@ impl CompressionConfig {
             row,
             a_4,
             row,
-            spread_pieces[1].clone().map(SpreadWord::try_new),
+            SpreadWord::try_from_value(spread_pieces[1].clone())?,
         )?;
         let b_hi = SpreadVar::without_lookup(
             region,
@@ -320,31 +363,31 @@ impl CompressionConfig {
             row + 1,
             a_4,
             row + 1,
-            spread_pieces[2].clone().map(SpreadWord::try_new),
+            SpreadWord::try_from_value(spread_pieces[2].clone())?,
         )?;
         let c_lo = SpreadVar::with_lookup(
             region,
             &self.lookup,
             row + 1,
-            spread_pieces[3].clone().map(SpreadWord::try_new),
+            SpreadWord::try_from_value(spread_pieces[3].clone())?,
         )?;
         let c_hi = SpreadVar::with_lookup(
             region,
             &self.lookup,
             row+2,
-            spread_pieces[4].clone().map(SpreadWord::try_new),
+            SpreadWord::try_from_value(spread_pieces[4].clone())?,
         )?;
         let d_lo = SpreadVar::with_lookup(
             region,
             &self.lookup,
             row+3,
-            spread_pieces[5].clone().map(SpreadWord::try_new),
+            SpreadWord::try_from_value(spread_pieces[5].clone())?,
         )?;
         let d_hi = SpreadVar::with_lookup(
             region,
             &self.lookup,
             row+4,
-            spread_pieces[6].clone().map(SpreadWord::try_new),
+            SpreadWord::try_from_value(spread_pieces[6].clone())?,
         )?;
 
         Ok(EfghVar {
@@ -360,10 +403,10 @@ impl CompressionConfig {
 
     pub(super) fn decompose_a(
         &self,
-        region: &mut Region<'_, bn256::Fr>,
+        region: &mut Region<'_, F>,
         round_idx: RoundIdx,
         a_val: Value<u64>,
-    ) -> Result<RoundWordA, Error> {
+    ) -> Result<RoundWordA<F>, Sha512Error> {
         let row = get_decompose_a_row(round_idx);
 
         let (dense_halves, spread_halves) = self.assign_word_halves(region, row, a_val)?;
@@ -373,10 +416,10 @@ impl CompressionConfig {
 
     pub(super) fn decompose_e(
         &self,
-        region: &mut Region<'_, bn256::Fr>,
+        region: &mut Region<'_, F>,
         round_idx: RoundIdx,
         e_val: Value<u64>,
-    ) -> Result<RoundWordE, Error> {
+    ) -> Result<RoundWordE<F>, Sha512Error> {
         let row = get_decompose_e_row(round_idx);
 
         let (dense_halves, spread_halves) = self.assign_word_halves(region, row, e_val)?;
@@ -386,10 +429,10 @@ impl CompressionConfig {
 
     pub(super) fn assign_upper_sigma_0(
         &self,
-        region: &mut Region<'_, bn256::Fr>,
+        region: &mut Region<'_, F>,
         round_idx: MainRoundIdx,
-        word: AbcdVar,
-    ) -> Result<(AssignedBits<32>, AssignedBits<32>), Error> {
+        word: AbcdVar<F>,
+    ) -> Result<(AssignedBits<F, 32>, AssignedBits<F, 32>), Sha512Error> {
         // Rename these here for ease of matching the gates to the specification.
         let a_3 = self.extras[0];
         let a_4 = self.extras[1];
@@ -435,11 +478,11 @@ impl CompressionConfig {
 
         // Calculate R_0^{even}, R_0^{odd}, R_1^{even}, R_1^{odd}
         let r = word.xor_upper_sigma();
-        let r_0: Value<[bool; 64]> = r.map(|r| r[..64].try_into().unwrap());
+        let r_0: Value<[bool; 64]> = transpose_result(r.map(|r| try_into_bits(&r[..64])))?;
         let r_0_even = r_0.map(even_bits);
         let r_0_odd = r_0.map(odd_bits);
 
-        let r_1: Value<[bool; 64]> = r.map(|r| r[64..].try_into().unwrap());
+        let r_1: Value<[bool; 64]> = transpose_result(r.map(|r| try_into_bits(&r[64..])))?;
         let r_1_even = r_1.map(even_bits);
         let r_1_odd = r_1.map(odd_bits);
 
@@ -457,10 +500,10 @@ impl CompressionConfig {
 
     pub(super) fn assign_upper_sigma_1(
         &self,
-        region: &mut Region<'_, bn256::Fr>,
+        region: &mut Region<'_, F>,
         round_idx: MainRoundIdx,
-        word: EfghVar,
-    ) -> Result<(AssignedBits<32>, AssignedBits<32>), Error> {
+        word: EfghVar<F>,
+    ) -> Result<(AssignedBits<F, 32>, AssignedBits<F, 32>), Sha512Error> {
         // Rename these here for ease of matching the gates to the specification.
         let a_3 = self.extras[0];
         let a_4 = self.extras[1];
@@ -494,11 +537,11 @@ impl CompressionConfig {
         // Calculate R_0^{even}, R_0^{odd}, R_1^{even}, R_1^{odd}
         // Calculate R_0^{even}, R_0^{odd}, R_1^{even}, R_1^{odd}
         let r = word.xor_upper_sigma();
-        let r_0: Value<[bool; 64]> = r.map(|r| r[..64].try_into().unwrap());
+        let r_0: Value<[bool; 64]> = transpose_result(r.map(|r| try_into_bits(&r[..64])))?;
         let r_0_even = r_0.map(even_bits);
         let r_0_odd = r_0.map(odd_bits);
 
-        let r_1: Value<[bool; 64]> = r.map(|r| r[64..].try_into().unwrap());
+        let r_1: Value<[bool; 64]> = transpose_result(r.map(|r| try_into_bits(&r[64..])))?;
         let r_1_even = r_1.map(even_bits);
         let r_1_odd = r_1.map(odd_bits);
 
@@ -516,13 +559,13 @@ impl CompressionConfig {
 
     fn assign_ch_outputs(
         &self,
-        region: &mut Region<'_, bn256::Fr>,
+        region: &mut Region<'_, F>,
         row: usize,
         r_0_even: Value<[bool; 32]>,
         r_0_odd: Value<[bool; 32]>,
         r_1_even: Value<[bool; 32]>,
         r_1_odd: Value<[bool; 32]>,
-    ) -> Result<(AssignedBits<32>, AssignedBits<32>), Error> {
+    ) -> Result<(AssignedBits<F, 32>, AssignedBits<F, 32>), Sha512Error> {
         let a_3 = self.extras[0];
 
         let (_even, odd) = self.assign_spread_outputs(
@@ -541,11 +584,11 @@ impl CompressionConfig {
 
     pub(super) fn assign_ch(
         &self,
-        region: &mut Region<'_, bn256::Fr>,
+        region: &mut Region<'_, F>,
         round_idx: MainRoundIdx,
-        spread_halves_e: RoundWordSpread,
-        spread_halves_f: RoundWordSpread,
-    ) -> Result<(AssignedBits<32>, AssignedBits<32>), Error> {
+        spread_halves_e: RoundWordSpread<F>,
+        spread_halves_f: RoundWordSpread<F>,
+    ) -> Result<(AssignedBits<F, 32>, AssignedBits<F, 32>), Sha512Error> {
         let a_3 = self.extras[0];
         let a_4 = self.extras[1];
 
@@ -574,11 +617,11 @@ impl CompressionConfig {
             .zip(spread_halves_f.value())
             .map(|(e, f)| i2lebsp(e + f));
 
-        let p_0: Value<[bool; 64]> = p.map(|p| p[..64].try_into().unwrap());
+        let p_0: Value<[bool; 64]> = transpose_result(p.map(|p| try_into_bits(&p[..64])))?;
         let p_0_even = p_0.map(even_bits);
         let p_0_odd = p_0.map(odd_bits);
 
-        let p_1: Value<[bool; 64]> = p.map(|p| p[64..].try_into().unwrap());
+        let p_1: Value<[bool; 64]> = transpose_result(p.map(|p| try_into_bits(&p[64..])))?;
         let p_1_even = p_1.map(even_bits);
         let p_1_odd = p_1.map(odd_bits);
 
@@ -587,11 +630,11 @@ impl CompressionConfig {
 
     pub(super) fn assign_ch_neg(
         &self,
-        region: &mut Region<'_, bn256::Fr>,
+        region: &mut Region<'_, F>,
         round_idx: MainRoundIdx,
-        spread_halves_e: RoundWordSpread,
-        spread_halves_g: RoundWordSpread,
-    ) -> Result<(AssignedBits<32>, AssignedBits<32>), Error> {
+        spread_halves_e: RoundWordSpread<F>,
+        spread_halves_g: RoundWordSpread<F>,
+    ) -> Result<(AssignedBits<F, 32>, AssignedBits<F, 32>), Sha512Error> {
         let row = get_ch_neg_row(round_idx);
 
         self.s_ch_neg.enable(region, row)?;
@@ -622,7 +665,7 @@ impl CompressionConfig {
             .value()
             .map(|spread_e_lo| negate_spread(spread_e_lo.0));
         // Assign spread_neg_e_lo
-        AssignedBits::<64>::assign_bits(
+        AssignedBits::<F, 64>::assign_bits(
             region,
             || "spread_neg_e_lo",
             a_3,
@@ -636,7 +679,7 @@ impl CompressionConfig {
             .value()
             .map(|spread_e_hi| negate_spread(spread_e_hi.0));
         // Assign spread_neg_e_hi
-        AssignedBits::<64>::assign_bits(
+        AssignedBits::<F, 64>::assign_bits(
             region,
             || "spread_neg_e_hi",
             a_4,
@@ -653,11 +696,11 @@ impl CompressionConfig {
                 .map(|(neg_e, g)| i2lebsp(neg_e + g))
         };
 
-        let p_0: Value<[bool; 64]> = p.map(|p| p[..64].try_into().unwrap());
+        let p_0: Value<[bool; 64]> = transpose_result(p.map(|p| try_into_bits(&p[..64])))?;
         let p_0_even = p_0.map(even_bits);
         let p_0_odd = p_0.map(odd_bits);
 
-        let p_1: Value<[bool; 64]> = p.map(|p| p[64..].try_into().unwrap());
+        let p_1: Value<[bool; 64]> = transpose_result(p.map(|p| try_into_bits(&p[64..])))?;
         let p_1_even = p_1.map(even_bits);
         let p_1_odd = p_1.map(odd_bits);
 
@@ -666,13 +709,13 @@ impl CompressionConfig {
 
     fn assign_maj_outputs(
         &self,
-        region: &mut Region<'_, bn256::Fr>,
+        region: &mut Region<'_, F>,
         row: usize,
         r_0_even: Value<[bool; 32]>,
         r_0_odd: Value<[bool; 32]>,
         r_1_even: Value<[bool; 32]>,
         r_1_odd: Value<[bool; 32]>,
-    ) -> Result<(AssignedBits<32>, AssignedBits<32>), Error> {
+    ) -> Result<(AssignedBits<F, 32>, AssignedBits<F, 32>), Sha512Error> {
         let a_3 = self.extras[0];
         let (_even, odd) = self.assign_spread_outputs(
             region,
@@ -690,12 +733,12 @@ impl CompressionConfig {
 
     pub(super) fn assign_maj(
         &self,
-        region: &mut Region<'_, bn256::Fr>,
+        region: &mut Region<'_, F>,
         round_idx: MainRoundIdx,
-        spread_halves_a: RoundWordSpread,
-        spread_halves_b: RoundWordSpread,
-        spread_halves_c: RoundWordSpread,
-    ) -> Result<(AssignedBits<32>, AssignedBits<32>), Error> {
+        spread_halves_a: RoundWordSpread<F>,
+        spread_halves_b: RoundWordSpread<F>,
+        spread_halves_c: RoundWordSpread<F>,
+    ) -> Result<(AssignedBits<F, 32>, AssignedBits<F, 32>), Sha512Error> {
         let a_4 = self.extras[1];
         let a_5 = self.message_schedule;
 
@@ -733,11 +776,11 @@ impl CompressionConfig {
             .zip(spread_halves_c.value())
             .map(|((a, b), c)| i2lebsp(a + b + c));
 
-        let m_0: Value<[bool; 64]> = m.map(|m| m[..64].try_into().unwrap());
+        let m_0: Value<[bool; 64]> = transpose_result(m.map(|m| try_into_bits(&m[..64])))?;
         let m_0_even = m_0.map(even_bits);
         let m_0_odd = m_0.map(odd_bits);
 
-        let m_1: Value<[bool; 64]> = m.map(|m| m[64..].try_into().unwrap());
+        let m_1: Value<[bool; 64]> = transpose_result(m.map(|m| try_into_bits(&m[64..])))?;
         let m_1_even = m_1.map(even_bits);
         let m_1_odd = m_1.map(odd_bits);
 
@@ -748,15 +791,14 @@ impl CompressionConfig {
     #[allow(clippy::too_many_arguments)]
     pub(super) fn assign_h_prime(
         &self,
-        region: &mut Region<'_, bn256::Fr>,
+        region: &mut Region<'_, F>,
         round_idx: MainRoundIdx,
-        h: RoundWordDense,
-        ch: (AssignedBits<32>, AssignedBits<32>),
-        ch_neg: (AssignedBits<32>, AssignedBits<32>),
-        sigma_1: (AssignedBits<32>, AssignedBits<32>),
-        k: u64,
-        w: &(AssignedBits<32>, AssignedBits<32>),
-    ) -> Result<RoundWordDense, Error> {
+        h: RoundWordDense<F>,
+        ch: (AssignedBits<F, 32>, AssignedBits<F, 32>),
+        ch_neg: (AssignedBits<F, 32>, AssignedBits<F, 32>),
+        sigma_1: (AssignedBits<F, 32>, AssignedBits<F, 32>),
+        w: &(AssignedBits<F, 32>, AssignedBits<F, 32>),
+    ) -> Result<RoundWordDense<F>, Sha512Error> {
         let row = get_h_prime_row(round_idx);
         self.s_h_prime.enable(region, row)?;
 
@@ -775,13 +817,16 @@ impl CompressionConfig {
         sigma_1.0.copy_advice(|| "sigma_1_lo", region, a_4, row)?;
         sigma_1.1.copy_advice(|| "sigma_1_hi", region, a_5, row)?;
 
-        // Assign k
-        let k: [bool; 64] = i2lebsp(k.into());
-        let k_lo: [bool; 32] = k[..32].try_into().unwrap();
-        let k_hi: [bool; 32] = k[32..].try_into().unwrap();
+        // Assign k, and constrain it to the round constant so that the prover cannot
+        // substitute a different value.
+        let round_constant = ROUND_CONSTANTS[round_idx.as_usize()];
+        let k_lo = Value::known(round_constant as u32);
+        let k_hi = Value::known((round_constant >> 32) as u32);
         {
-            AssignedBits::<32>::assign_bits(region, || "k_lo", a_6, row - 1, Value::known(k_lo))?;
-            AssignedBits::<32>::assign_bits(region, || "k_hi", a_6, row, Value::known(k_hi))?;
+            let k_lo = AssignedBits::<F, 32>::assign(region, || "k_lo", a_6, row - 1, k_lo)?;
+            let k_hi = AssignedBits::<F, 32>::assign(region, || "k_hi", a_6, row, k_hi)?;
+            region.constrain_constant(k_lo.cell(), F::from(round_constant & 0xffffffff))?;
+            region.constrain_constant(k_hi.cell(), F::from(round_constant >> 32))?;
         }
 
         // Assign and copy w
@@ -803,10 +848,7 @@ impl CompressionConfig {
                 (ch.0.value_u32(), ch.1.value_u32()),
                 (ch_neg.0.value_u32(), ch_neg.1.value_u32()),
                 (sigma_1.0.value_u32(), sigma_1.1.value_u32()),
-                (
-                    Value::known(lebs2ip(&k_lo) as u32),
-                    Value::known(lebs2ip(&k_hi) as u32),
-                ),
+                (k_lo, k_hi),
                 (w.0.value_u32(), w.1.value_u32()),
             ]);
 
@@ -814,17 +856,19 @@ impl CompressionConfig {
                 || "h_prime_carry",
                 a_9,
                 row + 1,
-                || h_prime_carry.map(bn256::Fr::from),
+                || h_prime_carry.map(F::from),
             )?;
 
             let h_prime: Value<[bool; 64]> = h_prime.map(|w| i2lebsp(w.into()));
-            let h_prime_lo: Value<[bool; 32]> = h_prime.map(|w| w[..32].try_into().unwrap());
-            let h_prime_hi: Value<[bool; 32]> = h_prime.map(|w| w[32..].try_into().unwrap());
+            let h_prime_lo: Value<[bool; 32]> =
+                transpose_result(h_prime.map(|w| try_into_bits(&w[..32])))?;
+            let h_prime_hi: Value<[bool; 32]> =
+                transpose_result(h_prime.map(|w| try_into_bits(&w[32..])))?;
 
             let h_prime_lo =
-                AssignedBits::<32>::assign_bits(region, || "h_prime_lo", a_7, row + 1, h_prime_lo)?;
+                AssignedBits::<F, 32>::assign_bits(region, || "h_prime_lo", a_7, row + 1, h_prime_lo)?;
             let h_prime_hi =
-                AssignedBits::<32>::assign_bits(region, || "h_prime_hi", a_8, row + 1, h_prime_hi)?;
+                AssignedBits::<F, 32>::assign_bits(region, || "h_prime_hi", a_8, row + 1, h_prime_hi)?;
 
             Ok((h_prime_lo, h_prime_hi).into())
         }
@@ -833,11 +877,11 @@ impl CompressionConfig {
     // s_e_new to get E_new = H' + D
     pub(super) fn assign_e_new(
         &self,
-        region: &mut Region<'_, bn256::Fr>,
+        region: &mut Region<'_, F>,
         round_idx: MainRoundIdx,
-        d: &RoundWordDense,
-        h_prime: &RoundWordDense,
-    ) -> Result<RoundWordDense, Error> {
+        d: &RoundWordDense<F>,
+        h_prime: &RoundWordDense<F>,
+    ) -> Result<RoundWordDense<F>, Sha512Error> {
         let row = get_e_new_row(round_idx);
 
         self.s_e_new.enable(region, row)?;
@@ -861,7 +905,7 @@ impl CompressionConfig {
             || "e_new_carry",
             a_9,
             row + 1,
-            || e_new_carry.map(bn256::Fr::from),
+            || e_new_carry.map(F::from),
         )?;
 
         Ok(e_new_dense)
@@ -870,12 +914,12 @@ impl CompressionConfig {
     // s_a_new to get A_new = H' + Maj(A, B, C) + s_upper_sigma_0(A)
     pub(super) fn assign_a_new(
         &self,
-        region: &mut Region<'_, bn256::Fr>,
+        region: &mut Region<'_, F>,
         round_idx: MainRoundIdx,
-        maj: (AssignedBits<32>, AssignedBits<32>),
-        sigma_0: (AssignedBits<32>, AssignedBits<32>),
-        h_prime: RoundWordDense,
-    ) -> Result<RoundWordDense, Error> {
+        maj: (AssignedBits<F, 32>, AssignedBits<F, 32>),
+        sigma_0: (AssignedBits<F, 32>, AssignedBits<F, 32>),
+        h_prime: RoundWordDense<F>,
+    ) -> Result<RoundWordDense<F>, Sha512Error> {
         let row = get_a_new_row(round_idx);
 
         self.s_a_new.enable(region, row)?;
@@ -916,7 +960,7 @@ impl CompressionConfig {
             || "a_new_carry",
             a_9,
             row,
-            || a_new_carry.map(bn256::Fr::from),
+            || a_new_carry.map(F::from),
         )?;
 
         Ok(a_new_dense)
@@ -924,92 +968,84 @@ impl CompressionConfig {
 
     pub fn assign_word_halves_dense(
         &self,
-        region: &mut Region<'_, bn256::Fr>,
+        region: &mut Region<'_, F>,
         lo_row: usize,
         lo_col: Column<Advice>,
         hi_row: usize,
         hi_col: Column<Advice>,
         word: Value<u64>,
-    ) -> Result<RoundWordDense, Error> {
+    ) -> Result<RoundWordDense<F>, Sha512Error> {
         let word: Value<[bool; 64]> = word.map(|w| i2lebsp(w.into()));
 
         let lo = {
-            let lo: Value<[bool; 32]> = word.map(|w| w[..32].try_into().unwrap());
-            AssignedBits::<32>::assign_bits(region, || "lo", lo_col, lo_row, lo)?
+            let lo: Value<[bool; 32]> = transpose_result(word.map(|w| try_into_bits(&w[..32])))?;
+            AssignedBits::<F, 32>::assign_bits(region, || "lo", lo_col, lo_row, lo)?
         };
 
         let hi = {
-            let hi: Value<[bool; 32]> = word.map(|w| w[32..].try_into().unwrap());
-            AssignedBits::<32>::assign_bits(region, || "hi", hi_col, hi_row, hi)?
+            let hi: Value<[bool; 32]> = transpose_result(word.map(|w| try_into_bits(&w[32..])))?;
+            AssignedBits::<F, 32>::assign_bits(region, || "hi", hi_col, hi_row, hi)?
         };
 
         Ok((lo, hi).into())
     }
 
-    fn joindense(x :&SpreadVar<16,32>,y : &SpreadVar<16,32>) -> Value<[bool; 32]> {
-        x
-        .dense
-        .value()
-        .zip(y.dense.value())
-        .map(|(x, y)| {
-            x.iter()
-                .chain(y.iter())
-                .copied()
-                .collect::<Vec<_>>()
-                .try_into()
-                .unwrap()
-        })
+    fn joindense(
+        x: &SpreadVar<F, 16, 32>,
+        y: &SpreadVar<F, 16, 32>,
+    ) -> Result<Value<[bool; 32]>, Sha512Error> {
+        let joined = x.dense.value().zip(y.dense.value()).map(|(x, y)| {
+            let joined: Vec<_> = x.iter().chain(y.iter()).copied().collect();
+            try_into_bits(&joined)
+        });
+        transpose_result(joined)
     }
 
-    fn joinspread(x :&SpreadVar<16,32>,y : &SpreadVar<16,32>) -> Value<[bool; 64]> {
-        x
-        .spread
-        .value()
-        .zip(y.spread.value())
-        .map(|(x, y)| {
-            x.iter()
-                .chain(y.iter())
-                .copied()
-                .collect::<Vec<_>>()
-                .try_into()
-                .unwrap()
-        })
+    fn joinspread(
+        x: &SpreadVar<F, 16, 32>,
+        y: &SpreadVar<F, 16, 32>,
+    ) -> Result<Value<[bool; 64]>, Sha512Error> {
+        let joined = x.spread.value().zip(y.spread.value()).map(|(x, y)| {
+            let joined: Vec<_> = x.iter().chain(y.iter()).copied().collect();
+            try_into_bits(&joined)
+        });
+        transpose_result(joined)
     }
 
     // Assign hi and lo halves for both dense and spread versions of a word
     #[allow(clippy::type_complexity)]
     pub fn assign_word_halves(
         &self,
-        region: &mut Region<'_, bn256::Fr>,
+        region: &mut Region<'_, F>,
         row: usize,
         word: Value<u64>,
-    ) -> Result<(RoundWordDense, RoundWordSpread), Error> {
+    ) -> Result<(RoundWordDense<F>, RoundWordSpread<F>), Sha512Error> {
         // Rename these here for ease of matching the gates to the specification.
         let a_7 = self.extras[3];
         let a_8 = self.extras[4];
 
         let word: Value<[bool; 64]> = word.map(|w| i2lebsp(w.into()));
-        let lo_lo: Value<[bool; 16]> = word.map(|w| w[..16].try_into().unwrap());
-        let lo_hi: Value<[bool; 16]> = word.map(|w| w[16..32].try_into().unwrap());
-        let hi_lo: Value<[bool; 16]> = word.map(|w| w[32..48].try_into().unwrap());
-        let hi_hi: Value<[bool; 16]> = word.map(|w| w[48..64].try_into().unwrap());
+        let lo_lo: Value<[bool; 16]> = transpose_result(word.map(|w| try_into_bits(&w[..16])))?;
+        let lo_hi: Value<[bool; 16]> = transpose_result(word.map(|w| try_into_bits(&w[16..32])))?;
+        let hi_lo: Value<[bool; 16]> = transpose_result(word.map(|w| try_into_bits(&w[32..48])))?;
+        let hi_hi: Value<[bool; 16]> = transpose_result(word.map(|w| try_into_bits(&w[48..64])))?;
 
         let w_lo_lo = SpreadVar::without_lookup(region, a_7, row, a_8, row, lo_lo.map(SpreadWord::new))?;
         let w_lo_hi = SpreadVar::without_lookup(region, a_7, row+1, a_8, row+1, lo_hi.map(SpreadWord::new))?;
         let w_hi_lo = SpreadVar::without_lookup(region, a_7, row+2, a_8, row+2, hi_lo.map(SpreadWord::new))?;
         let w_hi_hi = SpreadVar::without_lookup(region, a_7, row + 3, a_8, row + 3, hi_hi.map(SpreadWord::new))?;
 
-        let w_lo_dense = Self::joindense(&w_lo_lo,&w_lo_hi);
-        let w_lo_d = AssignedBits::<32>::assign_bits(region, || "w_lo_d", a_7, row, w_lo_dense)?;
+        let w_lo_dense = Self::joindense(&w_lo_lo,&w_lo_hi)?;
+        let w_lo_d = AssignedBits::<F, 32>::assign_bits(region, || "w_lo_d", a_7, row, w_lo_dense)?;
 
-        let w_lo_spread = Self::joinspread(&w_lo_lo,&w_lo_hi);
-        let w_lo_s = AssignedBits::<64>::assign_bits(region, || "w_lo_s", a_8, row, w_lo_spread)?;
+        let w_lo_spread = Self::joinspread(&w_lo_lo,&w_lo_hi)?;
+        let w_lo_s = AssignedBits::<F, 64>::assign_bits(region, || "w_lo_s", a_8, row, w_lo_spread)?;
 
-        let w_hi_dense = Self::joindense(&w_hi_lo,&w_hi_hi);
-        let w_hi_d = AssignedBits::<32>::assign_bits(region, || "w_hi_d", a_7, row + 1, w_hi_dense)?;
+        let w_hi_dense = Self::joindense(&w_hi_lo,&w_hi_hi)?;
+        let w_hi_d = AssignedBits::<F, 32>::assign_bits(region, || "w_hi_d", a_7, row + 1, w_hi_dense)?;
 
-        let w_hi_spread = Self::joinspread(&w_hi_lo,&w_hi_hi);
-        let w_hi_s = AssignedBits::<64>::assign_bits(region, || "w_hi_s", a_8, row + 1, w_hi_spread)?;
+        let w_hi_spread = Self::joinspread(&w_hi_lo,&w_hi_hi)?;
+        let w_hi_s = AssignedBits::<F, 64>::assign_bits(region, || "w_hi_s", a_8, row + 1, w_hi_spread)?;
 
         Ok((
             (w_lo_d, w_hi_d).into(),
@@ -1019,17 +1055,17 @@ impl CompressionConfig {
 }
 
 #[allow(clippy::many_single_char_names)]
-pub fn match_state(
-    state: State,
+pub fn match_state<F: FieldExt>(
+    state: State<F>,
 ) -> (
-    RoundWordA,
-    RoundWord,
-    RoundWord,
-    RoundWordDense,
-    RoundWordE,
-    RoundWord,
-    RoundWord,
-    RoundWordDense,
+    RoundWordA<F>,
+    RoundWord<F>,
+    RoundWord<F>,
+    RoundWordDense<F>,
+    RoundWordE<F>,
+    RoundWord<F>,
+    RoundWord<F>,
+    RoundWordDense<F>,
 ) {
     let a = match state.a {
         Some(StateWord::A(a)) => a,
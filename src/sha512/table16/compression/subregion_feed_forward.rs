@@ -0,0 +1,95 @@
+use super::super::{
+    util::*, AssignedBits, RoundWord, RoundWordA, RoundWordDense, RoundWordE, Sha512Error,
+    SpreadVar, SpreadWord, StateWord,
+};
+use super::{compression_util::*, CompressionConfig, State};
+use halo2_proofs::{
+    circuit::{Region, Value},
+    arithmetic::FieldExt,
+};
+
+impl<F: FieldExt> CompressionConfig<F> {
+    /// Adds the working variables output by the final round to the state the block
+    /// started from, returning the intermediate hash value H_i = H_{i-1} + (a..h).
+    #[allow(clippy::many_single_char_names)]
+    pub fn assign_feed_forward(
+        &self,
+        region: &mut Region<'_, F>,
+        initial_state: State<F>,
+        final_state: State<F>,
+    ) -> Result<State<F>, Sha512Error> {
+        let (a_0, b_0, c_0, d_0, e_0, f_0, g_0, h_0) = match_state(initial_state);
+        let (a, b, c, d, e, f, g, h) = match_state(final_state);
+
+        let a = self.assign_feed_forward_word(region, 0, a_0.dense_halves, a.dense_halves)?;
+        let b = self.assign_feed_forward_word(region, 1, b_0.dense_halves, b.dense_halves)?;
+        let c = self.assign_feed_forward_word(region, 2, c_0.dense_halves, c.dense_halves)?;
+        let d = self.assign_feed_forward_word(region, 3, d_0, d)?;
+        let e = self.assign_feed_forward_word(region, 4, e_0.dense_halves, e.dense_halves)?;
+        let f = self.assign_feed_forward_word(region, 5, f_0.dense_halves, f.dense_halves)?;
+        let g = self.assign_feed_forward_word(region, 6, g_0.dense_halves, g.dense_halves)?;
+        let h = self.assign_feed_forward_word(region, 7, h_0, h)?;
+
+        Ok(State::new(
+            StateWord::A(RoundWordA::new_dense(a)),
+            StateWord::B(RoundWord::new_dense(b)),
+            StateWord::C(RoundWord::new_dense(c)),
+            StateWord::D(d),
+            StateWord::E(RoundWordE::new_dense(e)),
+            StateWord::F(RoundWord::new_dense(f)),
+            StateWord::G(RoundWord::new_dense(g)),
+            StateWord::H(h),
+        ))
+    }
+
+    fn assign_feed_forward_word(
+        &self,
+        region: &mut Region<'_, F>,
+        word_idx: usize,
+        prev: RoundWordDense<F>,
+        cur: RoundWordDense<F>,
+    ) -> Result<RoundWordDense<F>, Sha512Error> {
+        let a_3 = self.extras[0];
+        let a_4 = self.extras[1];
+        let a_5 = self.message_schedule;
+        let a_6 = self.extras[2];
+
+        let row = get_feed_forward_row(word_idx);
+        self.s_feed_forward.enable(region, row)?;
+
+        prev.0.copy_advice(|| "prev_lo", region, a_3, row)?;
+        prev.1.copy_advice(|| "prev_hi", region, a_3, row + 1)?;
+        cur.0.copy_advice(|| "cur_lo", region, a_4, row)?;
+        cur.1.copy_advice(|| "cur_hi", region, a_4, row + 1)?;
+
+        let (sum, carry) = sum_with_carry(vec![
+            (prev.0.value_u32(), prev.1.value_u32()),
+            (cur.0.value_u32(), cur.1.value_u32()),
+        ]);
+
+        region.assign_advice(
+            || "feed_forward_carry",
+            a_6,
+            row,
+            || carry.map(|value| F::from(value)),
+        )?;
+
+        // Range-check both output halves through four 16-bit lookups.
+        let sum: Value<[bool; 64]> = sum.map(|w| i2lebsp(w.into()));
+        for piece in 0..4 {
+            let word = sum.map(|w| SpreadWord::<16, 32>::try_new(&w[piece * 16..(piece + 1) * 16]));
+            SpreadVar::with_lookup(region, &self.lookup, row + piece, transpose_result(word)?)?;
+        }
+
+        let lo = {
+            let lo: Value<[bool; 32]> = transpose_result(sum.map(|w| try_into_bits(&w[..32])))?;
+            AssignedBits::<F, 32>::assign_bits(region, || "feed_forward_lo", a_5, row, lo)?
+        };
+        let hi = {
+            let hi: Value<[bool; 32]> = transpose_result(sum.map(|w| try_into_bits(&w[32..])))?;
+            AssignedBits::<F, 32>::assign_bits(region, || "feed_forward_hi", a_5, row + 1, hi)?
+        };
+
+        Ok((lo, hi).into())
+    }
+}
@@ -1,43 +1,66 @@
-use super::super::{RoundWord, StateWord, STATE};
+use super::super::{
+    super::MidstateWord, util::*, AssignedBits, AssignedWord, RoundWord, RoundWordA,
+    RoundWordDense, RoundWordE, Sha512Error, SpreadVar, SpreadWord, StateWord, STATE,
+};
 use super::{compression_util::*, CompressionConfig, State};
 use halo2_proofs::{
     circuit::{Region, Value},
-    halo2curves::bn256,
+    arithmetic::FieldExt,
     plonk::Error,
 };
+use std::convert::TryInto;
 
-impl CompressionConfig {
+impl<F: FieldExt> CompressionConfig<F> {
+    /// Assigns `iv` as the initial state, constraining each word to its constant
+    /// value.
     #[allow(clippy::many_single_char_names)]
     pub fn initialize_iv(
         &self,
-        region: &mut Region<'_, bn256::Fr>,
+        region: &mut Region<'_, F>,
         iv: [u64; STATE],
-    ) -> Result<State, Error> {
+    ) -> Result<State<F>, Sha512Error> {
+        let witness = iv.map(Value::known);
         let a_7 = self.extras[3];
 
         // Decompose E into (14, 4, 23, 23)-bit chunks
-        let e = self.decompose_e(region, RoundIdx::Init, Value::known(iv[4]))?;
+        let e = self.decompose_e(region, RoundIdx::Init, witness[4])?;
 
         // Decompose F, G
-        let f = self.decompose_f(region, InitialRound, Value::known(iv[5]))?;
-        let g = self.decompose_g(region, InitialRound, Value::known(iv[6]))?;
+        let f = self.decompose_f(region, InitialRound, witness[5])?;
+        let g = self.decompose_g(region, InitialRound, witness[6])?;
 
         // Assign H
         let h_row = get_h_row(RoundIdx::Init);
-        let h =
-            self.assign_word_halves_dense(region, h_row, a_7, h_row + 1, a_7, Value::known(iv[7]))?;
+        let h = self.assign_word_halves_dense(region, h_row, a_7, h_row + 1, a_7, witness[7])?;
 
         // Decompose A into (28, 6, 5, 25)-bit chunks
-        let a = self.decompose_a(region, RoundIdx::Init, Value::known(iv[0]))?;
+        let a = self.decompose_a(region, RoundIdx::Init, witness[0])?;
 
         // Decompose B, C
-        let b = self.decompose_b(region, InitialRound, Value::known(iv[1]))?;
-        let c = self.decompose_c(region, InitialRound, Value::known(iv[2]))?;
+        let b = self.decompose_b(region, InitialRound, witness[1])?;
+        let c = self.decompose_c(region, InitialRound, witness[2])?;
 
         // Assign D
         let d_row = get_d_row(RoundIdx::Init);
-        let d =
-            self.assign_word_halves_dense(region, d_row, a_7, d_row + 1, a_7, Value::known(iv[3]))?;
+        let d = self.assign_word_halves_dense(region, d_row, a_7, d_row + 1, a_7, witness[3])?;
+
+        // Fix the words to the IV.
+        for (word, iv) in [
+            &a.dense_halves,
+            &b.dense_halves,
+            &c.dense_halves,
+            &d,
+            &e.dense_halves,
+            &f.dense_halves,
+            &g.dense_halves,
+            &h,
+        ]
+        .into_iter()
+        .zip(iv.iter())
+        {
+            region.constrain_constant(word.0.cell(), F::from(iv & 0xffffffff))?;
+            region.constrain_constant(word.1.cell(), F::from(iv >> 32))?;
+        }
 
         Ok(State::new(
             StateWord::A(a),
@@ -51,63 +74,147 @@ impl CompressionConfig {
         ))
     }
 
+    /// Assigns an intermediate hash value whose words are assigned elsewhere in the
+    /// circuit or fixed as a state. Unlike a state output by a compression, these
+    /// words are not known to fit in 64 bits, so each is split into 32-bit halves
+    /// range-checked through four 16-bit lookups.
+    #[allow(clippy::many_single_char_names)]
+    pub fn assign_midstate(
+        &self,
+        region: &mut Region<'_, F>,
+        midstate: [MidstateWord<AssignedWord<F>>; STATE],
+    ) -> Result<State<F>, Sha512Error> {
+        let words = midstate
+            .iter()
+            .enumerate()
+            .map(|(idx, word)| self.assign_midstate_word(region, idx, word))
+            .collect::<Result<Vec<_>, _>>()?;
+        let [a, b, c, d, e, f, g, h]: [RoundWordDense<F>; STATE] =
+            words.try_into().expect("midstate.len() == STATE");
+
+        Ok(State::new(
+            StateWord::A(RoundWordA::new_dense(a)),
+            StateWord::B(RoundWord::new_dense(b)),
+            StateWord::C(RoundWord::new_dense(c)),
+            StateWord::D(d),
+            StateWord::E(RoundWordE::new_dense(e)),
+            StateWord::F(RoundWord::new_dense(f)),
+            StateWord::G(RoundWord::new_dense(g)),
+            StateWord::H(h),
+        ))
+    }
+
+    fn assign_midstate_word(
+        &self,
+        region: &mut Region<'_, F>,
+        word_idx: usize,
+        word: &MidstateWord<AssignedWord<F>>,
+    ) -> Result<RoundWordDense<F>, Sha512Error> {
+        let a_3 = self.extras[0];
+        let a_4 = self.extras[1];
+
+        let row = get_midstate_row(word_idx);
+        self.s_midstate.enable(region, row)?;
+
+        let value = match word {
+            MidstateWord::Assigned(cell) => {
+                cell.copy_advice(|| "word", region, a_4, row)?;
+                cell.value().map(|word| word.get_lower_128() as u64)
+            }
+            MidstateWord::Constant(word) => {
+                region.assign_advice_from_constant(|| "word", a_4, row, F::from(*word))?;
+                Value::known(*word)
+            }
+        };
+
+        // Range-check both halves through four 16-bit lookups.
+        let value: Value<[bool; 64]> = value.map(|w| i2lebsp(w.into()));
+        for piece in 0..4 {
+            let chunk = value.map(|w| SpreadWord::<16, 32>::try_new(&w[piece * 16..(piece + 1) * 16]));
+            SpreadVar::with_lookup(region, &self.lookup, row + piece, transpose_result(chunk)?)?;
+        }
+
+        let lo = {
+            let lo: Value<[bool; 32]> = transpose_result(value.map(|w| try_into_bits(&w[..32])))?;
+            AssignedBits::<F, 32>::assign_bits(region, || "midstate_lo", a_3, row, lo)?
+        };
+        let hi = {
+            let hi: Value<[bool; 32]> = transpose_result(value.map(|w| try_into_bits(&w[32..])))?;
+            AssignedBits::<F, 32>::assign_bits(region, || "midstate_hi", a_3, row + 1, hi)?
+        };
+
+        Ok((lo, hi).into())
+    }
+
     #[allow(clippy::many_single_char_names)]
     pub fn initialize_state(
         &self,
-        region: &mut Region<'_, bn256::Fr>,
-        state: State,
-    ) -> Result<State, Error> {
+        region: &mut Region<'_, F>,
+        state: State<F>,
+    ) -> Result<State<F>, Sha512Error> {
         let a_7 = self.extras[3];
         let (a, b, c, d, e, f, g, h) = match_state(state);
 
         // Decompose E into (14, 4, 23, 23)-bit chunks
-        let e = e.dense_halves.value();
-        let e = self.decompose_e(region, RoundIdx::Init, e)?;
+        let e_new = self.decompose_e(region, RoundIdx::Init, e.dense_halves.value())?;
+        self.constrain_dense_halves(region, &e.dense_halves, &e_new.dense_halves)?;
 
         // Decompose F, G
-        let f = f.dense_halves.value();
-        let f = self.decompose_f(region, InitialRound, f)?;
-        let g = g.dense_halves.value();
-        let g = self.decompose_g(region, InitialRound, g)?;
+        let f_new = self.decompose_f(region, InitialRound, f.dense_halves.value())?;
+        self.constrain_dense_halves(region, &f.dense_halves, &f_new.dense_halves)?;
+        let g_new = self.decompose_g(region, InitialRound, g.dense_halves.value())?;
+        self.constrain_dense_halves(region, &g.dense_halves, &g_new.dense_halves)?;
 
         // Assign H
-        let h = h.value();
         let h_row = get_h_row(RoundIdx::Init);
-        let h = self.assign_word_halves_dense(region, h_row, a_7, h_row + 1, a_7, h)?;
+        let h_new = self.assign_word_halves_dense(region, h_row, a_7, h_row + 1, a_7, h.value())?;
+        self.constrain_dense_halves(region, &h, &h_new)?;
 
         // Decompose A into (28, 6, 5, 25)-bit chunks
-        let a = a.dense_halves.value();
-        let a = self.decompose_a(region, RoundIdx::Init, a)?;
+        let a_new = self.decompose_a(region, RoundIdx::Init, a.dense_halves.value())?;
+        self.constrain_dense_halves(region, &a.dense_halves, &a_new.dense_halves)?;
 
         // Decompose B, C
-        let b = b.dense_halves.value();
-        let b = self.decompose_b(region, InitialRound, b)?;
-        let c = c.dense_halves.value();
-        let c = self.decompose_c(region, InitialRound, c)?;
+        let b_new = self.decompose_b(region, InitialRound, b.dense_halves.value())?;
+        self.constrain_dense_halves(region, &b.dense_halves, &b_new.dense_halves)?;
+        let c_new = self.decompose_c(region, InitialRound, c.dense_halves.value())?;
+        self.constrain_dense_halves(region, &c.dense_halves, &c_new.dense_halves)?;
 
         // Assign D
-        let d = d.value();
         let d_row = get_d_row(RoundIdx::Init);
-        let d = self.assign_word_halves_dense(region, d_row, a_7, d_row + 1, a_7, d)?;
+        let d_new = self.assign_word_halves_dense(region, d_row, a_7, d_row + 1, a_7, d.value())?;
+        self.constrain_dense_halves(region, &d, &d_new)?;
 
         Ok(State::new(
-            StateWord::A(a),
-            StateWord::B(b),
-            StateWord::C(c),
-            StateWord::D(d),
-            StateWord::E(e),
-            StateWord::F(f),
-            StateWord::G(g),
-            StateWord::H(h),
+            StateWord::A(a_new),
+            StateWord::B(b_new),
+            StateWord::C(c_new),
+            StateWord::D(d_new),
+            StateWord::E(e_new),
+            StateWord::F(f_new),
+            StateWord::G(g_new),
+            StateWord::H(h_new),
         ))
     }
 
+    /// Constrains a re-assigned word to equal the word it was taken from, so that
+    /// each block starts from the state the previous block produced.
+    fn constrain_dense_halves(
+        &self,
+        region: &mut Region<'_, F>,
+        prev: &RoundWordDense<F>,
+        new: &RoundWordDense<F>,
+    ) -> Result<(), Error> {
+        region.constrain_equal(prev.0.cell(), new.0.cell())?;
+        region.constrain_equal(prev.1.cell(), new.1.cell())
+    }
+
     fn decompose_b(
         &self,
-        region: &mut Region<'_, bn256::Fr>,
+        region: &mut Region<'_, F>,
         round_idx: InitialRound,
         b_val: Value<u64>,
-    ) -> Result<RoundWord, Error> {
+    ) -> Result<RoundWord<F>, Sha512Error> {
         let row = get_decompose_b_row(round_idx);
 
         let (dense_halves, spread_halves) = self.assign_word_halves(region, row, b_val)?;
@@ -117,10 +224,10 @@ impl CompressionConfig {
 
     fn decompose_c(
         &self,
-        region: &mut Region<'_, bn256::Fr>,
+        region: &mut Region<'_, F>,
         round_idx: InitialRound,
         c_val: Value<u64>,
-    ) -> Result<RoundWord, Error> {
+    ) -> Result<RoundWord<F>, Sha512Error> {
         let row = get_decompose_c_row(round_idx);
 
         let (dense_halves, spread_halves) = self.assign_word_halves(region, row, c_val)?;
@@ -130,10 +237,10 @@ impl CompressionConfig {
 
     fn decompose_f(
         &self,
-        region: &mut Region<'_, bn256::Fr>,
+        region: &mut Region<'_, F>,
         round_idx: InitialRound,
         f_val: Value<u64>,
-    ) -> Result<RoundWord, Error> {
+    ) -> Result<RoundWord<F>, Sha512Error> {
         let row = get_decompose_f_row(round_idx);
 
         let (dense_halves, spread_halves) = self.assign_word_halves(region, row, f_val)?;
@@ -143,10 +250,10 @@ impl CompressionConfig {
 
     fn decompose_g(
         &self,
-        region: &mut Region<'_, bn256::Fr>,
+        region: &mut Region<'_, F>,
         round_idx: InitialRound,
         g_val: Value<u64>,
-    ) -> Result<RoundWord, Error> {
+    ) -> Result<RoundWord<F>, Sha512Error> {
         let row = get_decompose_g_row(round_idx);
 
         let (dense_halves, spread_halves) = self.assign_word_halves(region, row, g_val)?;
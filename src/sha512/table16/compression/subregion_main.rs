@@ -1,16 +1,45 @@
-use super::super::{AssignedBits, RoundWord, RoundWordA, RoundWordE, StateWord, ROUND_CONSTANTS};
-use super::{compression_util::*, CompressionConfig, State};
-use halo2_proofs::{circuit::Region, halo2curves::bn256, plonk::Error};
-
-impl CompressionConfig {
+use super::super::{AssignedBits, RoundWord, RoundWordA, RoundWordE, Sha512Error, StateWord};
+use super::{compression_util::*, CompressionConfig, RoundWordDense, State};
+use halo2_proofs::{
+    circuit::Region,
+    arithmetic::FieldExt,
+};
+
+/// The cells holding the intermediate values of a compression round, each as its low
+/// and high halves.
+#[allow(dead_code)]
+#[derive(Clone, Debug)]
+pub(super) struct RoundCells<F: FieldExt> {
+    /// Σ_0(A), the even bits of the spread sum of the rotations of A.
+    pub sigma_0: (AssignedBits<F, 32>, AssignedBits<F, 32>),
+    /// Σ_1(E), the even bits of the spread sum of the rotations of E.
+    pub sigma_1: (AssignedBits<F, 32>, AssignedBits<F, 32>),
+    /// E ∧ F, the odd bits of the spread sum of E and F.
+    pub ch: (AssignedBits<F, 32>, AssignedBits<F, 32>),
+    /// ¬E ∧ G, the odd bits of the spread sum of ¬E and G.
+    pub ch_neg: (AssignedBits<F, 32>, AssignedBits<F, 32>),
+    /// Maj(A, B, C), the odd bits of the spread sum of A, B and C.
+    pub maj: (AssignedBits<F, 32>, AssignedBits<F, 32>),
+    /// H' = H + Ch(E, F, G) + Σ_1(E) + K + W.
+    pub h_prime: RoundWordDense<F>,
+    /// A_new = H' + Maj(A, B, C) + Σ_0(A).
+    pub a_new: RoundWordDense<F>,
+    /// E_new = H' + D.
+    pub e_new: RoundWordDense<F>,
+}
+
+impl<F: FieldExt> CompressionConfig<F> {
+    /// Assigns a compression round, constraining its round constant to
+    /// `ROUND_CONSTANTS[round_idx]`. Returns the state after the round and the cells
+    /// of its intermediate values.
     #[allow(clippy::many_single_char_names)]
-    pub fn assign_round(
+    pub(super) fn assign_round(
         &self,
-        region: &mut Region<'_, bn256::Fr>,
+        region: &mut Region<'_, F>,
         round_idx: MainRoundIdx,
-        state: State,
-        schedule_word: &(AssignedBits<32>, AssignedBits<32>),
-    ) -> Result<State, Error> {
+        state: State<F>,
+        schedule_word: &(AssignedBits<F, 32>, AssignedBits<F, 32>),
+    ) -> Result<(State<F>, RoundCells<F>), Sha512Error> {
         let a_3 = self.extras[0];
         let a_4 = self.extras[1];
         let a_7 = self.extras[3];
@@ -26,13 +55,13 @@ impl CompressionConfig {
             region,
             round_idx,
             e.spread_halves.clone().unwrap(),
-            f.spread_halves.clone(),
+            f.spread_halves.clone().unwrap(),
         )?;
         let ch_neg = self.assign_ch_neg(
             region,
             round_idx,
             e.spread_halves.clone().unwrap(),
-            g.spread_halves.clone(),
+            g.spread_halves.clone().unwrap(),
         )?;
 
         // s_upper_sigma_0(A)
@@ -43,8 +72,8 @@ impl CompressionConfig {
             region,
             round_idx,
             a.spread_halves.clone().unwrap(),
-            b.spread_halves.clone(),
-            c.spread_halves.clone(),
+            b.spread_halves.clone().unwrap(),
+            c.spread_halves.clone().unwrap(),
         )?;
 
         // H' = H + Ch(E, F, G) + s_upper_sigma_1(E) + K + W
@@ -52,10 +81,9 @@ impl CompressionConfig {
             region,
             round_idx,
             h,
-            ch,
-            ch_neg,
-            sigma_1,
-            ROUND_CONSTANTS[round_idx.as_usize()],
+            ch.clone(),
+            ch_neg.clone(),
+            sigma_1.clone(),
             schedule_word,
         )?;
 
@@ -64,10 +92,16 @@ impl CompressionConfig {
         let e_new_val = e_new_dense.value();
 
         // A_new = H' + Maj(A, B, C) + sigma_0(A)
-        let a_new_dense = self.assign_a_new(region, round_idx, maj, sigma_0, h_prime)?;
+        let a_new_dense = self.assign_a_new(
+            region,
+            round_idx,
+            maj.clone(),
+            sigma_0.clone(),
+            h_prime.clone(),
+        )?;
         let a_new_val = a_new_dense.value();
 
-        if round_idx < 79.into() {
+        let state = if round_idx < 79.into() {
             // Assign and copy A_new
             let a_new_row = get_decompose_a_row((round_idx + 1).into());
             a_new_dense
@@ -92,7 +126,7 @@ impl CompressionConfig {
             // Decompose E into (14, 4, 23, 23)-bit chunks
             let e_new = self.decompose_e(region, (round_idx + 1).into(), e_new_val)?;
 
-            Ok(State::new(
+            State::new(
                 StateWord::A(a_new),
                 StateWord::B(RoundWord::new(a.dense_halves, a.spread_halves.unwrap())),
                 StateWord::C(b),
@@ -101,18 +135,30 @@ impl CompressionConfig {
                 StateWord::F(RoundWord::new(e.dense_halves, e.spread_halves.unwrap())),
                 StateWord::G(f),
                 StateWord::H(g.dense_halves),
-            ))
+            )
         } else {
             let abcd_row = get_digest_abcd_row();
             let efgh_row = get_digest_efgh_row();
 
-            let a_final =
-                self.assign_word_halves_dense(region, abcd_row, a_3, abcd_row, a_4, a_new_val)?;
+            // Copy A_new and E_new, so that the feed-forward adds the cells the last
+            // round constrains.
+            let lo = a_new_dense
+                .0
+                .copy_advice(|| "a_final_lo", region, a_3, abcd_row)?;
+            let hi = a_new_dense
+                .1
+                .copy_advice(|| "a_final_hi", region, a_4, abcd_row)?;
+            let a_final: RoundWordDense<F> = (AssignedBits(lo), AssignedBits(hi)).into();
 
-            let e_final =
-                self.assign_word_halves_dense(region, efgh_row, a_3, efgh_row, a_4, e_new_val)?;
+            let lo = e_new_dense
+                .0
+                .copy_advice(|| "e_final_lo", region, a_3, efgh_row)?;
+            let hi = e_new_dense
+                .1
+                .copy_advice(|| "e_final_hi", region, a_4, efgh_row)?;
+            let e_final: RoundWordDense<F> = (AssignedBits(lo), AssignedBits(hi)).into();
 
-            Ok(State::new(
+            State::new(
                 StateWord::A(RoundWordA::new_dense(a_final)),
                 StateWord::B(RoundWord::new(a.dense_halves, a.spread_halves.unwrap())),
                 StateWord::C(b),
@@ -121,7 +167,19 @@ impl CompressionConfig {
                 StateWord::F(RoundWord::new(e.dense_halves, e.spread_halves.unwrap())),
                 StateWord::G(f),
                 StateWord::H(g.dense_halves),
-            ))
-        }
+            )
+        };
+
+        let cells = RoundCells {
+            sigma_0,
+            sigma_1,
+            ch,
+            ch_neg,
+            maj,
+            h_prime,
+            a_new: a_new_dense,
+            e_new: e_new_dense,
+        };
+        Ok((state, cells))
     }
 }
\ No newline at end of file
@@ -1,10 +1,15 @@
 use std::convert::TryInto;
+use std::marker::PhantomData;
 
-use super::{super::BLOCK_SIZE, AssignedBits, BlockWord, SpreadInputs, Table16Assignment, ROUNDS};
+use super::{
+    super::{BlockInput, Sha512Error, BLOCK_SIZE},
+    util::assign_region,
+    AssignedBits, AssignedWord, BlockWord, SpreadInputs, Table16Assignment, ROUNDS,
+};
+use halo2_proofs::arithmetic::FieldExt;
 use halo2_proofs::{
-    circuit::Layouter,
-    halo2curves::bn256,
-    plonk::{Advice, Column, ConstraintSystem, Error, Selector},
+    circuit::{Layouter, Region, Value},
+    plonk::{Advice, Column, ConstraintSystem, Selector},
     poly::Rotation,
 };
 
@@ -17,14 +22,19 @@ mod subregion3;
 use schedule_gates::ScheduleGate;
 use schedule_util::*;
 
+pub(crate) use schedule_util::get_word_idx;
+
+/// The name of the region the message schedule of a block is assigned in.
+pub(crate) const SCHEDULE_REGION: &str = "process message block";
+
 #[cfg(test)]
 pub use schedule_util::msg_schedule_test_input;
 
 #[derive(Clone, Debug)]
-pub(super) struct MessageWord(AssignedBits<64>);
+pub(super) struct MessageWord<F: FieldExt>(AssignedBits<F, 64>);
 
-impl std::ops::Deref for MessageWord {
-    type Target = AssignedBits<64>;
+impl<F: FieldExt> std::ops::Deref for MessageWord<F> {
+    type Target = AssignedBits<F, 64>;
 
     fn deref(&self) -> &Self::Target {
         &self.0
@@ -32,7 +42,7 @@ impl std::ops::Deref for MessageWord {
 }
 
 #[derive(Clone, Debug)]
-pub(super) struct MessageScheduleConfig {
+pub(super) struct MessageScheduleConfig<F: FieldExt> {
     lookup: SpreadInputs,
     message_schedule: Column<Advice>,
     extras: [Column<Advice>; 6],
@@ -55,11 +65,13 @@ pub(super) struct MessageScheduleConfig {
     s_lower_sigma_0_v2: Selector,
     /// sigma_1_v2 gate for W_[14..65]
     s_lower_sigma_1_v2: Selector,
+
+    _marker: PhantomData<F>,
 }
 
-impl Table16Assignment for MessageScheduleConfig {}
+impl<F: FieldExt> Table16Assignment<F> for MessageScheduleConfig<F> {}
 
-impl MessageScheduleConfig {
+impl<F: FieldExt> MessageScheduleConfig<F> {
     /// Configures the message schedule.
     ///
     /// `message_schedule` is the column into which the message schedule will be placed.
@@ -71,7 +83,7 @@ impl MessageScheduleConfig {
     /// itself.
     #[allow(clippy::many_single_char_names)]
     pub(super) fn configure(
-        meta: &mut ConstraintSystem<bn256::Fr>,
+        meta: &mut ConstraintSystem<F>,
         lookup: SpreadInputs,
         message_schedule: Column<Advice>,
         extras: [Column<Advice>; 6],
@@ -350,133 +362,190 @@ impl MessageScheduleConfig {
             s_lower_sigma_1,
             s_lower_sigma_0_v2,
             s_lower_sigma_1_v2,
+            _marker: PhantomData,
         }
     }
 
+    /// Assigns the message schedule for a block. Padding words in `input` are
+    /// constrained to their fixed values.
     #[allow(clippy::type_complexity)]
     pub(super) fn process(
         &self,
-        layouter: &mut impl Layouter<bn256::Fr>,
-        input: [BlockWord; BLOCK_SIZE],
+        layouter: &mut impl Layouter<F>,
+        input: [BlockInput<BlockWord, AssignedWord<F>>; BLOCK_SIZE],
     ) -> Result<
         (
-            [MessageWord; ROUNDS],
-            [(AssignedBits<32>, AssignedBits<32>); ROUNDS],
+            [MessageWord<F>; ROUNDS],
+            [(AssignedBits<F, 32>, AssignedBits<F, 32>); ROUNDS],
         ),
-        Error,
+        Sha512Error,
     > {
-        let mut w = Vec::<MessageWord>::with_capacity(ROUNDS);
-        let mut w_halves = Vec::<(AssignedBits<32>, AssignedBits<32>)>::with_capacity(ROUNDS);
-
-        layouter.assign_region(
-            || "process message block",
-            |mut region| {
-                w = Vec::<MessageWord>::with_capacity(ROUNDS);
-                w_halves = Vec::<(AssignedBits<32>, AssignedBits<32>)>::with_capacity(ROUNDS);
-
-                // Assign all fixed columns
-                for index in 1..14 {
-                    let row = get_word_row(index);
-                    self.s_decompose_1.enable(&mut region, row)?;
-                    self.s_lower_sigma_0.enable(&mut region, row + 6)?;
-                }
+        let (w, w_halves) = assign_region(
+            layouter,
+            || SCHEDULE_REGION,
+            |mut region| self.assign_schedule(&mut region, &input),
+        )?;
 
-                for index in 14..65 {
-                    let row = get_word_row(index);
-                    self.s_decompose_2.enable(&mut region, row)?;
-                    self.s_lower_sigma_0_v2.enable(&mut region, row + 6)?;
-                    self.s_lower_sigma_1_v2
-                        .enable(&mut region, row + SIGMA_0_V2_ROWS + 6)?;
+        #[cfg(feature = "dev-trace")]
+        for (idx, word) in w.iter().enumerate() {
+            crate::sha512::dev_trace::record_word(
+                SCHEDULE_REGION,
+                idx,
+                word.0.value_u64(),
+                get_word_rows(idx),
+            );
+        }
 
-                    let new_word_idx = index + 2;
-                    self.s_word
-                        .enable(&mut region, get_word_row(new_word_idx - 16) + 1)?;
-                }
+        let wrong_length = |actual| Sha512Error::WrongLength {
+            expected: ROUNDS,
+            actual,
+        };
+        let (w_len, w_halves_len) = (w.len(), w_halves.len());
+        let w = w.try_into().map_err(|_| wrong_length(w_len))?;
+        let w_halves = w_halves
+            .try_into()
+            .map_err(|_| wrong_length(w_halves_len))?;
+        Ok((w, w_halves))
+    }
 
-                for index in 65..78 {
-                    let row = get_word_row(index);
-                    self.s_decompose_3.enable(&mut region, row)?;
-                    self.s_lower_sigma_1.enable(&mut region, row + 6)?;
+    /// Assigns the message schedule for a block in `region`, returning the words
+    /// W_0..W_79 and their halves.
+    #[allow(clippy::type_complexity)]
+    fn assign_schedule(
+        &self,
+        region: &mut Region<'_, F>,
+        input: &[BlockInput<BlockWord, AssignedWord<F>>; BLOCK_SIZE],
+    ) -> Result<
+        (
+            Vec<MessageWord<F>>,
+            Vec<(AssignedBits<F, 32>, AssignedBits<F, 32>)>,
+        ),
+        Sha512Error,
+    > {
+        let mut w = Vec::<MessageWord<F>>::with_capacity(ROUNDS);
+        let mut w_halves = Vec::<(AssignedBits<F, 32>, AssignedBits<F, 32>)>::with_capacity(ROUNDS);
+
+        let words: Vec<Value<u64>> = input
+            .iter()
+            .map(|word| match word {
+                BlockInput::Message(word) => word.0,
+                BlockInput::Assigned(cell) => cell.value().map(|v| v.get_lower_128() as u64),
+                BlockInput::Padding(word) => Value::known(*word),
+            })
+            .collect();
+
+        // Assign all fixed columns
+        for index in 1..14 {
+            let row = get_word_row(index);
+            self.s_decompose_1.enable(region, row)?;
+            self.s_lower_sigma_0.enable(region, row + 6)?;
+        }
 
-                    let new_word_idx = index + 2;
-                    self.s_word
-                        .enable(&mut region, get_word_row(new_word_idx - 16) + 1)?;
-                }
+        for index in 14..65 {
+            let row = get_word_row(index);
+            self.s_decompose_2.enable(region, row)?;
+            self.s_lower_sigma_0_v2.enable(region, row + 6)?;
+            self.s_lower_sigma_1_v2
+                .enable(region, row + SIGMA_0_V2_ROWS + 6)?;
 
-                for index in 0..80 {
-                    let row = get_word_row(index);
-                    self.s_decompose_0.enable(&mut region, row)?;
-                }
+            let new_word_idx = index + 2;
+            self.s_word
+                .enable(region, get_word_row(new_word_idx - 16) + 1)?;
+        }
 
-                // Assign W[0..16]
-                for (i, word) in input.iter().enumerate() {
-                    let (word, halves) = self.assign_word_and_halves(&mut region, word.0, i)?;
-                    w.push(MessageWord(word));
-                    w_halves.push(halves);
-                }
+        for index in 65..78 {
+            let row = get_word_row(index);
+            self.s_decompose_3.enable(region, row)?;
+            self.s_lower_sigma_1.enable(region, row + 6)?;
 
-                // Returns the output of sigma_0 on W_[1..14]
-                let lower_sigma_0_output = self.assign_subregion1(&mut region, &input[1..14])?;
+            let new_word_idx = index + 2;
+            self.s_word
+                .enable(region, get_word_row(new_word_idx - 16) + 1)?;
+        }
 
+        for index in 0..80 {
+            let row = get_word_row(index);
+            self.s_decompose_0.enable(region, row)?;
+        }
 
-                // sigma_0_v2 and sigma_1_v2 on W_[14..65]
-                // Returns the output of sigma_0_v2 on W_[36..49], to be used in subregion3 //this could be wrong, but we are not sure
-                let lower_sigma_0_v2_output = self.assign_subregion2(
-                    &mut region,
-                    lower_sigma_0_output,
-                    &mut w,
-                    &mut w_halves,
-                )?;
+        // Assign W[0..16]
+        for (i, (word, value)) in input.iter().zip(words.iter()).enumerate() {
+            let (assigned, halves) = self.assign_word_and_halves(region, *value, i)?;
 
-                // sigma_1 v1 on W[65..78]
-                self.assign_subregion3(
-                    &mut region,
-                    lower_sigma_0_v2_output,
-                    &mut w,
-                    &mut w_halves,
-                )?;
+            match word {
+                BlockInput::Message(_) => (),
+                // Tie the word to the cell it was computed in; the decomposition
+                // gates below range-check it as usual.
+                BlockInput::Assigned(cell) => {
+                    region.constrain_equal(cell.cell(), assigned.cell())?;
+                }
+                // Padding is not chosen by the prover, so pin the word and both
+                // halves to the constant.
+                BlockInput::Padding(word) => {
+                    region.constrain_constant(assigned.cell(), F::from(*word))?;
+                    region.constrain_constant(halves.0.cell(), F::from(*word & 0xffffffff))?;
+                    region.constrain_constant(halves.1.cell(), F::from(*word >> 32))?;
+                }
+            }
 
-                Ok(())
-            },
-        )?;
+            w.push(MessageWord(assigned));
+            w_halves.push(halves);
+        }
 
-        Ok((w.try_into().unwrap(), w_halves.try_into().unwrap()))
+        // Returns the output of sigma_0 on W_[1..14]
+        let lower_sigma_0_output = self.assign_subregion1(region, &words[1..14])?;
+
+        // sigma_0_v2 and sigma_1_v2 on W_[14..65]
+        // Returns the output of sigma_0_v2 on W_[36..49], to be used in subregion3 //this could be wrong, but we are not sure
+        let lower_sigma_0_v2_output =
+            self.assign_subregion2(region, lower_sigma_0_output, &mut w, &mut w_halves)?;
+
+        // sigma_1 v1 on W[65..78]
+        self.assign_subregion3(region, lower_sigma_0_v2_output, &mut w, &mut w_halves)?;
+
+        Ok((w, w_halves))
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::super::{
-        super::BLOCK_SIZE, util::lebs2ip, BlockWord, SpreadTableChip, Table16Chip, Table16Config,
+        super::{BlockInput, BLOCK_SIZE},
+        util::lebs2ip,
+        BlockWord, SpreadTableChip, Table16Chip, Table16Config,
     };
     use super::schedule_util::*;
+    use super::SCHEDULE_REGION;
+    use crate::sha512::diagnostics::{diagnose, Diagnosis, Location};
+    use crate::sha512::native::{lower_sigma_0, lower_sigma_1};
+    use crate::sha512::trace::spread;
     use halo2_proofs::{
-        circuit::{Layouter, SimpleFloorPlanner},
+        arithmetic::FieldExt,
+        circuit::{Layouter, SimpleFloorPlanner, Value},
         dev::MockProver,
-        halo2curves::bn256,
+        halo2curves::{bn256, pasta::pallas},
         plonk::{Circuit, ConstraintSystem, Error},
     };
 
-    #[test]
-    fn message_schedule() {
+    fn message_schedule<F: FieldExt>() {
         struct MyCircuit {}
 
-        impl Circuit<bn256::Fr> for MyCircuit {
-            type Config = Table16Config;
+        impl<F: FieldExt> Circuit<F> for MyCircuit {
+            type Config = Table16Config<F>;
             type FloorPlanner = SimpleFloorPlanner;
 
             fn without_witnesses(&self) -> Self {
                 MyCircuit {}
             }
 
-            fn configure(meta: &mut ConstraintSystem<bn256::Fr>) -> Self::Config {
+            fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
                 Table16Chip::configure(meta)
             }
 
             fn synthesize(
                 &self,
                 config: Self::Config,
-                mut layouter: impl Layouter<bn256::Fr>,
+                mut layouter: impl Layouter<F>,
             ) -> Result<(), Error> {
                 // Load lookup table
                 SpreadTableChip::load(config.lookup.clone(), &mut layouter)?;
@@ -486,7 +555,9 @@ mod tests {
                 let inputs: [BlockWord; BLOCK_SIZE] = msg_schedule_test_input();
 
                 // Run message_scheduler to get W_[0..80]
-                let (w, _) = config.message_schedule.process(&mut layouter, inputs)?;
+                let (w, _) = config
+                    .message_schedule
+                    .process(&mut layouter, inputs.map(BlockInput::Message))?;
             
                 for (word, test_word) in w.iter().zip(MSG_SCHEDULE_TEST_OUTPUT.iter()) {
                     word.value().assert_if_known(|bits| {
@@ -500,10 +571,153 @@ mod tests {
         }
 
         let circuit: MyCircuit = MyCircuit {};
-        let prover = match MockProver::<bn256::Fr>::run(17, &circuit, vec![]) {
+        let prover = match MockProver::<F>::run(17, &circuit, vec![]) {
             Ok(prover) => prover,
             Err(e) => panic!("{:?}", e),
         };
         prover.assert_satisfied();
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn message_schedule_bn256() {
+        message_schedule::<bn256::Fr>();
+    }
+
+    #[test]
+    fn message_schedule_pallas() {
+        message_schedule::<pallas::Base>();
+    }
+
+    /// Assigns the message schedule of the "abc" test block, then overwrites a cell of
+    /// its region with `value`, or with -1 if `value` is `None`. The cell is given by
+    /// its column a_i in the Table16 layout and its row.
+    struct TamperCircuit {
+        column: usize,
+        row: usize,
+        value: Option<u128>,
+    }
+
+    impl<F: FieldExt> Circuit<F> for TamperCircuit {
+        type Config = Table16Config<F>;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            TamperCircuit {
+                column: self.column,
+                row: self.row,
+                value: self.value,
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            Table16Chip::configure(meta)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            SpreadTableChip::load(config.lookup.clone(), &mut layouter)?;
+
+            let schedule = &config.message_schedule;
+            let column = match self.column {
+                0 => schedule.lookup.tag,
+                1 => schedule.lookup.dense,
+                2 => schedule.lookup.spread,
+                3 | 4 => schedule.extras[self.column - 3],
+                5 => schedule.message_schedule,
+                _ => schedule.extras[self.column - 4],
+            };
+            let value = self.value.map_or(-F::one(), F::from_u128);
+            let inputs = msg_schedule_test_input().map(BlockInput::Message);
+            layouter.assign_region(
+                || SCHEDULE_REGION,
+                |mut region| {
+                    schedule.assign_schedule(&mut region, &inputs)?;
+                    region.assign_advice(
+                        || "tampered",
+                        column,
+                        self.row,
+                        || Value::known(value),
+                    )?;
+                    Ok(())
+                },
+            )
+        }
+    }
+
+    #[test]
+    fn tampered_cells() {
+        let w = MSG_SCHEDULE_TEST_OUTPUT;
+        // The spread form of bits 16..32 of a σ output, which its row holds in a_2.
+        let sigma_hi = |sigma: u64| spread((sigma >> 16) & 0xffff);
+
+        // The selector whose gate must reject each tampered cell, the word whose rows
+        // the cell is in, the column a_i and row of the cell, and the value the cell
+        // holds. The words are overwritten in a_5, and the spread forms of σ_0 and σ_1
+        // in a_2.
+        let cases = [
+            ("s_word", 52, 5, get_word_row(52 - 16) + 1, w[52] as u128),
+            ("s_decompose_0", 0, 5, get_word_row(0), w[0] as u128),
+            ("s_decompose_1", 5, 5, get_word_row(5), w[5] as u128),
+            ("s_decompose_2", 30, 5, get_word_row(30), w[30] as u128),
+            ("s_decompose_3", 70, 5, get_word_row(70), w[70] as u128),
+            (
+                "s_lower_sigma_0",
+                5,
+                2,
+                get_word_row(5) + 6,
+                sigma_hi(lower_sigma_0(w[5])),
+            ),
+            (
+                "s_lower_sigma_0_v2",
+                30,
+                2,
+                get_word_row(30) + 6,
+                sigma_hi(lower_sigma_0(w[30])),
+            ),
+            (
+                "s_lower_sigma_1_v2",
+                30,
+                2,
+                get_word_row(30) + SIGMA_0_V2_ROWS + 6,
+                sigma_hi(lower_sigma_1(w[30])),
+            ),
+            (
+                "s_lower_sigma_1",
+                70,
+                2,
+                get_word_row(70) + 6,
+                sigma_hi(lower_sigma_1(w[70])),
+            ),
+        ];
+
+        // Each cell is overwritten with -1, which no honest cell holds, and with its
+        // honest value with the lowest bit flipped.
+        for (selector, word, column, row, honest) in cases {
+            for value in [None, Some(honest ^ 1)] {
+                let circuit = TamperCircuit { column, row, value };
+                let prover = match MockProver::<bn256::Fr>::run(17, &circuit, vec![]) {
+                    Ok(prover) => prover,
+                    Err(e) => panic!("{:?}", e),
+                };
+                let expected = Diagnosis {
+                    location: Location::ScheduleWord(word),
+                    check: format!("{} gate", selector),
+                };
+                let failures = prover.verify().unwrap_err();
+                assert!(
+                    failures
+                        .iter()
+                        .map(diagnose)
+                        .any(|diagnosis| diagnosis == expected),
+                    "{} accepted a tampered cell {:?}: {:?}",
+                    selector,
+                    value,
+                    failures
+                );
+            }
+        }
+    }
+}
@@ -1,9 +1,8 @@
-use super::super::AssignedBits;
+use super::super::{AssignedBits, Sha512Error};
 use super::MessageScheduleConfig;
 use halo2_proofs::{
     circuit::{Region, Value},
-    halo2curves::bn256,
-    plonk::Error,
+    arithmetic::FieldExt,
 };
 
 #[cfg(test)]
@@ -54,6 +53,24 @@ pub fn get_word_row(word_idx: usize) -> usize {
     }
 }
 
+/// Returns the rows taken by the gates of a word.
+pub fn get_word_rows(word_idx: usize) -> std::ops::Range<usize> {
+    let row = get_word_row(word_idx);
+    match word_idx {
+        1..=13 => row..row + SUBREGION_1_WORD,
+        // The decomposition gate of these words reads the row above the word.
+        14..=64 => row - 1..row - 1 + SUBREGION_2_WORD,
+        65..=77 => row..row + SUBREGION_3_WORD,
+        _ => row..row + DECOMPOSE_0_ROWS,
+    }
+}
+
+/// Returns the index of the word whose rows include the given row of the message
+/// schedule region.
+pub fn get_word_idx(row: usize) -> Option<usize> {
+    (0..ROUNDS).find(|&idx| get_word_rows(idx).contains(&row))
+}
+
 /// Test vector: "abc"
 #[cfg(test)]
 pub fn msg_schedule_test_input() -> [BlockWord; BLOCK_SIZE] {
@@ -162,14 +179,14 @@ pub const MSG_SCHEDULE_TEST_OUTPUT: [u64; ROUNDS] = [
     0b1001001010101110111011101101000110100111101111001111011111010010,
 ];
 
-impl MessageScheduleConfig {
+impl<F: FieldExt> MessageScheduleConfig<F> {
     // Assign a word and its hi and lo halves
     pub fn assign_word_and_halves(
         &self,
-        region: &mut Region<'_, bn256::Fr>,
+        region: &mut Region<'_, F>,
         word: Value<u64>,
         word_idx: usize,
-    ) -> Result<(AssignedBits<64>, (AssignedBits<32>, AssignedBits<32>)), Error> {
+    ) -> Result<(AssignedBits<F, 64>, (AssignedBits<F, 32>, AssignedBits<F, 32>)), Sha512Error> {
         // Rename these here for ease of matching the gates to the specification.
         let a_3 = self.extras[0];
         let a_4 = self.extras[1];
@@ -178,14 +195,14 @@ impl MessageScheduleConfig {
 
         let w_lo = {
             let w_lo_val = word.map(|word| word as u32);
-            AssignedBits::<32>::assign(region, || format!("W_{}_lo", word_idx), a_3, row, w_lo_val)?
+            AssignedBits::<F, 32>::assign(region, || format!("W_{}_lo", word_idx), a_3, row, w_lo_val)?
         };
         let w_hi = {
             let w_hi_val = word.map(|word| (word >> 32) as u32);
-            AssignedBits::<32>::assign(region, || format!("W_{}_hi", word_idx), a_4, row, w_hi_val)?
+            AssignedBits::<F, 32>::assign(region, || format!("W_{}_hi", word_idx), a_4, row, w_hi_val)?
         };
 
-        let word = AssignedBits::<64>::assign(
+        let word = AssignedBits::<F, 64>::assign(
             region,
             || format!("W_{}", word_idx),
             self.message_schedule,
@@ -1,31 +1,30 @@
-use super::super::{util::*, AssignedBits, BlockWord, SpreadVar, SpreadWord, Table16Assignment};
+use super::super::{util::*, AssignedBits, Sha512Error, SpreadVar, SpreadWord, Table16Assignment};
 use super::{schedule_util::*, MessageScheduleConfig};
 use halo2_proofs::{
     circuit::{Region, Value},
-    halo2curves::bn256,
-    plonk::Error,
+    arithmetic::FieldExt,
 };
 use std::convert::TryInto;
 
 // A word in subregion 1
 // (1, 6, 1, 56)-bit chunks
 #[derive(Debug)]
-pub struct Subregion1Word {
+pub struct Subregion1Word<F: FieldExt> {
     index: usize,
-    a: AssignedBits<1>,
-    b: AssignedBits<6>,
-    c: AssignedBits<1>,
-    _d_lo_lo: AssignedBits<14>,
-    _d_lo_hi: AssignedBits<14>,
-    _d_hi_lo: AssignedBits<14>,
-    _d_hi_hi: AssignedBits<14>,
-    spread_d_lo_lo: AssignedBits<28>,
-    spread_d_lo_hi: AssignedBits<28>,
-    spread_d_hi_lo: AssignedBits<28>,
-    spread_d_hi_hi: AssignedBits<28>,
+    a: AssignedBits<F, 1>,
+    b: AssignedBits<F, 6>,
+    c: AssignedBits<F, 1>,
+    _d_lo_lo: AssignedBits<F, 14>,
+    _d_lo_hi: AssignedBits<F, 14>,
+    _d_hi_lo: AssignedBits<F, 14>,
+    _d_hi_hi: AssignedBits<F, 14>,
+    spread_d_lo_lo: AssignedBits<F, 28>,
+    spread_d_lo_hi: AssignedBits<F, 28>,
+    spread_d_hi_lo: AssignedBits<F, 28>,
+    spread_d_hi_hi: AssignedBits<F, 28>,
 }
 
-impl Subregion1Word {
+impl<F: FieldExt> Subregion1Word<F> {
     fn spread_a(&self) -> Value<[bool; 2]> {
         self.a.value().map(|v| v.spread())
     }
@@ -102,12 +101,12 @@ impl Subregion1Word {
     }
 }
 
-impl MessageScheduleConfig {
+impl<F: FieldExt> MessageScheduleConfig<F> {
     pub fn assign_subregion1(
         &self,
-        region: &mut Region<'_, bn256::Fr>,
-        input: &[BlockWord],
-    ) -> Result<Vec<(AssignedBits<32>, AssignedBits<32>)>, Error> {
+        region: &mut Region<'_, F>,
+        input: &[Value<u64>],
+    ) -> Result<Vec<(AssignedBits<F, 32>, AssignedBits<F, 32>)>, Sha512Error> {
         assert_eq!(input.len(), SUBREGION_1_LEN);
         Ok(input
             .iter()
@@ -117,7 +116,7 @@ impl MessageScheduleConfig {
                 let subregion1_word = self
                     .decompose_subregion1_word(
                         region,
-                        word.0.map(|word| i2lebsp(word.into())),
+                        word.map(|word| i2lebsp(word.into())),
                         idx + 1,
                     )
                     .unwrap();
@@ -131,10 +130,10 @@ impl MessageScheduleConfig {
     /// Pieces of length [1, 6, 1, 56]
     fn decompose_subregion1_word(
         &self,
-        region: &mut Region<'_, bn256::Fr>,
+        region: &mut Region<'_, F>,
         word: Value<[bool; 64]>,
         index: usize,
-    ) -> Result<Subregion1Word, Error> {
+    ) -> Result<Subregion1Word<F>, Sha512Error> {
         let row = get_word_row(index);
 
         // Rename these here for ease of matching the gates to the specification.
@@ -156,29 +155,29 @@ impl MessageScheduleConfig {
 
         // Assign `a` (1-bit piece)
         let a =
-            AssignedBits::<1>::assign_bits(region, || "word_a", a_3, row + 1, pieces[0].clone())?;
+            AssignedBits::<F, 1>::assign_bits(region, || "word_a", a_3, row + 1, pieces[0].clone())?;
         // Assign `b` (6-bit piece)
         let b =
-            AssignedBits::<6>::assign_bits(region, || "word_b", a_4, row + 1, pieces[1].clone())?;
+            AssignedBits::<F, 6>::assign_bits(region, || "word_b", a_4, row + 1, pieces[1].clone())?;
 
         // Assign `c` (1-bit piece)
         let c =
-            AssignedBits::<1>::assign_bits(region, || "word_c", a_3, row + 2, pieces[2].clone())?;
+            AssignedBits::<F, 1>::assign_bits(region, || "word_c", a_3, row + 2, pieces[2].clone())?;
 
         // Assign `d_lo_lo` (14-bit piece) lookup
-        let spread_d_lo_lo = pieces[3].clone().map(SpreadWord::try_new);
+        let spread_d_lo_lo = SpreadWord::try_from_value(pieces[3].clone())?;
         let spread_d_lo_lo = SpreadVar::with_lookup(region, &self.lookup, row, spread_d_lo_lo)?;
 
         // Assign `d_lo_hi` (14-bit piece) lookup
-        let spread_d_lo_hi = pieces[4].clone().map(SpreadWord::try_new);
+        let spread_d_lo_hi = SpreadWord::try_from_value(pieces[4].clone())?;
         let spread_d_lo_hi = SpreadVar::with_lookup(region, &self.lookup, row + 1, spread_d_lo_hi)?;
 
         // Assign `d_hi_lo` (14-bit piece) lookup
-        let spread_d_hi_lo = pieces[5].clone().map(SpreadWord::try_new);
+        let spread_d_hi_lo = SpreadWord::try_from_value(pieces[5].clone())?;
         let spread_d_hi_lo = SpreadVar::with_lookup(region, &self.lookup, row + 2, spread_d_hi_lo)?;
 
         // Assign `d_hi_hi` (14-bit piece) lookup
-        let spread_d_hi_hi = pieces[6].clone().map(SpreadWord::try_new);
+        let spread_d_hi_hi = SpreadWord::try_from_value(pieces[6].clone())?;
         let spread_d_hi_hi = SpreadVar::with_lookup(region, &self.lookup, row + 3 , spread_d_hi_hi)?;
 
         Ok(Subregion1Word {
@@ -201,9 +200,9 @@ impl MessageScheduleConfig {
     // (1, 6, 1, 56)-bit chunks
     fn lower_sigma_0(
         &self,
-        region: &mut Region<'_, bn256::Fr>,
-        word: Subregion1Word,
-    ) -> Result<(AssignedBits<32>, AssignedBits<32>), Error> {
+        region: &mut Region<'_, F>,
+        word: Subregion1Word<F>,
+    ) -> Result<(AssignedBits<F, 32>, AssignedBits<F, 32>), Sha512Error> {
         let a_3 = self.extras[0];
         let a_4 = self.extras[1];
         let a_5 = self.message_schedule;
@@ -219,9 +218,9 @@ impl MessageScheduleConfig {
         let b_lo: Value<[bool; 3]> = word.b.value().map(|b| b.0[..3].try_into().unwrap());
         let spread_b_lo = b_lo.map(spread_bits);
         {
-            AssignedBits::<3>::assign_bits(region, || "b_lo", a_3, row - 1, b_lo)?;
+            AssignedBits::<F, 3>::assign_bits(region, || "b_lo", a_3, row - 1, b_lo)?;
 
-            AssignedBits::<6>::assign_bits(region, || "spread_b_lo", a_4, row - 1, spread_b_lo)?;
+            AssignedBits::<F, 6>::assign_bits(region, || "spread_b_lo", a_4, row - 1, spread_b_lo)?;
         };
 
         // Split `b` (6-bit chunk) into `b_hi` and `b_lo`
@@ -229,9 +228,9 @@ impl MessageScheduleConfig {
         let b_hi: Value<[bool; 3]> = word.b.value().map(|b| b.0[3..].try_into().unwrap());
         let spread_b_hi = b_hi.map(spread_bits);
         {
-            AssignedBits::<3>::assign_bits(region, || "b_hi", a_5, row - 1, b_hi)?;
+            AssignedBits::<F, 3>::assign_bits(region, || "b_hi", a_5, row - 1, b_hi)?;
 
-            AssignedBits::<6>::assign_bits(region, || "spread_b_hi", a_6, row - 1, spread_b_hi)?;
+            AssignedBits::<F, 6>::assign_bits(region, || "spread_b_hi", a_6, row - 1, spread_b_hi)?;
         };
 
         // Assign `b` and copy constraint
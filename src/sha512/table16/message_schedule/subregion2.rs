@@ -1,37 +1,35 @@
-use super::super::{util::*, AssignedBits, Bits, SpreadVar, SpreadWord, Table16Assignment};
+use super::super::{
+    util::*, AssignedBits, Bits, Sha512Error, SpreadVar, SpreadWord, Table16Assignment,
+};
 use super::{schedule_util::*, MessageScheduleConfig, MessageWord};
 
-use halo2_proofs::{
-    circuit::{Region, Value},
-    halo2curves::bn256,
-    plonk::Error,
-};
+use halo2_proofs::circuit::{Region, Value};
 use std::convert::TryInto;
 use halo2_proofs::arithmetic::FieldExt;
 
 /// A word in subregion 2
 /// (1, 5, 1, 1, 11, 42, 3)-bit chunks
 #[derive(Clone, Debug)]
-pub struct Subregion2Word {
+pub struct Subregion2Word<F: FieldExt> {
     index: usize,
-    a: AssignedBits<1>,
-    b: AssignedBits<5>,
-    c: AssignedBits<1>,
-    d: AssignedBits<1>,
-    _e: AssignedBits<11>,
-    _f_lo_lo: AssignedBits<11>,
-    _f_lo_hi: AssignedBits<10>,
-    _f_hi_lo: AssignedBits<11>,
-    _f_hi_hi: AssignedBits<10>,
-    g: AssignedBits<3>,
-    spread_e: AssignedBits<22>,
-    spread_f_lo_lo: AssignedBits<22>,
-    spread_f_lo_hi: AssignedBits<20>,
-    spread_f_hi_lo: AssignedBits<22>,
-    spread_f_hi_hi: AssignedBits<20>,
+    a: AssignedBits<F, 1>,
+    b: AssignedBits<F, 5>,
+    c: AssignedBits<F, 1>,
+    d: AssignedBits<F, 1>,
+    _e: AssignedBits<F, 11>,
+    _f_lo_lo: AssignedBits<F, 11>,
+    _f_lo_hi: AssignedBits<F, 10>,
+    _f_hi_lo: AssignedBits<F, 11>,
+    _f_hi_hi: AssignedBits<F, 10>,
+    g: AssignedBits<F, 3>,
+    spread_e: AssignedBits<F, 22>,
+    spread_f_lo_lo: AssignedBits<F, 22>,
+    spread_f_lo_hi: AssignedBits<F, 20>,
+    spread_f_hi_lo: AssignedBits<F, 22>,
+    spread_f_hi_hi: AssignedBits<F, 20>,
 }
 
-impl Subregion2Word {
+impl<F: FieldExt> Subregion2Word<F> {
     fn spread_a(&self) -> Value<[bool;2]> {
         self.a.value().map(|v| v.spread())
     }
@@ -195,15 +193,15 @@ impl Subregion2Word {
     }
 }
 
-impl MessageScheduleConfig {
+impl<F: FieldExt> MessageScheduleConfig<F> {
     // W_[14..65]
     pub fn assign_subregion2(
         &self,
-        region: &mut Region<'_, bn256::Fr>,
-        lower_sigma_0_output: Vec<(AssignedBits<32>, AssignedBits<32>)>,
-        w: &mut Vec<MessageWord>,
-        w_halves: &mut Vec<(AssignedBits<32>, AssignedBits<32>)>,
-    ) -> Result<Vec<(AssignedBits<32>, AssignedBits<32>)>, Error> {
+        region: &mut Region<'_, F>,
+        lower_sigma_0_output: Vec<(AssignedBits<F, 32>, AssignedBits<F, 32>)>,
+        w: &mut Vec<MessageWord<F>>,
+        w_halves: &mut Vec<(AssignedBits<F, 32>, AssignedBits<F, 32>)>,
+    ) -> Result<Vec<(AssignedBits<F, 32>, AssignedBits<F, 32>)>, Sha512Error> {
         let a_5 = self.message_schedule;
         let a_6 = self.extras[2];
         let a_7 = self.extras[3];
@@ -211,9 +209,9 @@ impl MessageScheduleConfig {
         let a_9 = self.extras[5];
 
         let mut lower_sigma_0_v2_results =
-            Vec::<(AssignedBits<32>, AssignedBits<32>)>::with_capacity(SUBREGION_2_LEN);
+            Vec::<(AssignedBits<F, 32>, AssignedBits<F, 32>)>::with_capacity(SUBREGION_2_LEN);
         let mut lower_sigma_1_v2_results =
-            Vec::<(AssignedBits<32>, AssignedBits<32>)>::with_capacity(SUBREGION_2_LEN);
+            Vec::<(AssignedBits<F, 32>, AssignedBits<F, 32>)>::with_capacity(SUBREGION_2_LEN);
 
         // Closure to compose new word
         // W_i = sigma_1(W_{i - 2}) + W_{i - 7} + sigma_0(W_{i - 15}) + W_{i - 16}
@@ -224,8 +222,8 @@ impl MessageScheduleConfig {
         // sigma_1_v2(W_[14..65]) will be used to get the W_[16..67]
         // The lowest-index words involved will be W_[0..13]
         let mut new_word = |idx: usize,
-                            sigma_0_output: &(AssignedBits<32>, AssignedBits<32>)|
-         -> Result<Vec<(AssignedBits<32>, AssignedBits<32>)>, Error> {
+                            sigma_0_output: &(AssignedBits<F, 32>, AssignedBits<F, 32>)|
+         -> Result<Vec<(AssignedBits<F, 32>, AssignedBits<F, 32>)>, Sha512Error> {
             // Decompose word into (1, 5, 1, 1, 11, 42, 3)-bit chunks
             let word = self.decompose_word(region, w[idx].value(), idx)?;
 
@@ -299,13 +297,13 @@ impl MessageScheduleConfig {
                 || format!("W_{}", new_word_idx),
                 a_5,
                 get_word_row(new_word_idx - 16) + 1,
-                || word.map(|word| bn256::Fr::from_u128(word as u128)),
+                || word.map(|word| F::from_u128(word as u128)),
             )?;
             region.assign_advice(
                 || format!("carry_{}", new_word_idx),
                 a_9,
                 get_word_row(new_word_idx - 16) + 1,
-                || carry.map(bn256::Fr::from),
+                || carry.map(F::from),
             )?;
             let (word, halves) = self.assign_word_and_halves(region, word, new_word_idx)?;
             w.push(MessageWord(word));
@@ -314,7 +312,7 @@ impl MessageScheduleConfig {
             Ok(lower_sigma_0_v2_results.clone())
         };
 
-        let mut tmp_lower_sigma_0_v2_results: Vec<(AssignedBits<32>, AssignedBits<32>)> =
+        let mut tmp_lower_sigma_0_v2_results: Vec<(AssignedBits<F, 32>, AssignedBits<F, 32>)> =
             Vec::with_capacity(SUBREGION_2_LEN);
 
         // Use up all the output from Subregion 1 lower_sigma_0
@@ -334,10 +332,10 @@ impl MessageScheduleConfig {
     /// Pieces of length [1, 5, 1, 1, 11, 42, 3]
     fn decompose_word(
         &self,
-        region: &mut Region<'_, bn256::Fr>,
+        region: &mut Region<'_, F>,
         word: Value<&Bits<64>>,
         index: usize,
-    ) -> Result<Subregion2Word, Error> {
+    ) -> Result<Subregion2Word<F>, Sha512Error> {
         let row = get_word_row(index);
 
         let pieces = word.map(|word| {
@@ -361,40 +359,40 @@ impl MessageScheduleConfig {
         let a_4 = self.extras[1];
 
         // Assign `a` (1-bit piece)
-        let a = AssignedBits::<1>::assign_bits(region, || "a", a_3, row - 1, pieces[0].clone())?;
+        let a = AssignedBits::<F, 1>::assign_bits(region, || "a", a_3, row - 1, pieces[0].clone())?;
 
         // Assign `b` (5-bit piece) lookup
-        let spread_b: Value<SpreadWord<5, 10>> = pieces[1].clone().map(SpreadWord::try_new);
+        let spread_b: Value<SpreadWord<5, 10>> = SpreadWord::try_from_value(pieces[1].clone())?;
         let spread_b = SpreadVar::with_lookup(region, &self.lookup, row + 4, spread_b)?;
 
         // Assign `c` (1-bit piece)
-        let c = AssignedBits::<1>::assign_bits(region, || "c", a_4, row - 1, pieces[2].clone())?;
+        let c = AssignedBits::<F, 1>::assign_bits(region, || "c", a_4, row - 1, pieces[2].clone())?;
 
         // Assign `d` (1-bit piece)
-        let d = AssignedBits::<1>::assign_bits(region, || "d", a_4, row + 1, pieces[3].clone())?;
+        let d = AssignedBits::<F, 1>::assign_bits(region, || "d", a_4, row + 1, pieces[3].clone())?;
 
         // Assign `e` (11-bit piece)
-        let spread_e = pieces[4].clone().map(SpreadWord::try_new);
+        let spread_e = SpreadWord::try_from_value(pieces[4].clone())?;
         let spread_e = SpreadVar::with_lookup(region, &self.lookup, row - 1, spread_e)?;
 
         // Assign `f_lo_lo` (11-bit piece)
-        let spread_f_lo_lo = pieces[5].clone().map(SpreadWord::try_new);
+        let spread_f_lo_lo = SpreadWord::try_from_value(pieces[5].clone())?;
         let spread_f_lo_lo = SpreadVar::with_lookup(region, &self.lookup, row, spread_f_lo_lo)?;
 
         // Assign `f_lo_hi` (10-bit piece)
-        let spread_f_lo_hi = pieces[6].clone().map(SpreadWord::try_new);
+        let spread_f_lo_hi = SpreadWord::try_from_value(pieces[6].clone())?;
         let spread_f_lo_hi = SpreadVar::with_lookup(region, &self.lookup, row + 1, spread_f_lo_hi)?;
 
         // Assign `f_hi_lo` (11-bit piece)
-        let spread_f_hi_lo = pieces[7].clone().map(SpreadWord::try_new);
+        let spread_f_hi_lo = SpreadWord::try_from_value(pieces[7].clone())?;
         let spread_f_hi_lo = SpreadVar::with_lookup(region, &self.lookup, row + 2, spread_f_hi_lo)?;
 
         // Assign `f_hi_hi` (10-bit piece)
-        let spread_f_hi_hi = pieces[8].clone().map(SpreadWord::try_new);
+        let spread_f_hi_hi = SpreadWord::try_from_value(pieces[8].clone())?;
         let spread_f_hi_hi = SpreadVar::with_lookup(region, &self.lookup, row + 3, spread_f_hi_hi)?;
 
         // Assign `g` (3-bit piece) lookup
-        let g = AssignedBits::<3>::assign_bits(region, || "g", a_3, row + 1, pieces[9].clone())?;
+        let g = AssignedBits::<F, 3>::assign_bits(region, || "g", a_3, row + 1, pieces[9].clone())?;
 
         Ok(Subregion2Word {
             index,
@@ -421,10 +419,10 @@ impl MessageScheduleConfig {
     #[allow(clippy::type_complexity)]
     fn assign_lower_sigma_v2_pieces(
         &self,
-        region: &mut Region<'_, bn256::Fr>,
+        region: &mut Region<'_, F>,
         row: usize,
-        word: &Subregion2Word,
-    ) -> Result<(), Error> {
+        word: &Subregion2Word<F>,
+    ) -> Result<(), Sha512Error> {
         let a_3 = self.extras[0];
         let a_4 = self.extras[1];
         let a_5 = self.message_schedule;
@@ -442,9 +440,9 @@ impl MessageScheduleConfig {
         let b_lo: Value<[bool; 3]> = word.b.value().map(|b| b.0[..3].try_into().unwrap());
         let spread_b_lo = b_lo.map(spread_bits);
         {
-            AssignedBits::<3>::assign_bits(region, || "b_lo", a_3, row - 1, b_lo)?;
+            AssignedBits::<F, 3>::assign_bits(region, || "b_lo", a_3, row - 1, b_lo)?;
 
-            AssignedBits::<6>::assign_bits(region, || "spread_b_lo", a_4, row - 1, spread_b_lo)?;
+            AssignedBits::<F, 6>::assign_bits(region, || "spread_b_lo", a_4, row - 1, spread_b_lo)?;
         };
 
         // Split `b` (2-bit chunk) into `b_hi` and `b_lo`
@@ -452,9 +450,9 @@ impl MessageScheduleConfig {
         let b_hi: Value<[bool; 2]> = word.b.value().map(|b| b.0[3..5].try_into().unwrap());
         let spread_b_hi = b_hi.map(spread_bits);
         {
-            AssignedBits::<2>::assign_bits(region, || "b_hi", a_5, row - 1, b_hi)?;
+            AssignedBits::<F, 2>::assign_bits(region, || "b_hi", a_5, row - 1, b_hi)?;
 
-            AssignedBits::<4>::assign_bits(region, || "spread_b_hi", a_6, row - 1, spread_b_hi)?;
+            AssignedBits::<F, 4>::assign_bits(region, || "spread_b_hi", a_6, row - 1, spread_b_hi)?;
         };
 
         // Assign `b` and copy constraint
@@ -485,16 +483,16 @@ impl MessageScheduleConfig {
         word.g.copy_advice(|| "g", region, a_5, row + 1)?;
 
         // Witness `spread_g`
-        AssignedBits::<6>::assign_bits(region, || "spread_g", a_5, row, word.spread_g())?;
+        AssignedBits::<F, 6>::assign_bits(region, || "spread_g", a_5, row, word.spread_g())?;
 
         Ok(())
     }
 
     fn lower_sigma_0_v2(
         &self,
-        region: &mut Region<'_, bn256::Fr>,
-        word: Subregion2Word,
-    ) -> Result<(AssignedBits<32>, AssignedBits<32>), Error> {
+        region: &mut Region<'_, F>,
+        word: Subregion2Word<F>,
+    ) -> Result<(AssignedBits<F, 32>, AssignedBits<F, 32>), Sha512Error> {
         let a_3 = self.extras[0];
         let row = get_word_row(word.index) + 6;
 
@@ -525,9 +523,9 @@ impl MessageScheduleConfig {
 
     fn lower_sigma_1_v2(
         &self,
-        region: &mut Region<'_, bn256::Fr>,
-        word: Subregion2Word,
-    ) -> Result<(AssignedBits<32>, AssignedBits<32>), Error> {
+        region: &mut Region<'_, F>,
+        word: Subregion2Word<F>,
+    ) -> Result<(AssignedBits<F, 32>, AssignedBits<F, 32>), Sha512Error> {
         let a_3 = self.extras[0];
         let row = get_word_row(word.index) + SIGMA_0_V2_ROWS + 6;
 
@@ -1,35 +1,35 @@
-use super::super::{util::*, AssignedBits, Bits, SpreadVar, SpreadWord, Table16Assignment};
+use super::super::{
+    util::*, AssignedBits, Bits, Sha512Error, SpreadVar, SpreadWord, Table16Assignment,
+};
 use super::{schedule_util::*, MessageScheduleConfig, MessageWord};
 
 use halo2_proofs::{
     arithmetic::FieldExt,
     circuit::{Region, Value},
-    halo2curves::bn256,
-    plonk::Error,
 };
 use std::convert::TryInto;
 
 // A word in subregion 3
 // (6, 13, 42, 3)-bit chunks
-pub struct Subregion3Word {
+pub struct Subregion3Word<F: FieldExt> {
     index: usize,
     #[allow(dead_code)]
-    a: AssignedBits<6>,
-    _b: AssignedBits<13>,
-    _c_lo_lo: AssignedBits<11>,
-    _c_lo_hi: AssignedBits<10>,
-    _c_hi_lo: AssignedBits<11>,
-    _c_hi_hi: AssignedBits<10>,
+    a: AssignedBits<F, 6>,
+    _b: AssignedBits<F, 13>,
+    _c_lo_lo: AssignedBits<F, 11>,
+    _c_lo_hi: AssignedBits<F, 10>,
+    _c_hi_lo: AssignedBits<F, 11>,
+    _c_hi_hi: AssignedBits<F, 10>,
     #[allow(dead_code)]
-    d: AssignedBits<3>,
-    spread_b: AssignedBits<26>,
-    spread_c_lo_lo: AssignedBits<22>,
-    spread_c_lo_hi: AssignedBits<20>,
-    spread_c_hi_lo: AssignedBits<22>,
-    spread_c_hi_hi: AssignedBits<20>,
+    d: AssignedBits<F, 3>,
+    spread_b: AssignedBits<F, 26>,
+    spread_c_lo_lo: AssignedBits<F, 22>,
+    spread_c_lo_hi: AssignedBits<F, 20>,
+    spread_c_hi_lo: AssignedBits<F, 22>,
+    spread_c_hi_hi: AssignedBits<F, 20>,
 }
 
-impl Subregion3Word {
+impl<F: FieldExt> Subregion3Word<F> {
     fn spread_a(&self) -> Value<[bool; 12]> {
         self.a.value().map(|v| v.spread())
     }
@@ -108,15 +108,15 @@ impl Subregion3Word {
     }
 }
 
-impl MessageScheduleConfig {
+impl<F: FieldExt> MessageScheduleConfig<F> {
     // W_[65..78]
     pub fn assign_subregion3(
         &self,
-        region: &mut Region<'_, bn256::Fr>,
-        lower_sigma_0_v2_output: Vec<(AssignedBits<32>, AssignedBits<32>)>,
-        w: &mut Vec<MessageWord>,
-        w_halves: &mut Vec<(AssignedBits<32>, AssignedBits<32>)>,
-    ) -> Result<(), Error> {
+        region: &mut Region<'_, F>,
+        lower_sigma_0_v2_output: Vec<(AssignedBits<F, 32>, AssignedBits<F, 32>)>,
+        w: &mut Vec<MessageWord<F>>,
+        w_halves: &mut Vec<(AssignedBits<F, 32>, AssignedBits<F, 32>)>,
+    ) -> Result<(), Sha512Error> {
         let a_5 = self.message_schedule;
         let a_6 = self.extras[2];
         let a_7 = self.extras[3];
@@ -130,7 +130,7 @@ impl MessageScheduleConfig {
         // sigma_0_v2(W_[52..65]) will be used to get the new W_[67..80]
         // sigma_1(W_[65..78]) will also be used to get the W_[67..80]
         // The lowest-index words involved will be W_[51..74]
-        let mut new_word = |idx: usize| -> Result<(), Error> {
+        let mut new_word = |idx: usize| -> Result<(), Sha512Error> {
             // Decompose word into (6, 13, 42, 3)-bit chunks
             let subregion3_word = self.decompose_subregion3_word(region, w[idx].value(), idx)?;
 
@@ -203,13 +203,13 @@ impl MessageScheduleConfig {
                 || format!("W_{}", new_word_idx),
                 a_5,
                 get_word_row(new_word_idx - 16) + 1,
-                || word.map(|word| bn256::Fr::from_u128(word as u128)),
+                || word.map(|word| F::from_u128(word as u128)),
             )?;
             region.assign_advice(
                 || format!("carry_{}", new_word_idx),
                 a_9,
                 get_word_row(new_word_idx - 16) + 1,
-                || carry.map(|carry| bn256::Fr::from_u128(carry as u128)),
+                || carry.map(|carry| F::from_u128(carry as u128)),
             )?;
             let (word, halves) = self.assign_word_and_halves(region, word, new_word_idx)?;
             w.push(MessageWord(word));
@@ -228,10 +228,10 @@ impl MessageScheduleConfig {
     /// Pieces of length [6, 13, 42, 3]
     fn decompose_subregion3_word(
         &self,
-        region: &mut Region<'_, bn256::Fr>,
+        region: &mut Region<'_, F>,
         word: Value<&Bits<64>>,
         index: usize,
-    ) -> Result<Subregion3Word, Error> {
+    ) -> Result<Subregion3Word<F>, Sha512Error> {
         let row = get_word_row(index);
 
         // Rename these here for ease of matching the gates to the specification.
@@ -252,30 +252,30 @@ impl MessageScheduleConfig {
         let pieces = pieces.transpose_vec(7);
 
         // Assign `a` (6-bit piece)
-        let a = AssignedBits::<6>::assign_bits(region, || "a", a_4, row + 1, pieces[0].clone())?;
+        let a = AssignedBits::<F, 6>::assign_bits(region, || "a", a_4, row + 1, pieces[0].clone())?;
 
         // Assign `b` (13-bit piece)
-        let spread_b = pieces[1].clone().map(SpreadWord::try_new);
+        let spread_b = SpreadWord::try_from_value(pieces[1].clone())?;
         let spread_b = SpreadVar::with_lookup(region, &self.lookup, row, spread_b)?;
 
         // Assign `c_lo_lo` (11-bit piece)
-        let spread_c_lo_lo = pieces[2].clone().map(SpreadWord::try_new);
+        let spread_c_lo_lo = SpreadWord::try_from_value(pieces[2].clone())?;
         let spread_c_lo_lo = SpreadVar::with_lookup(region, &self.lookup, row + 1, spread_c_lo_lo)?;
 
         // Assign `c_lo_hi` (10-bit piece)
-        let spread_c_lo_hi = pieces[3].clone().map(SpreadWord::try_new);
+        let spread_c_lo_hi = SpreadWord::try_from_value(pieces[3].clone())?;
         let spread_c_lo_hi = SpreadVar::with_lookup(region, &self.lookup, row + 2, spread_c_lo_hi)?;
 
         // Assign `c_hi_lo` (11-bit piece)
-        let spread_c_hi_lo = pieces[4].clone().map(SpreadWord::try_new);
+        let spread_c_hi_lo = SpreadWord::try_from_value(pieces[4].clone())?;
         let spread_c_hi_lo = SpreadVar::with_lookup(region, &self.lookup, row + 3, spread_c_hi_lo)?;
 
         // Assign `c_hi_hi` (10-bit piece)
-        let spread_c_hi_hi = pieces[5].clone().map(SpreadWord::try_new);
+        let spread_c_hi_hi = SpreadWord::try_from_value(pieces[5].clone())?;
         let spread_c_hi_hi = SpreadVar::with_lookup(region, &self.lookup, row + 4, spread_c_hi_hi)?;
 
         // Assign `d` (3-bit piece) lookup
-        let d = AssignedBits::<3>::assign_bits(region, || "d", a_3, row + 1, pieces[6].clone())?;
+        let d = AssignedBits::<F, 3>::assign_bits(region, || "d", a_3, row + 1, pieces[6].clone())?;
 
 
 
@@ -298,9 +298,9 @@ impl MessageScheduleConfig {
 
     fn lower_sigma_1(
         &self,
-        region: &mut Region<'_, bn256::Fr>,
-        word: Subregion3Word,
-    ) -> Result<(AssignedBits<32>, AssignedBits<32>), Error> {
+        region: &mut Region<'_, F>,
+        word: Subregion3Word<F>,
+    ) -> Result<(AssignedBits<F, 32>, AssignedBits<F, 32>), Sha512Error> {
         let a_3 = self.extras[0];
         let a_4 = self.extras[1];
         let a_5 = self.message_schedule;
@@ -349,7 +349,7 @@ impl MessageScheduleConfig {
          // Witness `spread_d`
          {
             let spread_d = word.d.value().map(spread_bits);
-            AssignedBits::<6>::assign_bits(region, || "spread_d", a_4, row + 1, spread_d)?;
+            AssignedBits::<F, 6>::assign_bits(region, || "spread_d", a_4, row + 1, spread_d)?;
         }
 
         // (6, 13, 42, 3)
@@ -1,13 +1,11 @@
-use super::{util::*, AssignedBits};
+use super::{super::Sha512Error, util::*, AssignedBits};
 
 use halo2_proofs::{
     arithmetic::FieldExt,
     circuit::{Chip, Layouter, Region, Value},
-    halo2curves::bn256,
     plonk::{Advice, Column, ConstraintSystem, Error, TableColumn},
     poly::Rotation,
 };
-use std::convert::TryInto;
 use std::marker::PhantomData;
 
 const BITS_10: usize = 1 << 10;
@@ -40,8 +38,12 @@ pub fn get_tag(input: u16) -> u8 {
 }
 
 impl<const DENSE: usize, const SPREAD: usize> SpreadWord<DENSE, SPREAD> {
+    /// Rejects at compile time the lengths the spread table does not hold.
+    const LENGTHS: () = assert!(DENSE <= 16 && SPREAD == 2 * DENSE);
+
     pub(super) fn new(dense: [bool; DENSE]) -> Self {
-        assert!(DENSE <= 16);
+        #[allow(clippy::let_unit_value)]
+        let () = Self::LENGTHS;
         SpreadWord {
             tag: get_tag(lebs2ip(&dense) as u16),
             dense,
@@ -49,35 +51,35 @@ impl<const DENSE: usize, const SPREAD: usize> SpreadWord<DENSE, SPREAD> {
         }
     }
 
-    pub(super) fn try_new<T: TryInto<[bool; DENSE]> + std::fmt::Debug>(dense: T) -> Self
-    where
-        <T as TryInto<[bool; DENSE]>>::Error: std::fmt::Debug,
-    {
-        assert!(DENSE <= 16);
-        let dense: [bool; DENSE] = dense.try_into().unwrap();
-        SpreadWord {
-            tag: get_tag(lebs2ip(&dense) as u16),
-            dense,
-            spread: spread_bits(dense),
-        }
+    /// As [`Self::new`], for a piece of a word held as a sequence of bits, returning an
+    /// error if it is not `DENSE` bits long.
+    pub(super) fn try_new<T: AsRef<[bool]>>(dense: T) -> Result<Self, Sha512Error> {
+        try_into_bits(dense.as_ref()).map(Self::new)
+    }
+
+    /// Applies [`Self::try_new`] to a witness, moving any error out of the `Value`.
+    pub(super) fn try_from_value<T: AsRef<[bool]>>(
+        dense: Value<T>,
+    ) -> Result<Value<Self>, Sha512Error> {
+        transpose_result(dense.map(Self::try_new))
     }
 }
 
 /// A variable stored in advice columns corresponding to a row of [`SpreadTableConfig`].
 #[derive(Clone, Debug)]
-pub(super) struct SpreadVar<const DENSE: usize, const SPREAD: usize> {
+pub(super) struct SpreadVar<F: FieldExt, const DENSE: usize, const SPREAD: usize> {
     pub _tag: Value<u8>,
-    pub dense: AssignedBits<DENSE>,
-    pub spread: AssignedBits<SPREAD>,
+    pub dense: AssignedBits<F, DENSE>,
+    pub spread: AssignedBits<F, SPREAD>,
 }
 
-impl<const DENSE: usize, const SPREAD: usize> SpreadVar<DENSE, SPREAD> {
+impl<F: FieldExt, const DENSE: usize, const SPREAD: usize> SpreadVar<F, DENSE, SPREAD> {
     pub(super) fn with_lookup(
-        region: &mut Region<'_, bn256::Fr>,
+        region: &mut Region<'_, F>,
         cols: &SpreadInputs,
         row: usize,
         word: Value<SpreadWord<DENSE, SPREAD>>,
-    ) -> Result<Self, Error> {
+    ) -> Result<Self, Sha512Error> {
         let tag = word.map(|word| word.tag);
         let dense_val = word.map(|word| word.dense);
         let spread_val = word.map(|word| word.spread);
@@ -86,14 +88,14 @@ impl<const DENSE: usize, const SPREAD: usize> SpreadVar<DENSE, SPREAD> {
             || "tag",
             cols.tag,
             row,
-            || tag.map(|tag| bn256::Fr::from(tag as u64)),
+            || tag.map(|tag| F::from(tag as u64)),
         )?;
 
         let dense =
-            AssignedBits::<DENSE>::assign_bits(region, || "dense", cols.dense, row, dense_val)?;
+            AssignedBits::<F, DENSE>::assign_bits(region, || "dense", cols.dense, row, dense_val)?;
 
         let spread =
-            AssignedBits::<SPREAD>::assign_bits(region, || "spread", cols.spread, row, spread_val)?;
+            AssignedBits::<F, SPREAD>::assign_bits(region, || "spread", cols.spread, row, spread_val)?;
 
         Ok(SpreadVar {
             _tag: tag,
@@ -103,18 +105,18 @@ impl<const DENSE: usize, const SPREAD: usize> SpreadVar<DENSE, SPREAD> {
     }
 
     pub(super) fn without_lookup(
-        region: &mut Region<'_, bn256::Fr>,
+        region: &mut Region<'_, F>,
         dense_col: Column<Advice>,
         dense_row: usize,
         spread_col: Column<Advice>,
         spread_row: usize,
         word: Value<SpreadWord<DENSE, SPREAD>>,
-    ) -> Result<Self, Error> {
+    ) -> Result<Self, Sha512Error> {
         let tag = word.map(|word| word.tag);
         let dense_val = word.map(|word| word.dense);
         let spread_val = word.map(|word| word.spread);
 
-        let dense = AssignedBits::<DENSE>::assign_bits(
+        let dense = AssignedBits::<F, DENSE>::assign_bits(
             region,
             || "dense",
             dense_col,
@@ -122,7 +124,7 @@ impl<const DENSE: usize, const SPREAD: usize> SpreadVar<DENSE, SPREAD> {
             dense_val,
         )?;
 
-        let spread = AssignedBits::<SPREAD>::assign_bits(
+        let spread = AssignedBits::<F, SPREAD>::assign_bits(
             region,
             || "spread",
             spread_col,
@@ -295,12 +297,11 @@ mod tests {
         arithmetic::FieldExt,
         circuit::{Layouter, SimpleFloorPlanner, Value},
         dev::MockProver,
+        halo2curves::{bn256, pasta::pallas},
         plonk::{Advice, Circuit, Column, ConstraintSystem, Error},
     };
-    use halo2_proofs::halo2curves::bn256;
 
-    #[test]
-    fn lookup_table() {
+    fn lookup_table<F: FieldExt>() {
         /// This represents an advice column at a certain row in the ConstraintSystem
         #[derive(Copy, Clone, Debug)]
         pub struct Variable(Column<Advice>, usize);
@@ -440,10 +441,20 @@ mod tests {
 
         let circuit: MyCircuit = MyCircuit {};
 
-        let prover = match MockProver::<bn256::Fr>::run(17, &circuit, vec![]) {
+        let prover = match MockProver::<F>::run(17, &circuit, vec![]) {
             Ok(prover) => prover,
             Err(e) => panic!("{:?}", e),
         };
         assert_eq!(prover.verify(), Ok(()));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn lookup_table_bn256() {
+        lookup_table::<bn256::Fr>();
+    }
+
+    #[test]
+    fn lookup_table_pallas() {
+        lookup_table::<pallas::Base>();
+    }
+}
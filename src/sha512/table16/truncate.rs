@@ -0,0 +1,92 @@
+use super::{assign_region, decompose_word, AssignedWord, DigestWord, Sha512Error, WORD_BITS};
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::Layouter,
+    plonk::{Advice, Column, ConstraintSystem, Constraints, Expression, Selector},
+    poly::Rotation,
+};
+use std::marker::PhantomData;
+
+/// Truncates a digest word to its high bits, for SHA-512/t outputs that end partway
+/// through a word.
+///
+/// The word is decomposed into bits, most significant first, accumulating its value
+/// on the row after each bit:
+///
+/// | a_3  | a_4                 |
+/// |------|---------------------|
+/// | b_63 | 0                   |
+/// | b_62 | b_63                |
+/// | ...  | ...                 |
+/// |      | word                |
+///
+/// After `bits` rows the accumulator holds the truncated word.
+#[derive(Clone, Debug)]
+pub(super) struct TruncateConfig<F: FieldExt> {
+    extras: [Column<Advice>; 6],
+
+    /// Accumulates a bit of the word.
+    s_truncate: Selector,
+
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> TruncateConfig<F> {
+    pub(super) fn configure(
+        meta: &mut ConstraintSystem<F>,
+        extras: [Column<Advice>; 6],
+    ) -> Self {
+        let s_truncate = meta.selector();
+
+        // Rename these here for ease of matching the gates to the layout.
+        let a_3 = extras[0];
+        let a_4 = extras[1];
+
+        meta.create_gate("s_truncate", |meta| {
+            let s_truncate = meta.query_selector(s_truncate);
+            let bit = meta.query_advice(a_3, Rotation::cur());
+            let acc = meta.query_advice(a_4, Rotation::cur());
+            let acc_next = meta.query_advice(a_4, Rotation::next());
+
+            let one = Expression::Constant(F::from(1));
+            Constraints::with_selector(
+                s_truncate,
+                [
+                    ("bit_bool", bit.clone() * (one - bit.clone())),
+                    ("acc", acc * F::from(2) + bit - acc_next),
+                ],
+            )
+        });
+
+        TruncateConfig {
+            extras,
+            s_truncate,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Returns a cell holding the high `bits` bits of `word`.
+    pub(super) fn truncate(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        word: &DigestWord<F>,
+        bits: usize,
+    ) -> Result<AssignedWord<F>, Sha512Error> {
+        assert!(bits > 0 && bits < WORD_BITS);
+        let a_3 = self.extras[0];
+        let a_4 = self.extras[1];
+
+        assign_region(
+            layouter,
+            || "truncate word",
+            |mut region| {
+                for row in 0..WORD_BITS {
+                    self.s_truncate.enable(&mut region, row)?;
+                }
+                // The accumulator after `bits` bits holds the truncated word.
+                let acc = decompose_word(&mut region, a_3, a_4, &word.word, 1)?;
+                Ok(acc[bits - 1].clone())
+            },
+        )
+    }
+}
@@ -1,5 +1,10 @@
 
-use halo2_proofs::circuit::Value;
+use super::super::Sha512Error;
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{Layouter, Region, Value},
+    plonk::Error,
+};
 
 pub const MASK_EVEN_64: u64= 0x5555555555555555;
 
@@ -115,4 +120,115 @@ pub fn sum_with_carry(words: Vec<(Value<u32>, Value<u32>)>) -> (Value<u64>, Valu
     let sum = sum.map(|sum| sum as u64);
 
     (sum, carry)
-}
\ No newline at end of file
+}
+
+/// Converts a sequence of bits into an array of `LEN` bits, returning an error rather
+/// than panicking if it is of another length.
+pub fn try_into_bits<const LEN: usize>(bits: &[bool]) -> Result<[bool; LEN], Sha512Error> {
+    bits.try_into().map_err(|_| Sha512Error::WrongLength {
+        expected: LEN,
+        actual: bits.len(),
+    })
+}
+
+/// Moves the error of a witness computation out of the `Value` holding its result, so
+/// that it can be returned.
+pub fn transpose_result<T, E>(value: Value<Result<T, E>>) -> Result<Value<T>, E> {
+    let mut error = None;
+    let value = value.and_then(|result| match result {
+        Ok(value) => Value::known(value),
+        Err(e) => {
+            error = Some(e);
+            Value::unknown()
+        }
+    });
+    match error {
+        Some(e) => Err(e),
+        None => Ok(value),
+    }
+}
+
+/// Assigns a region as [`Layouter::assign_region`] does, with an assignment that fails
+/// with a [`Sha512Error`]. A region can only fail with a [`plonk::Error`](Error), so an
+/// error other than one of halo2 is held aside while halo2 unwinds the region, and is
+/// returned in place of the [`Error::Synthesis`] it unwinds with.
+pub fn assign_region<F, A, AR, N, NR>(
+    layouter: &mut impl Layouter<F>,
+    name: N,
+    mut assignment: A,
+) -> Result<AR, Sha512Error>
+where
+    F: FieldExt,
+    A: FnMut(Region<'_, F>) -> Result<AR, Sha512Error>,
+    N: Fn() -> NR,
+    NR: Into<String>,
+{
+    let mut error = None;
+    let result = layouter.assign_region(name, |region| {
+        assignment(region).map_err(|e| match e {
+            Sha512Error::Synthesis(e) => e,
+            e => {
+                error = Some(e);
+                Error::Synthesis
+            }
+        })
+    });
+    result.map_err(|e| error.unwrap_or(Sha512Error::Synthesis(e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::Sha512Error;
+    use super::{assign_region, try_into_bits};
+    use halo2_proofs::{
+        arithmetic::FieldExt,
+        circuit::{Layouter, SimpleFloorPlanner, Value},
+        dev::MockProver,
+        halo2curves::bn256,
+        plonk::{Advice, Circuit, Column, ConstraintSystem, Error},
+    };
+
+    #[test]
+    fn region_error_detail() {
+        struct MyCircuit;
+
+        impl<F: FieldExt> Circuit<F> for MyCircuit {
+            type Config = Column<Advice>;
+            type FloorPlanner = SimpleFloorPlanner;
+
+            fn without_witnesses(&self) -> Self {
+                MyCircuit
+            }
+
+            fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+                meta.advice_column()
+            }
+
+            fn synthesize(
+                &self,
+                config: Self::Config,
+                mut layouter: impl Layouter<F>,
+            ) -> Result<(), Error> {
+                let error = assign_region(
+                    &mut layouter,
+                    || "wrong length",
+                    |mut region| {
+                        region.assign_advice(|| "cell", config, 0, || Value::known(F::from(0)))?;
+                        try_into_bits::<32>(&[false; 16])
+                    },
+                )
+                .unwrap_err();
+                assert!(matches!(
+                    error,
+                    Sha512Error::WrongLength {
+                        expected: 32,
+                        actual: 16
+                    }
+                ));
+                Ok(())
+            }
+        }
+
+        MockProver::<bn256::Fr>::run(4, &MyCircuit, vec![]).unwrap();
+    }
+}
@@ -0,0 +1,397 @@
+use super::{
+    super::{BLOCK_SIZE, DIGEST_SIZE, MIN_PADDING_BYTES, WORD_BYTES},
+    assign_region, AssignedBits, AssignedByte, AssignedWord, DigestWord, Sha512Error,
+};
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{AssignedCell, Layouter, Region, Value},
+    plonk::{
+        Advice, Assigned, Column, ConstraintSystem, Constraints, Error, Expression, Selector,
+    },
+    poly::Rotation,
+};
+use std::marker::PhantomData;
+
+/// The number of bytes in a block.
+const BLOCK_BYTES: usize = BLOCK_SIZE * WORD_BYTES;
+
+/// Pads messages whose length is a witness, and selects the digest of the block the
+/// padding ends in.
+///
+/// The padding region holds one row per byte of the blocks, after an initial row:
+///
+/// | a_3 | a_4    | a_7    | a_8 |
+/// |-----|--------|--------|-----|
+/// |     | 1      |        | 0   |
+/// | m_0 | is_msg | padded | len |
+/// | ... | ...    | ...    | ... |
+///
+/// `is_msg` is set exactly on the bytes of the message, so `len` counts them, and the
+/// padded byte is `m` on the message, 0x80 on the first byte after it, and 0 afterwards.
+#[derive(Clone, Debug)]
+pub(super) struct VarLenConfig<F: FieldExt> {
+    extras: [Column<Advice>; 6],
+
+    /// Pads a byte of the message.
+    s_var_pad: Selector,
+    /// Flags the block the padding ends in.
+    s_last_block: Selector,
+    /// Adds the message length in bits to the final word of the last block.
+    s_append_length: Selector,
+    /// Accumulates the flagged one of a set of values.
+    s_select: Selector,
+
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> VarLenConfig<F> {
+    pub(super) fn configure(
+        meta: &mut ConstraintSystem<F>,
+        extras: [Column<Advice>; 6],
+    ) -> Self {
+        let s_var_pad = meta.selector();
+        let s_last_block = meta.selector();
+        let s_append_length = meta.selector();
+        let s_select = meta.selector();
+
+        // Rename these here for ease of matching the gates to the layout.
+        let a_3 = extras[0];
+        let a_4 = extras[1];
+        let a_6 = extras[2];
+        let a_7 = extras[3];
+        let a_8 = extras[4];
+
+        let one = || Expression::Constant(F::from(1));
+
+        meta.create_gate("s_var_pad", |meta| {
+            let s_var_pad = meta.query_selector(s_var_pad);
+            let byte = meta.query_advice(a_3, Rotation::cur());
+            let is_msg = meta.query_advice(a_4, Rotation::cur());
+            let is_msg_prev = meta.query_advice(a_4, Rotation::prev());
+            let padded = meta.query_advice(a_7, Rotation::cur());
+            let len = meta.query_advice(a_8, Rotation::cur());
+            let len_prev = meta.query_advice(a_8, Rotation::prev());
+
+            // Set on the first byte after the message only.
+            let is_end = is_msg_prev - is_msg.clone();
+
+            Constraints::with_selector(
+                s_var_pad,
+                [
+                    ("is_msg_bool", is_msg.clone() * (one() - is_msg.clone())),
+                    ("is_msg_monotone", is_end.clone() * (one() - is_end.clone())),
+                    (
+                        "padded_byte",
+                        is_msg.clone() * byte + is_end * F::from(0x80) - padded,
+                    ),
+                    ("len", len_prev + is_msg - len),
+                ],
+            )
+        });
+
+        // The padding ends in a block if the message leaves room for the 0x80 byte and
+        // the length field in it, but not in the block before.
+        meta.create_gate("s_last_block", |meta| {
+            let s_last_block = meta.query_selector(s_last_block);
+            let spills = meta.query_advice(a_3, Rotation::cur());
+            let spilled_before = meta.query_advice(a_4, Rotation::cur());
+            let is_last = meta.query_advice(a_6, Rotation::cur());
+
+            Constraints::with_selector(
+                s_last_block,
+                Some(("is_last", (one() - spills) * spilled_before - is_last)),
+            )
+        });
+
+        meta.create_gate("s_append_length", |meta| {
+            let s_append_length = meta.query_selector(s_append_length);
+            let word = meta.query_advice(a_3, Rotation::cur());
+            let is_last = meta.query_advice(a_4, Rotation::cur());
+            let len = meta.query_advice(a_6, Rotation::cur());
+            let out = meta.query_advice(a_7, Rotation::cur());
+
+            Constraints::with_selector(
+                s_append_length,
+                Some((
+                    "append_length",
+                    word + is_last * len * F::from(8) - out,
+                )),
+            )
+        });
+
+        meta.create_gate("s_select", |meta| {
+            let s_select = meta.query_selector(s_select);
+            let value = meta.query_advice(a_3, Rotation::cur());
+            let flag = meta.query_advice(a_4, Rotation::cur());
+            let acc = meta.query_advice(a_8, Rotation::cur());
+            let acc_prev = meta.query_advice(a_8, Rotation::prev());
+
+            Constraints::with_selector(s_select, Some(("select", acc_prev + flag * value - acc)))
+        });
+
+        VarLenConfig {
+            extras,
+            s_var_pad,
+            s_last_block,
+            s_append_length,
+            s_select,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Pads the message held in the first `length` of the given bytes, which span a
+    /// whole number of blocks. Returns the padded bytes, the assigned length in bytes,
+    /// and for each block a flag set only on the block the padding ends in.
+    #[allow(clippy::type_complexity)]
+    pub(super) fn pad(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        bytes: &[Value<u8>],
+        length: Value<u64>,
+    ) -> Result<(Vec<AssignedByte<F>>, AssignedWord<F>, Vec<AssignedWord<F>>), Sha512Error> {
+        assert_eq!(bytes.len() % BLOCK_BYTES, 0);
+        let max_blocks = bytes.len() / BLOCK_BYTES;
+
+        let (padded, length, is_msg) = assign_region(
+            layouter,
+            || "variable length padding",
+            |mut region| self.assign_padding(&mut region, bytes, length),
+        )?;
+
+        let is_last = assign_region(
+            layouter,
+            || "last block flags",
+            |mut region| {
+                let a_3 = self.extras[0];
+                let a_4 = self.extras[1];
+                let a_6 = self.extras[2];
+
+                Ok((0..max_blocks)
+                    .map(|block| {
+                        self.s_last_block.enable(&mut region, block)?;
+
+                        // The message leaves no room for the padding in a block if it
+                        // covers the first byte the padding would need.
+                        let spills = &is_msg[(block + 1) * BLOCK_BYTES - MIN_PADDING_BYTES];
+                        let spills = spills.copy_advice(|| "spills", &mut region, a_3, block)?;
+                        let spilled_before = if block == 0 {
+                            region.assign_advice_from_constant(
+                                || "spilled_before",
+                                a_4,
+                                block,
+                                F::from(1),
+                            )?
+                        } else {
+                            is_msg[block * BLOCK_BYTES - MIN_PADDING_BYTES].copy_advice(
+                                || "spilled_before",
+                                &mut region,
+                                a_4,
+                                block,
+                            )?
+                        };
+
+                        let value = spills
+                            .value()
+                            .zip(spilled_before.value())
+                            .map(|(spills, before)| (F::from(1) - spills) * before);
+                        region.assign_advice(|| "is_last", a_6, block, || value)
+                    })
+                    .collect::<Result<Vec<_>, Error>>()?)
+            },
+        )?;
+
+        Ok((padded, length, is_last))
+    }
+
+    #[allow(clippy::type_complexity)]
+    fn assign_padding(
+        &self,
+        region: &mut Region<'_, F>,
+        bytes: &[Value<u8>],
+        length: Value<u64>,
+    ) -> Result<(Vec<AssignedByte<F>>, AssignedWord<F>, Vec<AssignedWord<F>>), Sha512Error> {
+        let a_3 = self.extras[0];
+        let a_4 = self.extras[1];
+        let a_7 = self.extras[3];
+        let a_8 = self.extras[4];
+
+        region.assign_advice_from_constant(|| "is_msg", a_4, 0, F::from(1))?;
+        let mut len =
+            region.assign_advice_from_constant(|| "len", a_8, 0, F::from(0))?;
+
+        let mut padded = Vec::with_capacity(bytes.len());
+        let mut is_msg = Vec::with_capacity(bytes.len());
+        let mut prev_is_msg = Value::known(true);
+        for (idx, byte) in bytes.iter().enumerate() {
+            let row = idx + 1;
+            self.s_var_pad.enable(region, row)?;
+
+            let cur_is_msg = length.map(|length| (idx as u64) < length);
+            let is_end = prev_is_msg.zip(cur_is_msg).map(|(prev, cur)| prev && !cur);
+            prev_is_msg = cur_is_msg;
+
+            region.assign_advice(
+                || "byte",
+                a_3,
+                row,
+                || byte.map(|byte| F::from(byte as u64)),
+            )?;
+            is_msg.push(region.assign_advice(
+                || "is_msg",
+                a_4,
+                row,
+                || cur_is_msg.map(|b| F::from(b as u64)),
+            )?);
+
+            let value = byte
+                .zip(cur_is_msg)
+                .zip(is_end)
+                .map(|((byte, is_msg), is_end)| match (is_msg, is_end) {
+                    (true, _) => byte,
+                    (false, true) => 0x80,
+                    (false, false) => 0,
+                });
+            padded.push(region.assign_advice(
+                || "padded",
+                a_7,
+                row,
+                || value.map(|b| F::from(b as u64)),
+            )?);
+
+            let value = len.value().copied() + cur_is_msg.map(|b| F::from(b as u64));
+            len = region.assign_advice(|| "len", a_8, row, || value)?;
+        }
+
+        // The message must leave room for the padding in the final block.
+        let last = &is_msg[bytes.len() - MIN_PADDING_BYTES];
+        region.constrain_constant(last.cell(), F::from(0))?;
+
+        Ok((padded, len, is_msg))
+    }
+
+    /// Adds the message length `len` in bits to `word` if `is_last` is set.
+    pub(super) fn append_length(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        word: &AssignedWord<F>,
+        is_last: &AssignedWord<F>,
+        len: &AssignedWord<F>,
+    ) -> Result<AssignedWord<F>, Sha512Error> {
+        let a_3 = self.extras[0];
+        let a_4 = self.extras[1];
+        let a_6 = self.extras[2];
+        let a_7 = self.extras[3];
+
+        assign_region(
+            layouter,
+            || "append length",
+            |mut region| {
+                self.s_append_length.enable(&mut region, 0)?;
+
+                let word = word.copy_advice(|| "word", &mut region, a_3, 0)?;
+                let is_last = is_last.copy_advice(|| "is_last", &mut region, a_4, 0)?;
+                let len = len.copy_advice(|| "len", &mut region, a_6, 0)?;
+
+                let value = word
+                    .value()
+                    .zip(is_last.value())
+                    .zip(len.value())
+                    .map(|((word, is_last), len)| *word + *is_last * len * F::from(8));
+                Ok(region.assign_advice(|| "word", a_7, 0, || value)?)
+            },
+        )
+    }
+
+    /// Selects the digest whose flag is set. Exactly one flag must be set.
+    pub(super) fn select_digest(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        digests: &[[DigestWord<F>; DIGEST_SIZE]],
+        flags: &[AssignedWord<F>],
+    ) -> Result<[DigestWord<F>; DIGEST_SIZE], Sha512Error> {
+        assert_eq!(digests.len(), flags.len());
+        // Each selection takes a row per candidate, after an initial row.
+        let rows = flags.len() + 1;
+
+        assign_region(
+            layouter,
+            || "select digest",
+            |mut region| {
+                let selected = (0..DIGEST_SIZE)
+                    .map(|i| {
+                        let row = 3 * i * rows;
+                        let word = digests.iter().map(|digest| digest[i].word.clone());
+                        let word = self.select(&mut region, row, word.collect(), flags)?;
+
+                        let lo = digests.iter().map(|digest| digest[i].lo.0.clone());
+                        let lo = self.select(&mut region, row + rows, lo.collect(), flags)?;
+
+                        let hi = digests.iter().map(|digest| digest[i].hi.0.clone());
+                        let hi = self.select(&mut region, row + 2 * rows, hi.collect(), flags)?;
+
+                        Ok(DigestWord {
+                            word,
+                            lo: AssignedBits(lo),
+                            hi: AssignedBits(hi),
+                        })
+                    })
+                    .collect::<Result<Vec<_>, Error>>()?;
+
+                let actual = selected.len();
+                selected.try_into().map_err(|_| Sha512Error::WrongLength {
+                    expected: DIGEST_SIZE,
+                    actual,
+                })
+            },
+        )
+    }
+
+    /// Accumulates `sum_i flags[i] * values[i]` on the rows following `row`, returning
+    /// the selected value.
+    fn select<V: Clone>(
+        &self,
+        region: &mut Region<'_, F>,
+        row: usize,
+        values: Vec<AssignedCell<V, F>>,
+        flags: &[AssignedWord<F>],
+    ) -> Result<AssignedCell<V, F>, Error>
+    where
+        for<'v> Assigned<F>: From<&'v V>,
+    {
+        let a_3 = self.extras[0];
+        let a_4 = self.extras[1];
+        let a_8 = self.extras[4];
+
+        region.assign_advice_from_constant(|| "acc", a_8, row, F::from(0))?;
+
+        let mut acc = Value::known(F::from(0));
+        let mut selected: Value<Option<V>> = Value::known(None);
+        for (offset, (value, flag)) in values.iter().zip(flags.iter()).enumerate() {
+            let row = row + offset + 1;
+            self.s_select.enable(region, row)?;
+
+            let value = value.copy_advice(|| "value", region, a_3, row)?;
+            let flag = flag.copy_advice(|| "flag", region, a_4, row)?;
+
+            acc = acc + flag.value().copied() * value.value_field().evaluate();
+            selected = selected
+                .zip(flag.value())
+                .zip(value.value())
+                .map(|((selected, flag), value)| {
+                    if *flag == F::from(1) {
+                        Some(value.clone())
+                    } else {
+                        selected
+                    }
+                });
+
+            // The final sum is the selected value itself.
+            if offset + 1 < flags.len() {
+                region.assign_advice(|| "acc", a_8, row, || acc)?;
+            }
+        }
+
+        let selected = selected.map(|selected| selected.expect("exactly one flag is set"));
+        region.assign_advice(|| "selected", a_8, row + flags.len(), || selected.clone())
+    }
+}
@@ -0,0 +1,54 @@
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{AssignedCell, Region, Value},
+    plonk::{Advice, Column, Error},
+};
+
+/// The number of bits in a word.
+pub(super) const WORD_BITS: usize = 64;
+
+/// Assigns the bits of `word` in `bits` on rows 0..64, most significant first, and the
+/// accumulator of those bits in `acc`, as by [`accumulate_bits`]. The full accumulation
+/// is constrained to be the word itself.
+///
+/// Returns the accumulator cells, on rows `step`, `2 * step`, ..., 64.
+pub(super) fn decompose_word<F: FieldExt>(
+    region: &mut Region<'_, F>,
+    bits: Column<Advice>,
+    acc: Column<Advice>,
+    word: &AssignedCell<F, F>,
+    step: usize,
+) -> Result<Vec<AssignedCell<F, F>>, Error> {
+    let value = word.value().map(|word| word.get_lower_128() as u64);
+    for row in 0..WORD_BITS {
+        let bit = value.map(|word| (word >> (WORD_BITS - 1 - row)) & 1);
+        region.assign_advice(|| "bit", bits, row, || bit.map(F::from))?;
+    }
+
+    let acc = accumulate_bits(region, acc, value, step)?;
+    let full = acc.last().expect("step <= WORD_BITS");
+    region.constrain_equal(word.cell(), full.cell())?;
+    Ok(acc)
+}
+
+/// Assigns in `column` the accumulator of the bits of `value`, most significant first:
+/// zero on row 0, then on every `step`-th row the bits above the row, ending at `value`
+/// on row 64.
+///
+/// Returns the accumulator cells, on rows `step`, `2 * step`, ..., 64.
+pub(super) fn accumulate_bits<F: FieldExt>(
+    region: &mut Region<'_, F>,
+    column: Column<Advice>,
+    value: Value<u64>,
+    step: usize,
+) -> Result<Vec<AssignedCell<F, F>>, Error> {
+    assert!(step > 0 && WORD_BITS % step == 0);
+    region.assign_advice_from_constant(|| "acc", column, 0, F::from(0))?;
+    (step..=WORD_BITS)
+        .step_by(step)
+        .map(|row| {
+            let prefix = value.map(|value| F::from(value >> (WORD_BITS - row)));
+            region.assign_advice(|| "acc", column, row, || prefix)
+        })
+        .collect()
+}
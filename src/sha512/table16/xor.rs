@@ -0,0 +1,112 @@
+use super::{accumulate_bits, assign_region, decompose_word, AssignedWord, Sha512Error, WORD_BITS};
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::Layouter,
+    plonk::{Advice, Column, ConstraintSystem, Constraints, Expression, Selector},
+    poly::Rotation,
+};
+use std::marker::PhantomData;
+
+/// XORs two assigned words, for PBKDF2's sum of its iterated MACs.
+///
+/// Both words are decomposed into bits, most significant first, accumulating their
+/// values and the value of their XOR on the row after each bit:
+///
+/// | a_3  | a_9  | a_4  | a_6  | a_7       |
+/// |------|------|------|------|-----------|
+/// | x_63 | y_63 | 0    | 0    | 0         |
+/// | x_62 | y_62 | x_63 | y_63 | x_63^y_63 |
+/// | ...  | ...  | ...  | ...  | ...       |
+/// |      |      | x    | y    | x^y       |
+#[derive(Clone, Debug)]
+pub(super) struct XorConfig<F: FieldExt> {
+    extras: [Column<Advice>; 6],
+
+    /// Accumulates a bit of each word and of their XOR.
+    s_xor: Selector,
+
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> XorConfig<F> {
+    pub(super) fn configure(
+        meta: &mut ConstraintSystem<F>,
+        extras: [Column<Advice>; 6],
+    ) -> Self {
+        let s_xor = meta.selector();
+
+        // Rename these here for ease of matching the gates to the layout.
+        let a_3 = extras[0];
+        let a_4 = extras[1];
+        let a_6 = extras[2];
+        let a_7 = extras[3];
+        let a_9 = extras[5];
+
+        meta.create_gate("s_xor", |meta| {
+            let s_xor = meta.query_selector(s_xor);
+            let x = meta.query_advice(a_3, Rotation::cur());
+            let y = meta.query_advice(a_9, Rotation::cur());
+            let one = || Expression::Constant(F::from(1));
+
+            let mut step = |column: Column<Advice>, bit: Expression<F>| {
+                let acc = meta.query_advice(column, Rotation::cur());
+                let acc_next = meta.query_advice(column, Rotation::next());
+                acc * F::from(2) + bit - acc_next
+            };
+            let x_acc = step(a_4, x.clone());
+            let y_acc = step(a_6, y.clone());
+            let xor = x.clone() + y.clone() - x.clone() * y.clone() * F::from(2);
+            let xor_acc = step(a_7, xor);
+
+            Constraints::with_selector(
+                s_xor,
+                [
+                    ("x_bool", x.clone() * (one() - x)),
+                    ("y_bool", y.clone() * (one() - y)),
+                    ("x", x_acc),
+                    ("y", y_acc),
+                    ("xor", xor_acc),
+                ],
+            )
+        });
+
+        XorConfig {
+            extras,
+            s_xor,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Returns the XOR of `x` and `y`.
+    pub(super) fn xor(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        x: &AssignedWord<F>,
+        y: &AssignedWord<F>,
+    ) -> Result<AssignedWord<F>, Sha512Error> {
+        let a_3 = self.extras[0];
+        let a_4 = self.extras[1];
+        let a_6 = self.extras[2];
+        let a_7 = self.extras[3];
+        let a_9 = self.extras[5];
+
+        assign_region(
+            layouter,
+            || "xor words",
+            |mut region| {
+                for row in 0..WORD_BITS {
+                    self.s_xor.enable(&mut region, row)?;
+                }
+                decompose_word(&mut region, a_3, a_4, x, 1)?;
+                decompose_word(&mut region, a_9, a_6, y, 1)?;
+
+                let xor_value = x
+                    .value()
+                    .zip(y.value())
+                    .map(|(x, y)| x.get_lower_128() as u64 ^ y.get_lower_128() as u64);
+                let xor = accumulate_bits(&mut region, a_7, xor_value, 1)?;
+                Ok(xor.last().expect("step <= WORD_BITS").clone())
+            },
+        )
+    }
+}
@@ -0,0 +1,135 @@
+use super::{
+    super::hmac::{IPAD_BYTE, OPAD_BYTE},
+    accumulate_bits, assign_region, decompose_word, AssignedWord, Sha512Error, WORD_BITS,
+};
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::Layouter,
+    plonk::{Advice, Column, ConstraintSystem, Constraints, Error, Expression, Selector},
+    poly::Rotation,
+};
+use std::marker::PhantomData;
+
+/// XORs a key word with the HMAC inner and outer pads.
+///
+/// The word is decomposed into bits, most significant first. Each byte of bits
+/// advances the accumulators of the word and of its XOR with each pad, which start at
+/// zero and end at their full values on the row after the last bit:
+///
+/// | a_3  | a_4  | a_6  | a_7  |
+/// |------|------|------|------|
+/// | b_63 | 0    | 0    | 0    |
+/// | ...  |      |      |      |
+/// | b_55 | k_0  | i_0  | o_0  |
+/// | ...  | ...  | ...  | ...  |
+/// |      | word | ipad | opad |
+#[derive(Clone, Debug)]
+pub(super) struct XorPadConfig<F: FieldExt> {
+    extras: [Column<Advice>; 6],
+
+    /// Accumulates a byte of the word and of its XOR with each pad.
+    s_xor_pad: Selector,
+
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> XorPadConfig<F> {
+    pub(super) fn configure(
+        meta: &mut ConstraintSystem<F>,
+        extras: [Column<Advice>; 6],
+    ) -> Self {
+        let s_xor_pad = meta.selector();
+
+        // Rename these here for ease of matching the gates to the layout.
+        let a_3 = extras[0];
+        let a_4 = extras[1];
+        let a_6 = extras[2];
+        let a_7 = extras[3];
+
+        meta.create_gate("s_xor_pad", |meta| {
+            let s_xor_pad = meta.query_selector(s_xor_pad);
+            let one = || Expression::Constant(F::from(1));
+            let zero = || Expression::Constant(F::from(0));
+
+            let bits: Vec<_> = (0..8)
+                .map(|i| meta.query_advice(a_3, Rotation(i)))
+                .collect();
+            // XOR with a constant bit is the bit itself or its complement.
+            let xor = |pad: u8| {
+                bits.iter().enumerate().fold(zero(), |acc, (i, bit)| {
+                    let bit = if (pad >> (7 - i)) & 1 == 1 {
+                        one() - bit.clone()
+                    } else {
+                        bit.clone()
+                    };
+                    acc * F::from(2) + bit
+                })
+            };
+            let byte = xor(0);
+            let ipad = xor(IPAD_BYTE);
+            let opad = xor(OPAD_BYTE);
+
+            let mut step = |column: Column<Advice>, byte: Expression<F>| {
+                let acc = meta.query_advice(column, Rotation::cur());
+                let acc_next = meta.query_advice(column, Rotation(8));
+                acc * F::from(1 << 8) + byte - acc_next
+            };
+            let word = step(a_4, byte);
+            let ipad = step(a_6, ipad);
+            let opad = step(a_7, opad);
+
+            let bool_checks = bits
+                .iter()
+                .map(|bit| ("bit_bool", bit.clone() * (one() - bit.clone())));
+
+            Constraints::with_selector(
+                s_xor_pad,
+                bool_checks
+                    .chain([("word", word), ("ipad", ipad), ("opad", opad)])
+                    .collect::<Vec<_>>(),
+            )
+        });
+
+        XorPadConfig {
+            extras,
+            s_xor_pad,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Returns `word` XORed with the inner and outer pads.
+    pub(super) fn xor_pads(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        word: &AssignedWord<F>,
+    ) -> Result<(AssignedWord<F>, AssignedWord<F>), Sha512Error> {
+        let a_3 = self.extras[0];
+        let a_4 = self.extras[1];
+        let a_6 = self.extras[2];
+        let a_7 = self.extras[3];
+
+        let pad_word = |pad: u8| u64::from_be_bytes([pad; 8]);
+
+        assign_region(
+            layouter,
+            || "xor pads",
+            |mut region| {
+                for row in (0..WORD_BITS).step_by(8) {
+                    self.s_xor_pad.enable(&mut region, row)?;
+                }
+                decompose_word(&mut region, a_3, a_4, word, 8)?;
+
+                let value = word.value().map(|word| word.get_lower_128() as u64);
+                let mut xor_pad = |column: Column<Advice>, pad: u8| {
+                    let value = value.map(|word| word ^ pad_word(pad));
+                    let acc = accumulate_bits(&mut region, column, value, 8)?;
+                    Ok::<_, Error>(acc.last().expect("step <= WORD_BITS").clone())
+                };
+                let ipad = xor_pad(a_6, IPAD_BYTE)?;
+                let opad = xor_pad(a_7, OPAD_BYTE)?;
+
+                Ok((ipad, opad))
+            },
+        )
+    }
+}
@@ -0,0 +1,262 @@
+//! A native model of the witness the Table16 chip assigns for a block: the message
+//! schedule words, and for each round the working variables, the pieces of A and E,
+//! the spread sums behind Σ_0, Σ_1, Ch and Maj, and H'.
+//!
+//! Values are computed through spread forms, as the chip computes them, so that a wrong
+//! cell in a synthesized block can be pinned to the round whose entry it disagrees
+//! with. The working variables themselves come from the round function of
+//! [`native`].
+
+use super::{
+    native,
+    table16::{ROUNDS, ROUND_CONSTANTS},
+    BLOCK_SIZE, DIGEST_SIZE,
+};
+
+/// The pieces of A for Σ_0: (28, 6, 5, 25) bits, each split in two.
+const A_PIECES: [usize; 8] = [14, 14, 3, 3, 2, 3, 14, 11];
+/// The pieces of E for Σ_1: (14, 4, 23, 23) bits, the last three each split in two.
+const E_PIECES: [usize; 7] = [14, 2, 2, 13, 10, 13, 10];
+
+/// A piece of a word as the chip decomposes it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Piece {
+    /// The bit length of the piece.
+    pub bits: usize,
+    /// The value of the piece.
+    pub dense: u64,
+    /// The spread form of the piece, with a zero interleaved above each bit.
+    pub spread: u128,
+}
+
+/// The sum of the spread forms of two or three words, split into its even and odd
+/// bits: for two words their XOR and AND, and for three their XOR and majority.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SpreadSum {
+    /// The even bits of the sum.
+    pub even: u64,
+    /// The odd bits of the sum.
+    pub odd: u64,
+}
+
+/// A compression round.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Round {
+    /// The working variables A..H the round starts from.
+    pub state: [u64; DIGEST_SIZE],
+    /// The pieces of A for Σ_0, least significant first.
+    pub a_pieces: Vec<Piece>,
+    /// The pieces of E for Σ_1, least significant first.
+    pub e_pieces: Vec<Piece>,
+    /// The rotations of A, whose XOR is Σ_0(A).
+    pub upper_sigma_0: SpreadSum,
+    /// The rotations of E, whose XOR is Σ_1(E).
+    pub upper_sigma_1: SpreadSum,
+    /// E and F, whose AND is the first term of Ch(E, F, G).
+    pub ch: SpreadSum,
+    /// ¬E and G, whose AND is the second term of Ch(E, F, G).
+    pub ch_neg: SpreadSum,
+    /// A, B and C, whose majority is Maj(A, B, C).
+    pub maj: SpreadSum,
+    /// The round constant K_t.
+    pub k: u64,
+    /// The message schedule word W_t.
+    pub w: u64,
+    /// H' = H + Ch(E, F, G) + Σ_1(E) + K_t + W_t, the T_1 of FIPS 180-4.
+    pub h_prime: u64,
+    /// The new A, H' + Maj(A, B, C) + Σ_0(A).
+    pub a_new: u64,
+    /// The new E, H' + D.
+    pub e_new: u64,
+}
+
+/// The witness of a block.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BlockTrace {
+    /// The message schedule W_0..W_79.
+    pub schedule: [u64; ROUNDS],
+    /// The rounds 0..79.
+    pub rounds: Vec<Round>,
+    /// The working variables after the final round.
+    pub final_state: [u64; DIGEST_SIZE],
+    /// The intermediate hash value: the state the block started from plus the final
+    /// working variables.
+    pub output: [u64; DIGEST_SIZE],
+}
+
+/// Returns the spread form of a word, with a zero interleaved above each bit.
+pub fn spread(word: u64) -> u128 {
+    (0..64).fold(0, |acc, i| acc | (((word >> i) & 1) as u128) << (2 * i))
+}
+
+/// Returns the even bits of a spread sum.
+fn even_bits(sum: u128) -> u64 {
+    (0..64).fold(0, |acc, i| acc | (((sum >> (2 * i)) & 1) as u64) << i)
+}
+
+fn spread_sum(words: &[u64]) -> SpreadSum {
+    assert!(
+        words.len() <= 3,
+        "a spread sum of up to three words fits in 128 bits"
+    );
+    let sum: u128 = words.iter().map(|word| spread(*word)).sum();
+    SpreadSum {
+        even: even_bits(sum),
+        odd: even_bits(sum >> 1),
+    }
+}
+
+fn pieces(word: u64, lengths: &[usize]) -> Vec<Piece> {
+    let mut offset = 0;
+    lengths
+        .iter()
+        .map(|&bits| {
+            let dense = (word >> offset) & ((1 << bits) - 1);
+            offset += bits;
+            Piece {
+                bits,
+                dense,
+                spread: spread(dense),
+            }
+        })
+        .collect()
+}
+
+/// Traces the compression of a block from the given state.
+pub fn trace_block(state: [u64; DIGEST_SIZE], block: [u64; BLOCK_SIZE]) -> BlockTrace {
+    let schedule = native::schedule(block);
+
+    let mut working = state;
+    let mut rounds = Vec::with_capacity(ROUNDS);
+    for (w, k) in schedule.iter().zip(ROUND_CONSTANTS.iter()) {
+        let [a, b, c, _, e, f, g, h] = working;
+        let upper_sigma_0 =
+            spread_sum(&[a.rotate_right(28), a.rotate_right(34), a.rotate_right(39)]);
+        let upper_sigma_1 =
+            spread_sum(&[e.rotate_right(14), e.rotate_right(18), e.rotate_right(41)]);
+        let ch = spread_sum(&[e, f]);
+        let ch_neg = spread_sum(&[!e, g]);
+        let maj = spread_sum(&[a, b, c]);
+
+        let h_prime = h
+            .wrapping_add(ch.odd)
+            .wrapping_add(ch_neg.odd)
+            .wrapping_add(upper_sigma_1.even)
+            .wrapping_add(*k)
+            .wrapping_add(*w);
+        let next = native::round(working, *k, *w);
+
+        rounds.push(Round {
+            state: working,
+            a_pieces: pieces(a, &A_PIECES),
+            e_pieces: pieces(e, &E_PIECES),
+            upper_sigma_0,
+            upper_sigma_1,
+            ch,
+            ch_neg,
+            maj,
+            k: *k,
+            w: *w,
+            h_prime,
+            a_new: next[0],
+            e_new: next[4],
+        });
+        working = next;
+    }
+
+    let mut output = state;
+    for (output, word) in output.iter_mut().zip(working.iter()) {
+        *output = output.wrapping_add(*word);
+    }
+
+    BlockTrace {
+        schedule,
+        rounds,
+        final_state: working,
+        output,
+    }
+}
+
+/// Traces the compression of every block of a padded message, starting from the
+/// given IV.
+pub fn trace_message(iv: [u64; DIGEST_SIZE], message: &[u8]) -> Vec<BlockTrace> {
+    let mut state = iv;
+    native::pad(message)
+        .into_iter()
+        .map(|block| {
+            let trace = trace_block(state, block);
+            state = trace.output;
+            trace
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{native, table16::IV};
+    use super::{trace_block, trace_message};
+    use sha2::{Digest, Sha512};
+
+    #[test]
+    fn trace_matches_sha2() {
+        for len in [0, 3, 111, 112, 128, 300] {
+            let message = "1".repeat(len).into_bytes();
+            let trace = trace_message(IV, &message);
+            let digest: Vec<u8> = trace
+                .last()
+                .unwrap()
+                .output
+                .iter()
+                .flat_map(|word| word.to_be_bytes())
+                .collect();
+            assert_eq!(digest, Sha512::digest(&message).to_vec());
+        }
+    }
+
+    #[test]
+    fn trace_matches_native() {
+        let block = native::pad(b"abc")[0];
+        let trace = trace_block(IV, block);
+        assert_eq!(trace.output, native::compress(IV, block));
+
+        for (t, round) in trace.rounds.iter().enumerate() {
+            // The pieces of A and E make them up again.
+            for (pieces, word) in [
+                (&round.a_pieces, round.state[0]),
+                (&round.e_pieces, round.state[4]),
+            ] {
+                let mut offset = 0;
+                let mut value = 0;
+                for piece in pieces.iter() {
+                    value |= piece.dense << offset;
+                    offset += piece.bits;
+                }
+                assert_eq!(offset, 64, "round {}", t);
+                assert_eq!(value, word, "round {}", t);
+            }
+        }
+
+        for (t, round) in trace.rounds.iter().enumerate() {
+            // The spread sums hold the bitwise functions of the round in their even or
+            // odd bits, and add up to the new A and E.
+            let [a, b, c, d, e, f, g, _] = round.state;
+            let sigma_0 = a.rotate_right(28) ^ a.rotate_right(34) ^ a.rotate_right(39);
+            let sigma_1 = e.rotate_right(14) ^ e.rotate_right(18) ^ e.rotate_right(41);
+            assert_eq!(round.upper_sigma_0.even, sigma_0, "round {}", t);
+            assert_eq!(round.upper_sigma_1.even, sigma_1, "round {}", t);
+            assert_eq!(round.ch.odd, e & f, "round {}", t);
+            assert_eq!(round.ch_neg.odd, !e & g, "round {}", t);
+            assert_eq!(round.maj.odd, (a & b) ^ (a & c) ^ (b & c), "round {}", t);
+            assert_eq!(
+                round.a_new,
+                round
+                    .h_prime
+                    .wrapping_add(round.maj.odd)
+                    .wrapping_add(sigma_0),
+                "round {}",
+                t
+            );
+            assert_eq!(round.e_new, round.h_prime.wrapping_add(d), "round {}", t);
+        }
+    }
+}
@@ -0,0 +1,249 @@
+//! The public API a downstream circuit builds on, imported through the prelude. A
+//! change to the crate that breaks this file is a breaking change.
+
+mod common;
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    halo2curves::{bn256, pasta::pallas},
+    plonk::{Advice, Circuit, Column, ConstraintSystem, Error, Instance},
+};
+use sha2::{
+    Digest, Sha384 as OtherSha384, Sha512 as OtherSha512, Sha512_224 as OtherSha512_224,
+    Sha512_256 as OtherSha512_256,
+};
+use sha512_halo2::prelude::{
+    AssignedBits, AssignedByte, AssignedWord, Bits, BlockInput, BlockWord, ByteInput, DigestWord,
+    MidstateWord, Sha384, Sha384Digest, Sha384Variant, Sha512, Sha512Digest, Sha512Error,
+    Sha512FullVariant, Sha512Instructions, Sha512T, Sha512TDigest, Sha512TInstructions,
+    Sha512Truncated, Sha512VarLenInstructions, Sha512Variant, Sha512_224, Sha512_224Digest,
+    Sha512_256, Sha512_256Digest, Sha512_256Variant, State, Table16Chip, Table16Config, BLOCK_SIZE,
+    DIGEST_SIZE, IV, IV_384, IV_512_224, IV_512_256,
+};
+use sha512_halo2::sha512::native;
+
+use common::mock_prover;
+
+/// The block hashed outside the circuit before the message, for the midstate digest.
+const PREFIX: [u8; BLOCK_SIZE * 8] = [b'a'; BLOCK_SIZE * 8];
+/// The SHA-512/t digest size the circuit hashes the message with.
+const T: usize = 200;
+
+/// Hashes `message` as a message of witnessed length, through any chip supporting it.
+fn digest_var_len<F: FieldExt, CS: Sha512VarLenInstructions<F>>(
+    chip: CS,
+    layouter: impl Layouter<F>,
+    message: &[Value<u8>],
+) -> Result<Sha512Digest<CS::DigestWord>, Sha512Error> {
+    let length = Value::known(message.len() as u64);
+    Sha512::digest_var_len(chip, layouter, message, length, 1).map(|(digest, _)| digest)
+}
+
+/// Hashes `message` with SHA-512/t, through any chip supporting it.
+fn digest_t<F: FieldExt, CS: Sha512TInstructions<F>>(
+    chip: CS,
+    layouter: impl Layouter<F>,
+    t: usize,
+    message: &[Value<u8>],
+) -> Result<Sha512TDigest<CS::DigestWord, CS::AssignedWord>, Sha512Error> {
+    Sha512T::digest_bytes(chip, layouter, t, message)
+}
+
+#[derive(Clone, Debug)]
+struct ApiConfig<F: FieldExt> {
+    table16: Table16Config<F>,
+    bytes: Column<Advice>,
+    instance: Column<Instance>,
+}
+
+/// Hashes a message of under 8 bytes with each gadget, and once by chaining the chip's
+/// instructions by hand from the IV. The digests are exposed one after the other.
+struct ApiCircuit {
+    message: Vec<u8>,
+}
+
+impl<F: FieldExt> Circuit<F> for ApiCircuit {
+    type Config = ApiConfig<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        ApiCircuit {
+            message: self.message.clone(),
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let bytes = meta.advice_column();
+        meta.enable_equality(bytes);
+        let instance = meta.instance_column();
+        meta.enable_equality(instance);
+        ApiConfig {
+            table16: Table16Chip::configure(meta),
+            bytes,
+            instance,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        Table16Chip::load(config.table16.clone(), &mut layouter)?;
+        let chip = Table16Chip::construct(config.table16);
+        let instance = config.instance;
+
+        let bytes: Vec<Value<u8>> = self.message.iter().copied().map(Value::known).collect();
+        let assigned: Vec<AssignedByte<F>> = layouter.assign_region(
+            || "message bytes",
+            |mut region| {
+                self.message
+                    .iter()
+                    .enumerate()
+                    .map(|(i, byte)| {
+                        region.assign_advice(
+                            || "byte",
+                            config.bytes,
+                            i,
+                            || Value::known(F::from(*byte as u64)),
+                        )
+                    })
+                    .collect()
+            },
+        )?;
+
+        let digest: Sha512Digest<DigestWord<F>> =
+            Sha512::digest_bytes(chip.clone(), layouter.namespace(|| "sha512"), &bytes)?;
+        digest.expose_public(layouter.namespace(|| "sha512 digest"), instance, 0)?;
+
+        // Pack the message and its padding into the first word, and chain the chip's
+        // instructions over the block it starts.
+        let mut word: [ByteInput<AssignedByte<F>>; 8] = [(); 8].map(|_| ByteInput::Padding(0));
+        word[assigned.len()] = ByteInput::Padding(0x80);
+        for (input, byte) in word.iter_mut().zip(&assigned) {
+            *input = ByteInput::Assigned(byte.clone());
+        }
+        let word: AssignedWord<F> = chip.pack_bytes(&mut layouter, word)?;
+        let mut block: [BlockInput<BlockWord, AssignedWord<F>>; BLOCK_SIZE] =
+            [(); BLOCK_SIZE].map(|_| BlockInput::Padding(0));
+        block[0] = BlockInput::Assigned(word);
+        block[BLOCK_SIZE - 1] = BlockInput::Padding(self.message.len() as u64 * 8);
+        let iv: State<F> = chip.initialization_vector(&mut layouter, IV)?;
+        let state: State<F> = chip.initialization(&mut layouter, &iv)?;
+        let state: State<F> = chip.compress_block(&mut layouter, &state, block)?;
+        let words: [DigestWord<F>; DIGEST_SIZE] = chip.digest(&mut layouter, &state)?;
+        // The halves of a digest word recombine into the word.
+        let (lo, hi): (&AssignedBits<F, 32>, &AssignedBits<F, 32>) = (words[0].lo(), words[0].hi());
+        let halves: Value<(&Bits<32>, &Bits<32>)> = lo.value().zip(hi.value());
+        words[0]
+            .value()
+            .zip(halves)
+            .assert_if_known(|(word, (lo, hi))| {
+                *word == (u32::from(*lo) as u64 | ((u32::from(*hi) as u64) << 32))
+            });
+        Sha512Digest(words).expose_public(layouter.namespace(|| "chained digest"), instance, 8)?;
+
+        let mut hasher = Sha512::new(chip.clone(), layouter.namespace(|| "assigned init"))?;
+        hasher.update_assigned_bytes(layouter.namespace(|| "assigned update"), &assigned)?;
+        let digest = hasher.finalize(layouter.namespace(|| "assigned finalize"))?;
+        digest.expose_public(layouter.namespace(|| "assigned digest"), instance, 16)?;
+
+        let digest = digest_var_len(chip.clone(), layouter.namespace(|| "var len"), &bytes)?;
+        digest.expose_public(layouter.namespace(|| "var len digest"), instance, 24)?;
+
+        let digest: Sha384Digest<DigestWord<F>> =
+            Sha384::digest_bytes(chip.clone(), layouter.namespace(|| "sha384"), &bytes)?;
+        digest.expose_public(layouter.namespace(|| "sha384 digest"), instance, 32)?;
+
+        let digest: Sha512_256Digest<DigestWord<F>> =
+            Sha512_256::digest_bytes(chip.clone(), layouter.namespace(|| "sha512/256"), &bytes)?;
+        digest.expose_public(layouter.namespace(|| "sha512/256 digest"), instance, 38)?;
+
+        let digest: Sha512_224Digest<DigestWord<F>, AssignedWord<F>> =
+            Sha512_224::digest_bytes(chip.clone(), layouter.namespace(|| "sha512/224"), &bytes)?;
+        digest.expose_public(layouter.namespace(|| "sha512/224 digest"), instance, 42)?;
+
+        let digest = digest_t(chip.clone(), layouter.namespace(|| "sha512/t"), T, &bytes)?;
+        digest.expose_public(layouter.namespace(|| "sha512/t digest"), instance, 46)?;
+        // SHA-512/384 is SHA-384, which SHA-512/t leaves to its own IV.
+        assert!(matches!(
+            Sha512T::new(chip.clone(), layouter.namespace(|| "sha512/384"), 384),
+            Err(Sha512Error::MalformedInput(_))
+        ));
+
+        let midstate = native::midstate(IV, &PREFIX).map(MidstateWord::Constant);
+        let mut hasher = Sha512::from_midstate(
+            chip.clone(),
+            layouter.namespace(|| "midstate init"),
+            midstate,
+            PREFIX.len(),
+        )?;
+        hasher.update_bytes(layouter.namespace(|| "midstate update"), &bytes)?;
+        let digest = hasher.finalize(layouter.namespace(|| "midstate finalize"))?;
+        digest.expose_public(layouter.namespace(|| "midstate digest"), instance, 50)?;
+
+        let digest = Sha512Truncated::<F, Table16Chip<F>, Sha512FullVariant>::digest_bytes(
+            chip,
+            layouter.namespace(|| "full variant"),
+            &bytes,
+        )?;
+        digest.expose_public(layouter.namespace(|| "full variant digest"), instance, 58)
+    }
+}
+
+/// Splits a digest into big-endian 64-bit words, the last of which may be shorter.
+fn digest_words<F: FieldExt>(digest: &[u8]) -> Vec<F> {
+    digest
+        .chunks(8)
+        .map(|word| F::from(word.iter().fold(0, |acc, byte| acc << 8 | *byte as u64)))
+        .collect()
+}
+
+fn api_test<F: FieldExt>() {
+    let message = b"abc".to_vec();
+    let sha512 = digest_words::<F>(&OtherSha512::digest(&message));
+    let digest_t: Vec<u8> = native::digest_with_iv(native::sha512_t_iv(T), &message)
+        .iter()
+        .flat_map(|word| word.to_be_bytes())
+        .take(T / 8)
+        .collect();
+    let prefixed = [&PREFIX[..], &message].concat();
+
+    let instance = [
+        sha512.clone(),
+        sha512.clone(),
+        sha512.clone(),
+        sha512.clone(),
+        digest_words(&OtherSha384::digest(&message)),
+        digest_words(&OtherSha512_256::digest(&message)),
+        digest_words(&OtherSha512_224::digest(&message)),
+        digest_words(&digest_t),
+        digest_words(&OtherSha512::digest(&prefixed)),
+        sha512,
+    ]
+    .concat();
+    assert_eq!(instance.len(), 66);
+    mock_prover(&ApiCircuit { message }, vec![instance]).assert_satisfied();
+}
+
+#[test]
+fn sha512_api_test() {
+    api_test::<bn256::Fr>();
+    api_test::<pallas::Base>();
+}
+
+#[test]
+fn sha512_api_sizes() {
+    assert_eq!(BLOCK_SIZE, 16);
+    assert_eq!(DIGEST_SIZE, 8);
+    assert_eq!(IV[0], 0x6a09e667f3bcc908);
+    assert_eq!(IV_384[0], 0xcbbb9d5dc1059ed8);
+    assert_eq!(IV_512_224, native::sha512_t_iv(224));
+    assert_eq!(IV_512_256, native::sha512_t_iv(256));
+
+    // The variants fix the IV of the truncated gadgets.
+    assert_eq!(<Sha512FullVariant as Sha512Variant<()>>::IV, IV);
+    assert_eq!(<Sha384Variant as Sha512Variant<()>>::IV, IV_384);
+    assert_eq!(<Sha512_256Variant as Sha512Variant<()>>::IV, IV_512_256);
+}
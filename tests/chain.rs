@@ -0,0 +1,60 @@
+mod common;
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    halo2curves::{bn256, pasta::pallas},
+};
+use sha2::{Digest, Sha512};
+use sha512_halo2::sha512::{chain::split_message, IV};
+
+use common::mock_prover;
+
+fn chain_test<F: FieldExt>(message: &[u8], blocks: usize, links: usize) {
+    let chain = split_message(message, blocks).unwrap();
+    assert_eq!(chain.len(), links);
+
+    // The chain starts from the IV, each link from the output of the one before it,
+    // and ends in the digest.
+    assert_eq!(chain[0].1.input, IV);
+    for pair in chain.windows(2) {
+        assert_eq!(pair[0].1.output, pair[1].1.input);
+    }
+    let digest: Vec<u8> = chain[links - 1]
+        .1
+        .output
+        .iter()
+        .flat_map(|word| word.to_be_bytes())
+        .collect();
+    assert_eq!(digest, Sha512::digest(message).to_vec());
+
+    for (circuit, public) in chain.iter() {
+        mock_prover(circuit, vec![public.instance::<F>()]).assert_satisfied();
+    }
+}
+
+#[test]
+fn sha512_chain_test() {
+    // Three one-block chunks, then the last 66 bytes with their padding.
+    let message = "abc".repeat(150).into_bytes();
+    chain_test::<bn256::Fr>(&message, 1, 4);
+    chain_test::<pallas::Base>(&message, 1, 4);
+}
+
+#[test]
+fn sha512_chain_empty_last_chunk_test() {
+    // A message filling its chunks leaves only the padding to the last.
+    let message = "12".repeat(128).into_bytes();
+    chain_test::<bn256::Fr>(&message, 2, 2);
+}
+
+#[test]
+fn sha512_chain_broken_link_test() {
+    // A link must be rejected when started from a chaining value other than the one
+    // its chunk was hashed from.
+    let message = "abc".repeat(150).into_bytes();
+    let chain = split_message(&message, 1).unwrap();
+    let (circuit, public) = &chain[1];
+    let mut instance: Vec<bn256::Fr> = public.instance();
+    instance[0] += bn256::Fr::from(1);
+    assert!(mock_prover(circuit, vec![instance]).verify().is_err());
+}
@@ -0,0 +1,14 @@
+//! Helpers shared by the integration tests.
+
+use halo2_proofs::{arithmetic::FieldExt, dev::MockProver, plonk::Circuit};
+
+/// Runs the mock prover on `circuit` over the field `F`.
+pub fn mock_prover<F: FieldExt, C: Circuit<F>>(
+    circuit: &C,
+    instance: Vec<Vec<F>>,
+) -> MockProver<F> {
+    match MockProver::<F>::run(19, circuit, instance) {
+        Ok(prover) => prover,
+        Err(e) => panic!("{:?}", e),
+    }
+}
@@ -1,166 +1,743 @@
+mod common;
+
 use hex_literal::hex;
-use sha2::{Sha512, Digest, digest::{generic_array::GenericArray, typenum::{U64, U8, U2}}};
-use sha512_halo2::sha512::{BlockWord, Sha512 as OtherSha512, Table16Chip, Table16Config,IV,BLOCK_SIZE};
+use sha2::{Sha384, Sha512, Sha512_224, Sha512_256, Digest, digest::{generic_array::GenericArray, typenum::{U64, U8, U2}}};
+use sha512_halo2::sha512::{
+    native, BlockWord, MidstateWord, Sha384 as OtherSha384, Sha512 as OtherSha512, Sha512Error,
+    Sha512T, Sha512_224 as OtherSha512_224, Sha512_256 as OtherSha512_256, Table16Chip,
+    Table16Config, BLOCK_SIZE, IV, IV_512_224,
+};
 use halo2_proofs::{
     circuit::{AssignedCell, Chip, Layouter, Region, Value, SimpleFloorPlanner},
-    halo2curves::bn256,
-    plonk::{Advice, Any, Assigned, Column, ConstraintSystem, Error, Circuit}, dev::MockProver,
+    halo2curves::{bn256, pasta::pallas},
+    plonk::{Advice, Any, Assigned, Column, ConstraintSystem, Error, Circuit, Instance},
 };
 use halo2_proofs::arithmetic::FieldExt;
 
-fn preprocess_message(message: &str) -> Vec<Vec<u8>> {
-    // translate message into bits
-    let bits = translate(message);
-    // message length
-    let length = bits.len();
-    // get length in bits of message (64 bit block)
-    let message_len = format!("{:0128b}", length)
-        .chars()
-        .map(|c| c.to_digit(10).unwrap() as u8)
-        .collect::<Vec<_>>();
-    // if length smaller than 448 handle block individually otherwise
-    // if exactly 448 then add single 1 and add up to 1024 and if longer than 448
-    // create multiple of 512 - 64 bits for the length at the end of the message (big endian)
-    if length < 896 {
-        // append single 1
-        let mut bits = bits;
-        bits.push(1);
-        // fill zeros little endian wise
-        fill_zeros(&mut bits, 896, "LE");
-        // add the 64 bits representing the length of the message
-        bits.extend_from_slice(&message_len);
-        // return as list
-        vec![bits]
-    } else if length == 896 {
-        let mut bits = bits;
-        bits.push(1);
-        // moves to next message block - total length = 1024
-        fill_zeros(&mut bits, 1024, "LE");
-        // replace the last 64 bits of the multiple of 512 with the original message length
-        let ln = bits.len();
-        bits[ln - 128..].copy_from_slice(&message_len);
-        // returns it in 512 bit chunks
-        chunker(&bits, 1024)
-    } else {
-        let mut bits = bits;
-        bits.push(1);
-        // loop until multiple of 512 if message length exceeds 448 bits
-        while bits.len() % 1024 != 0 {
-            bits.push(0);
-        }
-        // replace the last 64 bits of the multiple of 512 with the original message length
-        let ln = bits.len();
-        bits[ln - 128..].copy_from_slice(&message_len);
-        // returns it in 512 bit chunks
-        chunker(&bits, 1024)
-    }
-}
-
-fn translate(message: &str) -> Vec<u8> {
-    // string characters to unicode values
-    let charcodes = message.chars().map(|c| c as u32);
-    // unicode values to 8-bit strings (removed binary indicator)
-    let bytes = charcodes
-        .map(|char| format!("{:08b}", char))
-        .collect::<Vec<_>>();
-    // 8-bit strings to list of bits as integers
-    let mut bits = Vec::new();
-    for byte in bytes {
-        for bit in byte.chars() {
-            bits.push(bit.to_digit(10).unwrap() as u8);
+use common::mock_prover;
+
+/// Packs a message into big-endian 64-bit words. The message length must be a
+/// multiple of 8 bytes.
+fn message_words(message: &[u8]) -> Vec<BlockWord> {
+    assert_eq!(message.len() % 8, 0);
+    message
+        .chunks(8)
+        .map(|chunk| BlockWord(Value::known(u64::from_be_bytes(chunk.try_into().unwrap()))))
+        .collect()
+}
+
+#[derive(Default)]
+struct MyCircuit {
+    message: Vec<u8>,
+}
+
+impl<F: FieldExt> Circuit<F> for MyCircuit {
+    type Config = Table16Config<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        Table16Chip::configure(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        Table16Chip::load(config.clone(), &mut layouter)?;
+        let table16_chip = Table16Chip::construct(config);
+
+        let words = message_words(&self.message);
+        let digest = OtherSha512::digest(table16_chip, layouter.namespace(|| "message"), &words)?;
+
+        let expected_digest = Sha512::digest(&self.message);
+        for (digest_word, expected) in digest.0.iter().zip(expected_digest.chunks(8)) {
+            let expected = u64::from_be_bytes(expected.try_into().unwrap());
+            digest_word.value().assert_if_known(|digest_word| *digest_word == expected);
         }
+        Ok(())
+    }
+}
+
+#[test]
+fn sha512_test(){
+    // Test vector: "12" * 55, which ends partway through a word and leaves room for
+    // the padding in one block, so is hashed as bytes.
+    let circuit = BytesCircuit {
+        message: "12".repeat(55).into_bytes(),
+    };
+    mock_prover::<bn256::Fr, _>(&circuit, vec![]).assert_satisfied();
+    mock_prover::<pallas::Base, _>(&circuit, vec![]).assert_satisfied();
+}
+
+#[test]
+fn sha512_multi_block_test(){
+    // Test vector: "12" * 128, two full blocks followed by a block of padding.
+    let circuit = MyCircuit {
+        message: "12".repeat(128).into_bytes(),
+    };
+    mock_prover::<bn256::Fr, _>(&circuit, vec![]).assert_satisfied();
+    mock_prover::<pallas::Base, _>(&circuit, vec![]).assert_satisfied();
+}
+
+#[derive(Default)]
+struct PublicDigestCircuit {
+    message: Vec<u8>,
+}
+
+impl<F: FieldExt> Circuit<F> for PublicDigestCircuit {
+    type Config = (Table16Config<F>, Column<Instance>);
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let instance = meta.instance_column();
+        meta.enable_equality(instance);
+        (Table16Chip::configure(meta), instance)
+    }
+
+    fn synthesize(
+        &self,
+        (config, instance): Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        Table16Chip::load(config.clone(), &mut layouter)?;
+        let table16_chip = Table16Chip::construct(config);
+
+        let words = message_words(&self.message);
+        let digest = OtherSha512::digest(table16_chip, layouter.namespace(|| "message"), &words)?;
+        digest.expose_public(layouter.namespace(|| "expose digest"), instance, 0)
     }
-    bits
 }
 
-fn fill_zeros(bits: &mut Vec<u8>, length: usize, endian: &str) {
-    let l = bits.len();
-    if endian == "LE" {
-        for _ in l..length {
-            bits.push(0);
+/// The digest of `message` as eight public 64-bit words.
+fn digest_instance<F: FieldExt>(message: &[u8]) -> Vec<F> {
+    Sha512::digest(message)
+        .chunks(8)
+        .map(|word| F::from(u64::from_be_bytes(word.try_into().unwrap())))
+        .collect()
+}
+
+fn public_digest_test<F: FieldExt>() {
+    let message = "12".repeat(52).into_bytes();
+    let mut instance: Vec<F> = digest_instance(&message);
+    let circuit = PublicDigestCircuit { message };
+
+    mock_prover(&circuit, vec![instance.clone()]).assert_satisfied();
+
+    // A different claimed digest must be rejected.
+    instance[5] += F::from(1);
+    assert!(mock_prover(&circuit, vec![instance]).verify().is_err());
+}
+
+#[test]
+fn sha512_public_digest_test() {
+    public_digest_test::<bn256::Fr>();
+    public_digest_test::<pallas::Base>();
+}
+
+#[derive(Default)]
+struct AssignedInputCircuit {
+    message: Vec<u8>,
+}
+
+impl<F: FieldExt> Circuit<F> for AssignedInputCircuit {
+    type Config = (Table16Config<F>, Column<Advice>);
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let input = meta.advice_column();
+        meta.enable_equality(input);
+        (Table16Chip::configure(meta), input)
+    }
+
+    fn synthesize(
+        &self,
+        (config, input): Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        Table16Chip::load(config.clone(), &mut layouter)?;
+        let table16_chip = Table16Chip::construct(config);
+
+        // Assign the message in a region of our own, as another gadget would.
+        let words = message_words(&self.message);
+        let cells = layouter.assign_region(
+            || "message",
+            |mut region| {
+                words
+                    .iter()
+                    .enumerate()
+                    .map(|(i, word)| {
+                        region.assign_advice(
+                            || format!("message word {}", i),
+                            input,
+                            i,
+                            || word.0.map(F::from),
+                        )
+                    })
+                    .collect::<Result<Vec<_>, _>>()
+            },
+        )?;
+        let digest = OtherSha512::digest_assigned(
+            table16_chip.clone(),
+            layouter.namespace(|| "message"),
+            &cells,
+        )?;
+
+        // Hash the digest cells again, so that SHA-512(SHA-512(message)) is constrained
+        // end to end.
+        let digest_cells: Vec<_> = digest.0.iter().map(|word| word.word().clone()).collect();
+        let digest = OtherSha512::digest_assigned(
+            table16_chip,
+            layouter.namespace(|| "digest"),
+            &digest_cells,
+        )?;
+
+        let expected_digest = Sha512::digest(Sha512::digest(&self.message));
+        for (digest_word, expected) in digest.0.iter().zip(expected_digest.chunks(8)) {
+            let expected = u64::from_be_bytes(expected.try_into().unwrap());
+            digest_word.value().assert_if_known(|digest_word| *digest_word == expected);
         }
-    } else {
-        while bits.len() < length {
-            bits.insert(0, 0);
+        Ok(())
+    }
+}
+
+#[test]
+fn sha512_assigned_input_test() {
+    // Test vector: "12" * 128, two full blocks of assigned words.
+    let circuit = AssignedInputCircuit {
+        message: "12".repeat(128).into_bytes(),
+    };
+    mock_prover::<bn256::Fr, _>(&circuit, vec![]).assert_satisfied();
+    mock_prover::<pallas::Base, _>(&circuit, vec![]).assert_satisfied();
+}
+
+#[derive(Default)]
+struct BytesCircuit {
+    message: Vec<u8>,
+}
+
+impl<F: FieldExt> Circuit<F> for BytesCircuit {
+    type Config = Table16Config<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        Table16Chip::configure(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        Table16Chip::load(config.clone(), &mut layouter)?;
+        let table16_chip = Table16Chip::construct(config);
+
+        let bytes: Vec<_> = self.message.iter().copied().map(Value::known).collect();
+        let digest =
+            OtherSha512::digest_bytes(table16_chip, layouter.namespace(|| "message"), &bytes)?;
+
+        let expected_digest = Sha512::digest(&self.message);
+        for (digest_word, expected) in digest.0.iter().zip(expected_digest.chunks(8)) {
+            let expected = u64::from_be_bytes(expected.try_into().unwrap());
+            digest_word.value().assert_if_known(|digest_word| *digest_word == expected);
         }
+        Ok(())
     }
 }
 
-fn chunker(bits: &[u8], chunk_length: usize) -> Vec<Vec<u8>> {
-    bits.chunks(chunk_length).map(|chunk| chunk.to_vec()).collect()
+#[test]
+fn sha512_bytes_test() {
+    // "abc", and lengths either side of the point where the padding spills into a
+    // second block, and where the message ends mid-word.
+    for message in [
+        b"abc".to_vec(),
+        "1".repeat(111).into_bytes(),
+        "1".repeat(112).into_bytes(),
+        "1".repeat(133).into_bytes(),
+    ] {
+        let circuit = BytesCircuit { message };
+        mock_prover::<bn256::Fr, _>(&circuit, vec![]).assert_satisfied();
+        mock_prover::<pallas::Base, _>(&circuit, vec![]).assert_satisfied();
+    }
 }
 
+const VAR_LEN_MAX_BLOCKS: usize = 2;
+
+#[derive(Default)]
+struct VarLenCircuit {
+    message: Vec<u8>,
+}
+
+impl<F: FieldExt> Circuit<F> for VarLenCircuit {
+    type Config = (Table16Config<F>, Column<Instance>);
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let instance = meta.instance_column();
+        meta.enable_equality(instance);
+        (Table16Chip::configure(meta), instance)
+    }
+
+    fn synthesize(
+        &self,
+        (config, instance): Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        Table16Chip::load(config.clone(), &mut layouter)?;
+        let table16_chip = Table16Chip::construct(config);
+
+        let bytes: Vec<_> = self.message.iter().copied().map(Value::known).collect();
+        let (digest, length) = OtherSha512::digest_var_len(
+            table16_chip,
+            layouter.namespace(|| "message"),
+            &bytes,
+            Value::known(self.message.len() as u64),
+            VAR_LEN_MAX_BLOCKS,
+        )?;
+        digest.expose_public(layouter.namespace(|| "expose digest"), instance, 0)?;
+        layouter.constrain_instance(length.cell(), instance, 8)
+    }
+}
+
+fn var_len_instance<F: FieldExt>(message: &[u8]) -> Vec<F> {
+    let mut instance = digest_instance(message);
+    instance.push(F::from(message.len() as u64));
+    instance
+}
+
+fn var_len_test<F: FieldExt>() {
+    // The same circuit hashes messages ending in either block, with the padding
+    // spilling into the second block for 112 bytes.
+    for len in [0, 3, 111, 112, 128, 239] {
+        let message = "1".repeat(len).into_bytes();
+        let instance = var_len_instance::<F>(&message);
+        let circuit = VarLenCircuit { message };
+        mock_prover(&circuit, vec![instance]).assert_satisfied();
+    }
+}
 
+#[test]
+fn sha512_var_len_test() {
+    var_len_test::<bn256::Fr>();
+    var_len_test::<pallas::Base>();
+}
 
 #[test]
-fn sha512_test(){
-    #[derive(Default)]
-    struct MyCircuit {}
+fn sha512_var_len_wrong_length_test() {
+    // Claiming a shorter prefix of the message must not verify.
+    let message = "1".repeat(100).into_bytes();
+    let mut instance = var_len_instance::<bn256::Fr>(&message[..99]);
+    instance[8] = bn256::Fr::from(100);
+    let circuit = VarLenCircuit { message };
+    assert!(mock_prover(&circuit, vec![instance]).verify().is_err());
+}
+
+/// Hashes a message with the member of the SHA-512 family whose output has `bits` bits,
+/// exposing the digest as public inputs.
+#[derive(Default)]
+struct TruncatedCircuit {
+    message: Vec<u8>,
+    bits: usize,
+}
+
+impl<F: FieldExt> Circuit<F> for TruncatedCircuit {
+    type Config = (Table16Config<F>, Column<Instance>);
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            message: vec![],
+            bits: self.bits,
+        }
+    }
 
-    impl Circuit<bn256::Fr> for MyCircuit {
-        type Config = Table16Config;
-        type FloorPlanner = SimpleFloorPlanner;
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let instance = meta.instance_column();
+        meta.enable_equality(instance);
+        (Table16Chip::configure(meta), instance)
+    }
+
+    fn synthesize(
+        &self,
+        (config, instance): Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        Table16Chip::load(config.clone(), &mut layouter)?;
+        let table16_chip = Table16Chip::construct(config);
 
-        fn without_witnesses(&self) -> Self {
-            Self::default()
+        let bytes: Vec<_> = self.message.iter().copied().map(Value::known).collect();
+        let message = layouter.namespace(|| "message");
+        match self.bits {
+            384 => OtherSha384::digest_bytes(table16_chip, message, &bytes)?
+                .expose_public(layouter.namespace(|| "expose digest"), instance, 0),
+            256 => OtherSha512_256::digest_bytes(table16_chip, message, &bytes)?
+                .expose_public(layouter.namespace(|| "expose digest"), instance, 0),
+            224 => OtherSha512_224::digest_bytes(table16_chip, message, &bytes)?
+                .expose_public(layouter.namespace(|| "expose digest"), instance, 0),
+            _ => unreachable!(),
         }
+    }
+}
+
+/// Splits a digest into public 64-bit words, with a trailing half word as a 32-bit
+/// value.
+fn truncated_instance<F: FieldExt>(digest: &[u8]) -> Vec<F> {
+    digest
+        .chunks(8)
+        .map(|word| {
+            let word = word.iter().fold(0u64, |acc, byte| (acc << 8) + *byte as u64);
+            F::from(word)
+        })
+        .collect()
+}
 
-        fn configure(meta: &mut ConstraintSystem<bn256::Fr>) -> Self::Config {
-            Table16Chip::configure(meta)
+fn truncated_test<F: FieldExt>() {
+    // "abc" fits in one block; 112 bytes spill the padding into a second block, and
+    // 300 bytes span three.
+    for message in [
+        b"abc".to_vec(),
+        "1".repeat(112).into_bytes(),
+        "1".repeat(300).into_bytes(),
+    ] {
+        for (bits, digest) in [
+            (384, Sha384::digest(&message).to_vec()),
+            (256, Sha512_256::digest(&message).to_vec()),
+            (224, Sha512_224::digest(&message).to_vec()),
+        ] {
+            let instance = truncated_instance::<F>(&digest);
+            let circuit = TruncatedCircuit {
+                message: message.clone(),
+                bits,
+            };
+            mock_prover(&circuit, vec![instance]).assert_satisfied();
         }
+    }
+}
+
+#[test]
+fn sha512_truncated_test() {
+    truncated_test::<bn256::Fr>();
+    truncated_test::<pallas::Base>();
+}
 
-        fn synthesize(
-            &self,
-            config: Self::Config,
-            mut layouter: impl Layouter<bn256::Fr>,
-        ) -> Result<(), Error> {
-            Table16Chip::load(config.clone(), &mut layouter)?;
-            let table16_chip = Table16Chip::construct(config);
-
-            // Test vector: "12"
-            let str = "12";
-            let result = preprocess_message(&str.repeat(55));
-            let mut res_new : Vec<Vec<Vec<u8>>> = Vec::new();
-            for y in result{
-                let chnk = y.chunks(64).map(|chunk| chunk.to_vec()).collect::<Vec<_>>();
-                res_new.push(chnk);
-            }
-            let mut str_vec : Vec<BlockWord> = Vec::new();
-            for y in res_new {
-                for x in y {
-                    let bin_str = x.iter().map(|&b| b.to_string()).collect::<String>();
-                    let bin_num = u64::from_str_radix(&bin_str, 2).unwrap();
-                    // let num = format!("0b{:064b}", bin_num);
-                    str_vec.push(BlockWord(Value::known(bin_num)));
-                }
-            }   
-            let expected_digest = Sha512::digest(&str.repeat(55));
-            let digest = OtherSha512::digest(table16_chip, layouter.namespace(|| "'abc' * 2"), &str_vec)?;
-            let mut s: Vec<u64> = Vec::new();
-            for i in 0..8 {
-                let temp = &expected_digest[8*i..8*i+8];
-                let mut string = String::from("0b");
-                for num in temp {
-                    string.push_str(&format!("{:08b}", num));
-                }
-                s.push(u64::from_str_radix(&string[2..], 2).unwrap());
-            }
-            for (idx, digest_word) in digest.0.iter().enumerate() {
-                digest_word.0.assert_if_known(|digest_word| {
-                    (*digest_word as u128 + IV[idx] as u128) as u64
-                        == s[idx] as u64
-                    });
-                }
-            Ok(())
+#[test]
+fn sha384_wrong_digest_test() {
+    // The SHA-512 digest truncated to six words is not the SHA-384 digest.
+    let message = b"abc".to_vec();
+    let instance: Vec<bn256::Fr> = digest_instance(&message)[..6].to_vec();
+    let circuit = TruncatedCircuit { message, bits: 384 };
+    assert!(mock_prover(&circuit, vec![instance]).verify().is_err());
+}
+
+#[test]
+fn sha512_224_wrong_half_word_test() {
+    // The untruncated last word in place of its high half must be rejected.
+    let message = b"abc".to_vec();
+    let digest = Sha512_224::digest(&message);
+    let mut instance: Vec<bn256::Fr> = truncated_instance(&digest);
+    let last = native::digest_with_iv(IV_512_224, &message)[3];
+    assert_eq!(bn256::Fr::from(last >> 32), instance[3]);
+    instance[3] = bn256::Fr::from(last);
+    let circuit = TruncatedCircuit { message, bits: 224 };
+    assert!(mock_prover(&circuit, vec![instance]).verify().is_err());
+}
+
+#[derive(Default)]
+struct Sha512TCircuit {
+    message: Vec<u8>,
+    t: usize,
+}
+
+impl<F: FieldExt> Circuit<F> for Sha512TCircuit {
+    type Config = (Table16Config<F>, Column<Instance>);
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            message: vec![],
+            t: self.t,
         }
     }
-    let circuit: MyCircuit = MyCircuit {};
-    let prover = match MockProver::<bn256::Fr>::run(19, &circuit, vec![]) {
-        Ok(prover) => prover,
-        Err(e) => panic!("{:?}", e),
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let instance = meta.instance_column();
+        meta.enable_equality(instance);
+        (Table16Chip::configure(meta), instance)
+    }
+
+    fn synthesize(
+        &self,
+        (config, instance): Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        Table16Chip::load(config.clone(), &mut layouter)?;
+        let table16_chip = Table16Chip::construct(config);
+
+        let bytes: Vec<_> = self.message.iter().copied().map(Value::known).collect();
+        let digest = Sha512T::digest_bytes(
+            table16_chip,
+            layouter.namespace(|| "message"),
+            self.t,
+            &bytes,
+        )?;
+        digest.expose_public(layouter.namespace(|| "expose digest"), instance, 0)
+    }
+}
+
+/// The SHA-512/t digest of `message` as public inputs: its whole words, then the
+/// truncated last word.
+fn sha512_t_instance<F: FieldExt>(message: &[u8], t: usize) -> Vec<F> {
+    let digest = native::digest_with_iv(native::sha512_t_iv(t), message);
+    let (words, bits) = (t / 64, t % 64);
+    digest[..words]
+        .iter()
+        .copied()
+        .chain((bits > 0).then(|| digest[words] >> (64 - bits)))
+        .map(F::from)
+        .collect()
+}
+
+fn sha512_t_test<F: FieldExt>() {
+    // SHA-512/160 ends on a half word, SHA-512/100 partway through one, and
+    // SHA-512/256 on a word boundary.
+    for (message, t) in [
+        (b"abc".to_vec(), 160),
+        (b"abc".to_vec(), 100),
+        (b"abc".to_vec(), 256),
+        ("1".repeat(200).into_bytes(), 160),
+    ] {
+        let instance = sha512_t_instance::<F>(&message, t);
+        let circuit = Sha512TCircuit { message, t };
+        mock_prover(&circuit, vec![instance]).assert_satisfied();
+    }
+}
+
+#[test]
+fn sha512_t_digest_test() {
+    sha512_t_test::<bn256::Fr>();
+    sha512_t_test::<pallas::Base>();
+}
+
+#[test]
+fn sha512_t_matches_sha2_test() {
+    // The generated IVs reproduce the SHA-512/224 and SHA-512/256 digests.
+    let message = b"abc";
+    let instance = sha512_t_instance::<bn256::Fr>(message, 224);
+    assert_eq!(instance, truncated_instance(&Sha512_224::digest(message)));
+    let instance = sha512_t_instance::<bn256::Fr>(message, 256);
+    assert_eq!(instance, truncated_instance(&Sha512_256::digest(message)));
+}
+
+#[test]
+fn sha512_t_wrong_digest_test() {
+    // Flipping the lowest of the 100 output bits must be rejected.
+    let message = b"abc".to_vec();
+    let mut instance = sha512_t_instance::<bn256::Fr>(&message, 100);
+    instance[1] += bn256::Fr::from(1);
+    let circuit = Sha512TCircuit { message, t: 100 };
+    assert!(mock_prover(&circuit, vec![instance]).verify().is_err());
+}
+
+/// Hashes the rest of a message from the midstate of its prefix, which is either
+/// public input or fixed in the circuit.
+#[derive(Default)]
+struct MidstateCircuit {
+    midstate: [u64; 8],
+    processed_len: usize,
+    suffix: Vec<u8>,
+    public: bool,
+}
+
+impl<F: FieldExt> Circuit<F> for MidstateCircuit {
+    type Config = (Table16Config<F>, Column<Advice>, Column<Instance>);
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let midstate = meta.advice_column();
+        meta.enable_equality(midstate);
+        let instance = meta.instance_column();
+        meta.enable_equality(instance);
+        (Table16Chip::configure(meta), midstate, instance)
+    }
+
+    fn synthesize(
+        &self,
+        (config, midstate_column, instance): Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        Table16Chip::load(config.clone(), &mut layouter)?;
+        let table16_chip = Table16Chip::construct(config);
+
+        // A public midstate takes the first eight rows of the instance column, and the
+        // digest the eight after it.
+        let (state_words, digest_row): (Vec<_>, _) = if self.public {
+            let words = layouter.assign_region(
+                || "midstate",
+                |mut region| {
+                    (0..8)
+                        .map(|i| {
+                            region.assign_advice_from_instance(
+                                || format!("midstate word {}", i),
+                                instance,
+                                i,
+                                midstate_column,
+                                i,
+                            )
+                        })
+                        .collect::<Result<Vec<_>, _>>()
+                },
+            )?;
+            (words.into_iter().map(MidstateWord::Assigned).collect(), 8)
+        } else {
+            (self.midstate.iter().copied().map(MidstateWord::Constant).collect(), 0)
+        };
+
+        let mut hasher = OtherSha512::from_midstate(
+            table16_chip,
+            layouter.namespace(|| "midstate"),
+            state_words.try_into().unwrap(),
+            self.processed_len,
+        )?;
+        let bytes: Vec<_> = self.suffix.iter().copied().map(Value::known).collect();
+        hasher.update_bytes(layouter.namespace(|| "suffix"), &bytes)?;
+        let digest = hasher.finalize(layouter.namespace(|| "finalize"))?;
+        digest.expose_public(layouter.namespace(|| "expose digest"), instance, digest_row)
+    }
+}
+
+fn midstate_test<F: FieldExt>() {
+    // The first block is hashed outside the circuit.
+    let message = "12".repeat(100).into_bytes();
+    let (prefix, suffix) = message.split_at(BLOCK_SIZE * 8);
+    let midstate = native::midstate(IV, prefix);
+
+    for public in [false, true] {
+        let circuit = MidstateCircuit {
+            midstate,
+            processed_len: prefix.len(),
+            suffix: suffix.to_vec(),
+            public,
+        };
+        let mut instance: Vec<F> = Vec::new();
+        if public {
+            instance.extend(midstate.iter().copied().map(F::from));
+        }
+        instance.extend(digest_instance::<F>(&message));
+        mock_prover(&circuit, vec![instance]).assert_satisfied();
+    }
+}
+
+#[test]
+fn sha512_midstate_test() {
+    midstate_test::<bn256::Fr>();
+    midstate_test::<pallas::Base>();
+}
+
+#[test]
+fn sha512_wrong_midstate_test() {
+    // The digest of the whole message must be rejected from a different midstate.
+    let message = "12".repeat(100).into_bytes();
+    let (prefix, suffix) = message.split_at(BLOCK_SIZE * 8);
+    let midstate = native::midstate(IV, prefix);
+    let circuit = MidstateCircuit {
+        midstate,
+        processed_len: prefix.len(),
+        suffix: suffix.to_vec(),
+        public: true,
     };
-    prover.assert_satisfied();
+    let mut instance: Vec<bn256::Fr> = midstate.iter().copied().map(bn256::Fr::from).collect();
+    instance[3] += bn256::Fr::from(1);
+    instance.extend(digest_instance::<bn256::Fr>(&message));
+    assert!(mock_prover(&circuit, vec![instance]).verify().is_err());
 }
 
+/// Feeds the gadgets input they cannot hash, checking that each is rejected with an
+/// error rather than a panic.
+#[derive(Default)]
+struct MalformedInputCircuit;
+
+impl<F: FieldExt> Circuit<F> for MalformedInputCircuit {
+    type Config = Table16Config<F>;
+    type FloorPlanner = SimpleFloorPlanner;
 
+    fn without_witnesses(&self) -> Self {
+        Self
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        Table16Chip::configure(meta)
+    }
 
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        Table16Chip::load(config.clone(), &mut layouter)?;
+        let table16_chip = Table16Chip::construct(config);
+
+        // Words cannot follow a partial word of bytes.
+        let mut hasher = OtherSha512::new(table16_chip.clone(), layouter.namespace(|| "init"))?;
+        hasher.update_bytes(layouter.namespace(|| "byte"), &[Value::known(0x61)])?;
+        let result = hasher.update(layouter.namespace(|| "word"), &message_words(b"12345678"));
+        assert!(matches!(result, Err(Sha512Error::MalformedInput(_))), "{:?}", result);
+
+        // A midstate is only taken between blocks.
+        let result = OtherSha512::from_midstate(
+            table16_chip.clone(),
+            layouter.namespace(|| "midstate"),
+            IV.map(MidstateWord::Constant),
+            100,
+        );
+        assert!(matches!(result, Err(Sha512Error::MalformedInput(_))));
+        let mut hasher = OtherSha512::new(table16_chip.clone(), layouter.namespace(|| "init"))?;
+        hasher.update_bytes(layouter.namespace(|| "bytes"), &[Value::known(0x61); 8])?;
+        let result = hasher.midstate(layouter.namespace(|| "partial midstate"));
+        assert!(matches!(result, Err(Sha512Error::MalformedInput(_))));
+
+        // A length too long for max_blocks leaves no block to end the message in.
+        let result = OtherSha512::digest_var_len(
+            table16_chip.clone(),
+            layouter.namespace(|| "var len"),
+            &[Value::known(0x61); 3],
+            Value::known(112),
+            1,
+        );
+        assert!(matches!(result, Err(Sha512Error::MalformedInput(_))));
+
+        // SHA-512/384 is not defined.
+        let result = Sha512T::new(table16_chip, layouter.namespace(|| "init"), 384);
+        assert!(matches!(result, Err(Sha512Error::MalformedInput(_))));
+
+        Ok(())
+    }
+}
+
+#[test]
+fn sha512_malformed_input_test() {
+    mock_prover::<bn256::Fr, _>(&MalformedInputCircuit, vec![]).assert_satisfied();
+}
@@ -0,0 +1,208 @@
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    dev::MockProver,
+    halo2curves::bn256,
+    plonk::{Advice, Circuit, Column, ConstraintSystem, Error, Instance},
+};
+use hex_literal::hex;
+use sha512_halo2::sha512::{
+    hkdf::{HkdfExpand, HkdfExtract},
+    hmac::HmacVariant,
+    ByteInput, DigestWord, Sha384Variant, Sha512FullVariant, Table16Chip, Table16Config,
+};
+
+struct HkdfCircuit<V> {
+    salt: Vec<u8>,
+    ikm: Vec<u8>,
+    info: Vec<u8>,
+    length: usize,
+    _marker: PhantomData<V>,
+}
+
+impl<V> HkdfCircuit<V> {
+    fn new(salt: &[u8], ikm: &[u8], info: &[u8], length: usize) -> Self {
+        HkdfCircuit {
+            salt: salt.to_vec(),
+            ikm: ikm.to_vec(),
+            info: info.to_vec(),
+            length,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<V: HmacVariant<DigestWord<bn256::Fr>>> Circuit<bn256::Fr> for HkdfCircuit<V> {
+    type Config = (Table16Config<bn256::Fr>, Column<Advice>, Column<Instance>);
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        // The output length and label shape the circuit.
+        HkdfCircuit {
+            salt: vec![0; self.salt.len()],
+            ikm: vec![0; self.ikm.len()],
+            info: self.info.clone(),
+            length: self.length,
+            _marker: PhantomData,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<bn256::Fr>) -> Self::Config {
+        let secret = meta.advice_column();
+        meta.enable_equality(secret);
+        let instance = meta.instance_column();
+        meta.enable_equality(instance);
+        (Table16Chip::configure(meta), secret, instance)
+    }
+
+    fn synthesize(
+        &self,
+        (config, secret_column, instance): Self::Config,
+        mut layouter: impl Layouter<bn256::Fr>,
+    ) -> Result<(), Error> {
+        Table16Chip::load(config.clone(), &mut layouter)?;
+        let table16_chip = Table16Chip::construct(config);
+
+        // Assign the salt and input keying material in a region of our own, as the
+        // gadget deriving them would.
+        let (salt, ikm) = layouter.assign_region(
+            || "secrets",
+            |mut region| {
+                let mut assign = |bytes: &[u8], offset: usize| {
+                    bytes
+                        .iter()
+                        .enumerate()
+                        .map(|(i, byte)| {
+                            region.assign_advice(
+                                || format!("byte {}", offset + i),
+                                secret_column,
+                                offset + i,
+                                || Value::known(bn256::Fr::from(*byte as u64)),
+                            )
+                        })
+                        .collect::<Result<Vec<_>, _>>()
+                };
+                let salt = assign(&self.salt, 0)?;
+                let ikm = assign(&self.ikm, self.salt.len())?;
+                Ok((salt, ikm))
+            },
+        )?;
+
+        let prk = HkdfExtract::<_, _, V>::extract(
+            table16_chip.clone(),
+            layouter.namespace(|| "extract"),
+            &salt,
+            &ikm,
+        )?;
+
+        // The label is public, so it is fixed in the circuit.
+        let info: Vec<_> = self.info.iter().copied().map(ByteInput::Padding).collect();
+        let okm = HkdfExpand::<_, _, V>::new(table16_chip, &prk).expand(
+            layouter.namespace(|| "expand"),
+            &info,
+            self.length,
+        )?;
+        okm.expose_public(layouter.namespace(|| "expose okm"), instance, 0)
+    }
+}
+
+fn okm_instance(okm: &[u8]) -> Vec<bn256::Fr> {
+    okm.chunks(8)
+        .map(|word| {
+            word.iter()
+                .fold(0u64, |acc, byte| (acc << 8) + *byte as u64)
+        })
+        .map(bn256::Fr::from)
+        .collect()
+}
+
+fn assert_okm<V: HmacVariant<DigestWord<bn256::Fr>>>(
+    salt: &[u8],
+    ikm: &[u8],
+    info: &[u8],
+    okm: &[u8],
+) {
+    let circuit = HkdfCircuit::<V>::new(salt, ikm, info, okm.len());
+    let prover = match MockProver::<bn256::Fr>::run(19, &circuit, vec![okm_instance(okm)]) {
+        Ok(prover) => prover,
+        Err(e) => panic!("{:?}", e),
+    };
+    prover.assert_satisfied();
+}
+
+// The inputs of the test vectors in RFC 5869 appendix A, with HMAC-SHA512 and
+// HMAC-SHA384 in place of HMAC-SHA256.
+
+#[test]
+fn hkdf_sha512_test_case_1() {
+    let salt: Vec<u8> = (0x00..=0x0c).collect();
+    let info: Vec<u8> = (0xf0..=0xf9).collect();
+    assert_okm::<Sha512FullVariant>(
+        &salt,
+        &[0x0b; 22],
+        &info,
+        &hex!(
+            "832390086cda71fb47625bb5ceb168e4c8e26a1a16ed34d9fc7fe92c1481579338da362cb8d9f925d7cb"
+        ),
+    );
+}
+
+#[test]
+fn hkdf_sha512_test_case_2() {
+    // Longer inputs, and an output spanning two digests.
+    let salt: Vec<u8> = (0x60..=0xaf).collect();
+    let ikm: Vec<u8> = (0x00..=0x4f).collect();
+    let info: Vec<u8> = (0xb0..=0xff).collect();
+    assert_okm::<Sha512FullVariant>(
+        &salt,
+        &ikm,
+        &info,
+        &hex!(
+            "ce6c97192805b346e6161e821ed165673b84f400a2b514b2fe23d84cd189ddf1b695b48cbd1c8388441137b3ce28f16aa64ba33ba466b24df6cfcb021ecff235f6a2056ce3af1de44d572097a8505d9e7a93"
+        ),
+    );
+}
+
+#[test]
+fn hkdf_sha512_test_case_3() {
+    // An empty salt and label.
+    assert_okm::<Sha512FullVariant>(
+        &[],
+        &[0x0b; 22],
+        &[],
+        &hex!(
+            "f5fa02b18298a72a8c23898a8703472c6eb179dc204c03425c970e3b164bf90fff22d04836d0e2343bac"
+        ),
+    );
+}
+
+#[test]
+fn hkdf_sha384_test_case_1() {
+    let salt: Vec<u8> = (0x00..=0x0c).collect();
+    let info: Vec<u8> = (0xf0..=0xf9).collect();
+    assert_okm::<Sha384Variant>(
+        &salt,
+        &[0x0b; 22],
+        &info,
+        &hex!(
+            "9b5097a86038b805309076a44b3a9f38063e25b516dcbf369f394cfab43685f748b6457763e4f0204fc5"
+        ),
+    );
+}
+
+#[test]
+fn hkdf_sha512_wrong_ikm_test() {
+    // The output of test case 1 from different input keying material must be rejected.
+    let salt: Vec<u8> = (0x00..=0x0c).collect();
+    let info: Vec<u8> = (0xf0..=0xf9).collect();
+    let okm = hex!(
+        "832390086cda71fb47625bb5ceb168e4c8e26a1a16ed34d9fc7fe92c1481579338da362cb8d9f925d7cb"
+    );
+    let circuit = HkdfCircuit::<Sha512FullVariant>::new(&salt, &[0x0c; 22], &info, okm.len());
+    let prover = match MockProver::<bn256::Fr>::run(19, &circuit, vec![okm_instance(&okm)]) {
+        Ok(prover) => prover,
+        Err(e) => panic!("{:?}", e),
+    };
+    assert!(prover.verify().is_err());
+}
@@ -0,0 +1,174 @@
+use halo2_proofs::{
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    dev::MockProver,
+    halo2curves::bn256,
+    plonk::{Advice, Circuit, Column, ConstraintSystem, Error, Instance},
+};
+use hex_literal::hex;
+use sha512_halo2::sha512::{hmac::HmacSha512, Table16Chip, Table16Config};
+
+#[derive(Default)]
+struct HmacCircuit {
+    key: Vec<u8>,
+    message: Vec<u8>,
+}
+
+impl Circuit<bn256::Fr> for HmacCircuit {
+    type Config = (Table16Config<bn256::Fr>, Column<Advice>, Column<Instance>);
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<bn256::Fr>) -> Self::Config {
+        let key = meta.advice_column();
+        meta.enable_equality(key);
+        let instance = meta.instance_column();
+        meta.enable_equality(instance);
+        (Table16Chip::configure(meta), key, instance)
+    }
+
+    fn synthesize(
+        &self,
+        (config, key_column, instance): Self::Config,
+        mut layouter: impl Layouter<bn256::Fr>,
+    ) -> Result<(), Error> {
+        Table16Chip::load(config.clone(), &mut layouter)?;
+        let table16_chip = Table16Chip::construct(config);
+
+        // Assign the key in a region of our own, as the gadget holding it would.
+        let key = layouter.assign_region(
+            || "key",
+            |mut region| {
+                self.key
+                    .iter()
+                    .enumerate()
+                    .map(|(i, byte)| {
+                        region.assign_advice(
+                            || format!("key byte {}", i),
+                            key_column,
+                            i,
+                            || Value::known(bn256::Fr::from(*byte as u64)),
+                        )
+                    })
+                    .collect::<Result<Vec<_>, _>>()
+            },
+        )?;
+
+        let message: Vec<_> = self.message.iter().copied().map(Value::known).collect();
+        let tag = HmacSha512::mac_bytes(
+            table16_chip,
+            layouter.namespace(|| "hmac"),
+            &key,
+            &message,
+        )?;
+        tag.expose_public(layouter.namespace(|| "expose tag"), instance, 0)
+    }
+}
+
+fn tag_instance(tag: &[u8]) -> Vec<bn256::Fr> {
+    tag.chunks(8)
+        .map(|word| bn256::Fr::from(u64::from_be_bytes(word.try_into().unwrap())))
+        .collect()
+}
+
+fn assert_tag(key: &[u8], message: &[u8], tag: &[u8]) {
+    let circuit = HmacCircuit {
+        key: key.to_vec(),
+        message: message.to_vec(),
+    };
+    let prover = match MockProver::<bn256::Fr>::run(19, &circuit, vec![tag_instance(tag)]) {
+        Ok(prover) => prover,
+        Err(e) => panic!("{:?}", e),
+    };
+    prover.assert_satisfied();
+}
+
+// Test vectors from RFC 4231 section 4.
+
+#[test]
+fn hmac_sha512_rfc4231_test_case_1() {
+    assert_tag(
+        &[0x0b; 20],
+        b"Hi There",
+        &hex!(
+            "87aa7cdea5ef619d4ff0b4241a1d6cb02379f4e2ce4ec2787ad0b30545e17cdedaa833b7d6b8a702038b274eaea3f4e4be9d914eeb61f1702e696c203a126854"
+        ),
+    );
+}
+
+#[test]
+fn hmac_sha512_rfc4231_test_case_2() {
+    assert_tag(
+        b"Jefe",
+        b"what do ya want for nothing?",
+        &hex!(
+            "164b7a7bfcf819e2e395fbe73b56e0a387bd64222e831fd610270cd7ea2505549758bf75c05a994a6d034f65f8f0e6fdcaeab1a34d4a6b4b636e070a38bce737"
+        ),
+    );
+}
+
+#[test]
+fn hmac_sha512_rfc4231_test_case_3() {
+    assert_tag(
+        &[0xaa; 20],
+        &[0xdd; 50],
+        &hex!(
+            "fa73b0089d56a284efb0f0756c890be9b1b5dbdd8ee81a3655f83e33b2279d39bf3e848279a722c806b485a47e67c807b946a337bee8942674278859e13292fb"
+        ),
+    );
+}
+
+#[test]
+fn hmac_sha512_rfc4231_test_case_4() {
+    let key: Vec<u8> = (0x01..=0x19).collect();
+    assert_tag(
+        &key,
+        &[0xcd; 50],
+        &hex!(
+            "b0ba465637458c6990e5a8c5f61d4af7e576d97ff94b872de76f8050361ee3dba91ca5c11aa25eb4d679275cc5788063a5f19741120c4f2de2adebeb10a298dd"
+        ),
+    );
+}
+
+#[test]
+fn hmac_sha512_rfc4231_test_case_6() {
+    // A key longer than a block is hashed first.
+    assert_tag(
+        &[0xaa; 131],
+        b"Test Using Larger Than Block-Size Key - Hash Key First",
+        &hex!(
+            "80b24263c7c1a3ebb71493c1dd7be8b49b46d1f41b4aeec1121b013783f8f3526b56d037e05f2598bd0fd2215d6a1e5295e64f73f63f0aec8b915a985d786598"
+        ),
+    );
+}
+
+#[test]
+fn hmac_sha512_rfc4231_test_case_7() {
+    assert_tag(
+        &[0xaa; 131],
+        b"This is a test using a larger than block-size key and a larger than block-size \
+          data. The key needs to be hashed before being used by the HMAC algorithm.",
+        &hex!(
+            "e37b6a775dc87dbaa4dfa9f96e5e3ffddebd71f8867289865df5a32d20cdc944b6022cac3c4982b10d5eeb55c3e4de15134676fb6de0446065c97440fa8c6a58"
+        ),
+    );
+}
+
+#[test]
+fn hmac_sha512_wrong_key_test() {
+    // The tag of RFC 4231 test case 2 under a different key must be rejected.
+    let circuit = HmacCircuit {
+        key: b"Jeff".to_vec(),
+        message: b"what do ya want for nothing?".to_vec(),
+    };
+    let tag = hex!(
+        "164b7a7bfcf819e2e395fbe73b56e0a387bd64222e831fd610270cd7ea2505549758bf75c05a994a6d034f65f8f0e6fdcaeab1a34d4a6b4b636e070a38bce737"
+    );
+    let prover = match MockProver::<bn256::Fr>::run(19, &circuit, vec![tag_instance(&tag)]) {
+        Ok(prover) => prover,
+        Err(e) => panic!("{:?}", e),
+    };
+    assert!(prover.verify().is_err());
+}
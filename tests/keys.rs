@@ -0,0 +1,129 @@
+use std::fs;
+
+use halo2_proofs::{
+    halo2curves::bn256::{Bn256, Fr, G1Affine},
+    plonk::{create_proof, verify_proof},
+    poly::{
+        commitment::ParamsProver,
+        kzg::{
+            commitment::KZGCommitmentScheme,
+            multiopen::{ProverSHPLONK, VerifierSHPLONK},
+            strategy::SingleStrategy,
+        },
+    },
+    transcript::{
+        Blake2bRead, Blake2bWrite, Challenge255, TranscriptReadBuffer, TranscriptWriterBuffer,
+    },
+};
+use rand::SeedableRng;
+use rand_xorshift::XorShiftRng;
+use sha512_halo2::sha512::{
+    chain::{split_message, Sha512ChunkCircuit},
+    keys::{fingerprint, read_vk, KeyCache},
+    preimage::{public_inputs, DigestLayout, Sha512PreimageCircuit},
+};
+
+/// The smallest `k` leaving room for a block beside the 2^16-row spread table.
+const K: u32 = 17;
+
+#[test]
+fn sha512_key_cache_test() {
+    let dir = std::path::Path::new(env!("CARGO_TARGET_TMPDIR")).join("key-cache-test");
+    // Start from an empty cache, so that the keys are generated and then read back.
+    let _ = fs::remove_dir_all(&dir);
+    let cache = KeyCache::new(&dir);
+    let rng = XorShiftRng::seed_from_u64(0);
+
+    let params = cache.params(K, rng.clone()).unwrap();
+    let circuit = Sha512PreimageCircuit::unknown(1);
+    let pk = cache.pk(&params, &circuit).unwrap();
+    assert_eq!(fs::read_dir(&dir).unwrap().count(), 3);
+
+    // Prove with the generated key, and verify with the parameters and verifying key
+    // read back from the cache.
+    let message = b"abc";
+    let instance: Vec<Fr> = public_inputs(message, DigestLayout::Words);
+    let mut transcript = Blake2bWrite::<_, G1Affine, Challenge255<_>>::init(vec![]);
+    create_proof::<
+        KZGCommitmentScheme<Bn256>,
+        ProverSHPLONK<'_, Bn256>,
+        Challenge255<G1Affine>,
+        XorShiftRng,
+        Blake2bWrite<Vec<u8>, G1Affine, Challenge255<G1Affine>>,
+        Sha512PreimageCircuit,
+    >(
+        &params,
+        &pk,
+        &[Sha512PreimageCircuit::new(message)],
+        &[&[&instance[..]]],
+        rng.clone(),
+        &mut transcript,
+    )
+    .unwrap();
+    let proof = transcript.finalize();
+
+    let params = cache.params(K, rng).unwrap();
+    let vk = cache.vk(&params, &circuit).unwrap();
+    assert_eq!(vk.transcript_repr(), pk.get_vk().transcript_repr());
+    let vk = read_vk::<Sha512PreimageCircuit>(&cache.vk_path(K, &circuit)).unwrap();
+
+    let mut transcript = Blake2bRead::<_, G1Affine, Challenge255<_>>::init(&proof[..]);
+    verify_proof::<
+        KZGCommitmentScheme<Bn256>,
+        VerifierSHPLONK<'_, Bn256>,
+        Challenge255<G1Affine>,
+        Blake2bRead<&[u8], G1Affine, Challenge255<G1Affine>>,
+        SingleStrategy<'_, Bn256>,
+    >(
+        params.verifier_params(),
+        &vk,
+        SingleStrategy::new(&params),
+        &[&[&instance[..]]],
+        &mut transcript,
+    )
+    .unwrap();
+}
+
+#[test]
+fn sha512_fingerprint_test() {
+    assert_eq!(
+        fingerprint::<Sha512PreimageCircuit>(),
+        fingerprint::<Sha512PreimageCircuit>()
+    );
+    // The chunk circuit configures an advice column more.
+    assert_ne!(
+        fingerprint::<Sha512PreimageCircuit>(),
+        fingerprint::<Sha512ChunkCircuit>()
+    );
+}
+
+#[test]
+fn sha512_key_cache_shape_test() {
+    let dir = std::path::Path::new(env!("CARGO_TARGET_TMPDIR")).join("key-cache-shape-test");
+    let _ = fs::remove_dir_all(&dir);
+    let cache = KeyCache::new(&dir);
+    let rng = XorShiftRng::seed_from_u64(0);
+
+    // The layout of the digest changes the keys of the preimage circuit, so each layout
+    // has its own entry.
+    let params = cache.params(K, rng).unwrap();
+    let words = Sha512PreimageCircuit::unknown(1);
+    let half_words = Sha512PreimageCircuit::unknown(1).with_layout(DigestLayout::HalfWords);
+    assert_ne!(cache.vk_path(K, &words), cache.vk_path(K, &half_words));
+    let words_vk = cache.vk(&params, &words).unwrap();
+    let half_words_vk = cache.vk(&params, &half_words).unwrap();
+    assert_ne!(words_vk.transcript_repr(), half_words_vk.transcript_repr());
+    assert_eq!(fs::read_dir(&dir).unwrap().count(), 3);
+
+    // So do the number of blocks, and the length of the message the last chunk of a
+    // chain pads. The chunks before it share an entry.
+    let blocks = Sha512PreimageCircuit::unknown(2);
+    assert_ne!(cache.vk_path(K, &words), cache.vk_path(K, &blocks));
+    let short = split_message(&[0; 300], 1).unwrap();
+    let long = split_message(&[0; 556], 1).unwrap();
+    assert_eq!(cache.vk_path(K, &short[0].0), cache.vk_path(K, &long[1].0));
+    assert_ne!(
+        cache.vk_path(K, &short.last().unwrap().0),
+        cache.vk_path(K, &long.last().unwrap().0)
+    );
+}
@@ -0,0 +1,158 @@
+use halo2_proofs::{
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    dev::MockProver,
+    halo2curves::bn256,
+    plonk::{Advice, Circuit, Column, ConstraintSystem, Error, Instance},
+};
+use hex_literal::hex;
+use sha512_halo2::sha512::{pbkdf2::Pbkdf2Sha512, ByteInput, Table16Chip, Table16Config};
+
+#[derive(Default)]
+struct Pbkdf2Circuit {
+    password: Vec<u8>,
+    salt: Vec<u8>,
+    iterations: usize,
+    blocks: usize,
+}
+
+impl Circuit<bn256::Fr> for Pbkdf2Circuit {
+    type Config = (Table16Config<bn256::Fr>, Column<Advice>, Column<Instance>);
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        // The salt and the iteration count shape the circuit.
+        Pbkdf2Circuit {
+            password: vec![0; self.password.len()],
+            salt: self.salt.clone(),
+            iterations: self.iterations,
+            blocks: self.blocks,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<bn256::Fr>) -> Self::Config {
+        let password = meta.advice_column();
+        meta.enable_equality(password);
+        let instance = meta.instance_column();
+        meta.enable_equality(instance);
+        (Table16Chip::configure(meta), password, instance)
+    }
+
+    fn synthesize(
+        &self,
+        (config, password_column, instance): Self::Config,
+        mut layouter: impl Layouter<bn256::Fr>,
+    ) -> Result<(), Error> {
+        Table16Chip::load(config.clone(), &mut layouter)?;
+        let table16_chip = Table16Chip::construct(config);
+
+        // Assign the password in a region of our own, as the gadget holding it would.
+        let password = layouter.assign_region(
+            || "password",
+            |mut region| {
+                self.password
+                    .iter()
+                    .enumerate()
+                    .map(|(i, byte)| {
+                        region.assign_advice(
+                            || format!("password byte {}", i),
+                            password_column,
+                            i,
+                            || Value::known(bn256::Fr::from(*byte as u64)),
+                        )
+                    })
+                    .collect::<Result<Vec<_>, _>>()
+            },
+        )?;
+
+        // The salt is public, so it is fixed in the circuit.
+        let salt: Vec<_> = self.salt.iter().copied().map(ByteInput::Padding).collect();
+        let key = Pbkdf2Sha512::derive_key(
+            table16_chip,
+            layouter.namespace(|| "pbkdf2"),
+            &password,
+            &salt,
+            self.iterations,
+            self.blocks,
+        )?;
+        for (i, word) in key.iter().enumerate() {
+            layouter.constrain_instance(word.cell(), instance, i)?;
+        }
+        Ok(())
+    }
+}
+
+fn key_instance(key: &[u8]) -> Vec<bn256::Fr> {
+    key.chunks(8)
+        .map(|word| bn256::Fr::from(u64::from_be_bytes(word.try_into().unwrap())))
+        .collect()
+}
+
+fn pbkdf2_circuit(password: &[u8], salt: &[u8], iterations: usize, key: &[u8]) -> Pbkdf2Circuit {
+    Pbkdf2Circuit {
+        password: password.to_vec(),
+        salt: salt.to_vec(),
+        iterations,
+        blocks: key.len() / 64,
+    }
+}
+
+fn assert_key(password: &[u8], salt: &[u8], iterations: usize, key: &[u8]) {
+    let circuit = pbkdf2_circuit(password, salt, iterations, key);
+    let prover = match MockProver::<bn256::Fr>::run(19, &circuit, vec![key_instance(key)]) {
+        Ok(prover) => prover,
+        Err(e) => panic!("{:?}", e),
+    };
+    prover.assert_satisfied();
+}
+
+// The inputs of the test vectors in RFC 6070, with HMAC-SHA512 in place of HMAC-SHA1.
+
+#[test]
+fn pbkdf2_sha512_one_iteration_test() {
+    assert_key(
+        b"password",
+        b"salt",
+        1,
+        &hex!(
+            "867f70cf1ade02cff3752599a3a53dc4af34c7a669815ae5d513554e1c8cf252c02d470a285a0501bad999bfe943c08f050235d7d68b1da55e63f73b60a57fce"
+        ),
+    );
+}
+
+#[test]
+fn pbkdf2_sha512_two_iterations_test() {
+    assert_key(
+        b"password",
+        b"salt",
+        2,
+        &hex!(
+            "e1d9c16aa681708a45f5c7c4e215ceb66e011a2e9f0040713f18aefdb866d53cf76cab2868a39b9f7840edce4fef5a82be67335c77a6068e04112754f27ccf4e"
+        ),
+    );
+}
+
+#[test]
+fn pbkdf2_sha512_two_blocks_test() {
+    assert_key(
+        b"passwordPASSWORDpassword",
+        b"saltSALTsaltSALTsaltSALTsaltSALTsalt",
+        3,
+        &hex!(
+            "e3ad582d92516a866ef6a2725080fbee6f7cd51734047789cccdae6581e79529601c42bf26261838b697a3a819e36dab84f1987867fc40a605429d6c540e3cb223551306ab87c412d04ce40f3def06757fe3789fdcf8e2ad8e4343427a94fe8224aa48bbc9eb3039c3a2fe2508193d1cce542f28ad01b2515ed32c60426200e1"
+        ),
+    );
+}
+
+#[test]
+fn pbkdf2_sha512_wrong_password_test() {
+    // The key derived from "password" must be rejected for a different password.
+    let key = hex!(
+        "e1d9c16aa681708a45f5c7c4e215ceb66e011a2e9f0040713f18aefdb866d53cf76cab2868a39b9f7840edce4fef5a82be67335c77a6068e04112754f27ccf4e"
+    );
+    let circuit = pbkdf2_circuit(b"passw0rd", b"salt", 2, &key);
+    let prover = match MockProver::<bn256::Fr>::run(19, &circuit, vec![key_instance(&key)]) {
+        Ok(prover) => prover,
+        Err(e) => panic!("{:?}", e),
+    };
+    assert!(prover.verify().is_err());
+}
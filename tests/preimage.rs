@@ -0,0 +1,86 @@
+mod common;
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    halo2curves::{bn256, pasta::pallas},
+};
+use sha2::{Digest, Sha512};
+use sha512_halo2::sha512::{
+    preimage::{public_inputs, DigestLayout, PreimageDigest, Sha512PreimageCircuit},
+    Sha512Error,
+};
+
+use common::mock_prover;
+
+fn preimage_test<F: FieldExt>(layout: DigestLayout) {
+    // The same two-block circuit proves messages ending in either block.
+    for len in [0, 3, 111, 112, 239] {
+        let message = "1".repeat(len).into_bytes();
+        let circuit = Sha512PreimageCircuit::with_max_blocks(&message, 2)
+            .unwrap()
+            .with_layout(layout);
+        let instance = public_inputs::<F>(&message, layout);
+        assert_eq!(instance.len(), layout.rows() + 1);
+        mock_prover(&circuit, vec![instance]).assert_satisfied();
+    }
+}
+
+#[test]
+fn sha512_preimage_test() {
+    preimage_test::<bn256::Fr>(DigestLayout::Words);
+    preimage_test::<pallas::Base>(DigestLayout::Words);
+    preimage_test::<bn256::Fr>(DigestLayout::HalfWords);
+}
+
+#[test]
+fn sha512_preimage_public_inputs_test() {
+    let message = b"abc";
+    let digest = Sha512::digest(message);
+
+    let words: Vec<bn256::Fr> = digest
+        .chunks(8)
+        .map(|word| bn256::Fr::from(u64::from_be_bytes(word.try_into().unwrap())))
+        .chain(Some(bn256::Fr::from(3)))
+        .collect();
+    assert_eq!(public_inputs(message, DigestLayout::Words), words);
+
+    let halves: Vec<bn256::Fr> = digest
+        .chunks(4)
+        .map(|half| bn256::Fr::from(u32::from_be_bytes(half.try_into().unwrap()) as u64))
+        .chain(Some(bn256::Fr::from(3)))
+        .collect();
+    assert_eq!(public_inputs(message, DigestLayout::HalfWords), halves);
+}
+
+#[test]
+fn sha512_preimage_new_test() {
+    // Without a bound, the circuit spans the blocks of the padded message.
+    let message = "abc".repeat(50).into_bytes();
+    let circuit = Sha512PreimageCircuit::new(&message);
+    assert_eq!(circuit.max_blocks(), 2);
+    assert_eq!(circuit.layout(), DigestLayout::Words);
+    let instance = public_inputs::<pallas::Base>(&message, DigestLayout::Words);
+    mock_prover(&circuit, vec![instance]).assert_satisfied();
+
+    assert!(matches!(
+        Sha512PreimageCircuit::with_max_blocks(&message, 1),
+        Err(Sha512Error::MalformedInput(_))
+    ));
+}
+
+#[test]
+fn sha512_preimage_wrong_digest_test() {
+    // Neither a digest of another message nor another length may verify.
+    let message = b"abc";
+    let circuit = Sha512PreimageCircuit::new(message).with_layout(DigestLayout::HalfWords);
+
+    let mut other = PreimageDigest::of(message);
+    other.digest[7] ^= 1;
+    let instance = other.instance::<bn256::Fr>(DigestLayout::HalfWords);
+    assert!(mock_prover(&circuit, vec![instance]).verify().is_err());
+
+    let mut other = PreimageDigest::of(message);
+    other.length = 2;
+    let instance = other.instance::<bn256::Fr>(DigestLayout::HalfWords);
+    assert!(mock_prover(&circuit, vec![instance]).verify().is_err());
+}